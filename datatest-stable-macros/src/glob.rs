@@ -0,0 +1,97 @@
+// Copyright (c) The datatest-stable Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Glob translation, for checking `glob` literals.
+//!
+//! A proc-macro crate can only export macros, so this is a copy of
+//! `glob_to_regex` in `datatest-stable`'s `src/pattern.rs`. Keep the two in
+//! sync.
+
+/// The following syntax is supported:
+///
+/// * `*` matches any sequence of characters other than `/`.
+/// * `?` matches any single character other than `/`.
+/// * `**` matches any sequence of characters including `/`. At the start of a
+///   path component, `**/` also matches zero components.
+/// * `[abc]`, `[a-z]` and `[!abc]` match a single character in (or not in) a set.
+/// * `{a,b}` matches either of the alternatives, which may themselves contain
+///   glob syntax. If `alternation` is false, braces and commas are literal
+///   characters, as in `.gitignore` files.
+/// * `\` escapes the following character.
+pub(crate) fn glob_to_regex(glob: &str, alternation: bool) -> Result<String, String> {
+    let mut regex = String::from("^");
+    let mut chars = glob.chars().peekable();
+    // The number of currently open `{` alternations.
+    let mut depth = 0;
+    // Whether the previous character ended a path component, or started the
+    // glob.
+    let mut at_component_start = true;
+
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                if at_component_start && chars.next_if_eq(&'/').is_some() {
+                    // A `**/` component matches zero or more components.
+                    regex.push_str("(?:[^/]*/)*");
+                    continue;
+                }
+                regex.push_str(".*");
+            }
+            '*' => regex.push_str("[^/]*"),
+            '?' => regex.push_str("[^/]"),
+            '[' => {
+                regex.push('[');
+                if chars.next_if_eq(&'!').is_some() {
+                    regex.push('^');
+                }
+                // A `]` right after the opening bracket is part of the set.
+                if chars.next_if_eq(&']').is_some() {
+                    regex.push_str("\\]");
+                }
+                loop {
+                    match chars.next() {
+                        Some(']') => break,
+                        Some('\\') => match chars.next() {
+                            Some(c) => push_escaped(&mut regex, c),
+                            None => return Err("trailing `\\`".to_owned()),
+                        },
+                        Some(c @ ('[' | '&' | '~' | '^')) => push_escaped(&mut regex, c),
+                        Some(c) => regex.push(c),
+                        None => return Err("unclosed `[`".to_owned()),
+                    }
+                }
+                regex.push(']');
+            }
+            '{' if alternation => {
+                depth += 1;
+                regex.push_str("(?:");
+            }
+            ',' if depth > 0 => regex.push('|'),
+            '}' if depth > 0 => {
+                depth -= 1;
+                regex.push(')');
+            }
+            '}' if alternation => return Err("unmatched `}`".to_owned()),
+            '\\' => match chars.next() {
+                Some(c) => push_escaped(&mut regex, c),
+                None => return Err("trailing `\\`".to_owned()),
+            },
+            c => push_escaped(&mut regex, c),
+        }
+        at_component_start = c == '/' || (alternation && matches!(c, '{' | ','));
+    }
+
+    if depth > 0 {
+        return Err("unclosed `{`".to_owned());
+    }
+    regex.push('$');
+    Ok(regex)
+}
+
+fn push_escaped(regex: &mut String, c: char) {
+    if "\\.+*?()|[]{}^$#&-~".contains(c) {
+        regex.push('\\');
+    }
+    regex.push(c);
+}
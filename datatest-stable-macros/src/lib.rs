@@ -0,0 +1,351 @@
+// Copyright (c) The datatest-stable Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+#![forbid(unsafe_code)]
+
+//! Procedural macros for [`datatest-stable`](https://crates.io/crates/datatest-stable).
+//!
+//! This crate is an implementation detail of `datatest-stable`. Enable its
+//! `macros` feature, and use `#[datatest_stable::files(...)]` instead of
+//! depending on this crate directly.
+
+mod glob;
+
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::{quote, ToTokens};
+use syn::{
+    parse::Parser, punctuated::Punctuated, Expr, ExprArray, ExprLit, ExprTuple, Ident, ItemFn, Lit,
+    MetaNameValue, Token,
+};
+
+/// Declares a group of data-driven tests that run the function this is
+/// attached to.
+///
+/// The arguments are the same as for one `{ test = ..., root = ..., ... }`
+/// entry in `harness!`, except that `test` is the function itself, and that
+/// arguments can be given in any order:
+///
+/// ```rust,ignore
+/// #[datatest_stable::files(root = "tests/files", pattern = r"\.txt$")]
+/// fn my_test(path: &Utf8Path, contents: String) -> datatest_stable::Result<()> {
+///     // ... write test here
+///     Ok(())
+/// }
+///
+/// datatest_stable::harness! {}
+/// ```
+///
+/// Groups declared this way are collected when the harness runs, along with
+/// any groups passed to `harness!`. The name of each group is the path to the
+/// function from the crate root, such as `parser::my_test`, unless `name` is
+/// specified.
+///
+/// The generated code refers to `::datatest_stable`. If the crate is only
+/// available under another path, such as a re-export, pass that path as
+/// `crate`, as in `crate = my_crate::datatest_stable`.
+///
+/// Regexes passed to `pattern` and `exclude`, and globs passed to `glob`, are
+/// checked when the attribute is expanded if they're string literals.
+#[proc_macro_attribute]
+pub fn files(args: TokenStream, item: TokenStream) -> TokenStream {
+    let item = syn::parse_macro_input!(item as ItemFn);
+    let args = match Punctuated::<MetaNameValue, Token![,]>::parse_terminated.parse(args) {
+        Ok(args) => args,
+        Err(error) => return with_error(&item, error),
+    };
+
+    match expand(args, &item) {
+        Ok(tokens) => tokens.into(),
+        Err(error) => with_error(&item, error),
+    }
+}
+
+/// Checks a regex or glob passed to `harness!` as `pattern`, `glob` or
+/// `exclude`.
+///
+/// Not part of the public API. The input is `regex` or `glob`, a comma, then
+/// the value. String literals that aren't valid are reported as errors
+/// pointing at the literal; anything else expands to nothing.
+#[doc(hidden)]
+#[proc_macro]
+pub fn check_pattern_literal(input: TokenStream) -> TokenStream {
+    let parser = |input: syn::parse::ParseStream<'_>| {
+        let kind: Ident = input.parse()?;
+        input.parse::<Token![,]>()?;
+        let expr: Expr = input.parse()?;
+        match kind.to_string().as_str() {
+            "regex" => check_regex(&expr),
+            "glob" => check_glob(&expr),
+            _ => Err(syn::Error::new(kind.span(), "expected `regex` or `glob`")),
+        }
+    };
+    match parser.parse(input) {
+        Ok(()) => TokenStream::new(),
+        Err(error) => error.to_compile_error().into(),
+    }
+}
+
+/// Emits the function unchanged along with an error, so that the error isn't
+/// followed by others about the function being missing.
+fn with_error(item: &ItemFn, error: syn::Error) -> TokenStream {
+    let error = error.to_compile_error();
+    quote!(#item #error).into()
+}
+
+fn expand(args: Punctuated<MetaNameValue, Token![,]>, item: &ItemFn) -> syn::Result<TokenStream2> {
+    // The path to `datatest_stable` is needed to expand the other arguments,
+    // so it's looked up first.
+    let krate = match args.iter().find(|arg| arg.path.is_ident("crate")) {
+        Some(arg) => match &arg.value {
+            Expr::Path(path) if path.qself.is_none() => path.path.clone(),
+            value => {
+                return Err(syn::Error::new_spanned(
+                    value,
+                    "expected a path to `datatest_stable`, such as `::datatest_stable`",
+                ))
+            }
+        },
+        None => syn::parse_quote!(::datatest_stable),
+    };
+    let mut seen: Vec<Ident> = Vec::new();
+    let mut root = None;
+    let mut options = Vec::new();
+
+    for arg in args {
+        let key = arg
+            .path
+            .get_ident()
+            .ok_or_else(|| syn::Error::new_spanned(&arg.path, "expected an argument name"))?
+            .clone();
+        if seen.contains(&key) {
+            return Err(syn::Error::new(
+                key.span(),
+                format!("`{key}` was specified more than once"),
+            ));
+        }
+        let value = arg.value;
+
+        let option = match key.to_string().as_str() {
+            "crate" => None,
+            "root" => {
+                root = Some(value);
+                None
+            }
+            "pattern" | "glob" => {
+                let other = if key == "pattern" { "glob" } else { "pattern" };
+                if let Some(other) = seen.iter().find(|seen| *seen == other) {
+                    return Err(syn::Error::new(
+                        key.span(),
+                        format!(
+                            "`{key}` can't be combined with `{other}` -- specify only one of them"
+                        ),
+                    ));
+                }
+                if key == "pattern" {
+                    check_regex(&value)?;
+                } else {
+                    check_glob(&value)?;
+                }
+                Some(quote!(.#key((#value).to_string())))
+            }
+            "exclude" => match &value {
+                Expr::Array(ExprArray { elems, .. }) => {
+                    for elem in elems {
+                        check_regex(elem)?;
+                    }
+                    let elems = elems.iter();
+                    Some(quote!(#(.exclude((#elems).to_string()))*))
+                }
+                _ => {
+                    check_regex(&value)?;
+                    Some(quote!(.exclude((#value).to_string())))
+                }
+            },
+            "expected" => Some(quote!(.expected((#value).to_string()))),
+            "format" => {
+                let format = variant(&value, &["json", "toml", "yaml"], "format")?;
+                Some(quote!(.format(#krate::Format::#format)))
+            }
+            "unit" => {
+                let unit = variant(&value, &["file", "dir"], "unit")?;
+                Some(quote!(.unit(#krate::Unit::#unit)))
+            }
+            "on_missing" => {
+                let on_missing = match variant(&value, &["fail", "skip", "none"], "policy")? {
+                    ident if ident == "None" => Ident::new("PassNone", ident.span()),
+                    ident => ident,
+                };
+                Some(quote!(.on_missing(#krate::OnMissing::#on_missing)))
+            }
+            "case_name" => match &value {
+                Expr::Tuple(ExprTuple { elems, .. }) if elems.len() == 2 => {
+                    let (to_name, from_name) = (&elems[0], &elems[1]);
+                    Some(quote!(.case_name_fn(#to_name, #from_name)))
+                }
+                _ => Some(quote!(.case_name(#value))),
+            },
+            "hidden" | "gitignore" | "sections" | "directives" | "name" | "strip_prefix"
+            | "root_env" | "optional" | "allow_empty" => Some(quote!(.#key(#value))),
+            "test" => {
+                return Err(syn::Error::new(
+                    key.span(),
+                    "`test` can't be specified -- the test function is the one this attribute \
+                     is attached to",
+                ))
+            }
+            _ => {
+                return Err(syn::Error::new(
+                    key.span(),
+                    format!("unexpected argument `{key}`"),
+                ))
+            }
+        };
+
+        options.extend(option);
+        seen.push(key);
+    }
+
+    let root = root.ok_or_else(|| syn::Error::new(Span::call_site(), "expected `root`"))?;
+    let ident = &item.sig.ident;
+    let name = ident.to_string();
+    let new_group = match &root {
+        Expr::Array(ExprArray { elems, .. }) => {
+            if elems.is_empty() {
+                return Err(syn::Error::new_spanned(
+                    &root,
+                    "`root` must contain at least one source",
+                ));
+            }
+            let roots = elems.iter().map(labelled_root);
+            quote! {
+                #krate::TestGroup::with_roots(
+                    #krate::default_group_name(::std::module_path!(), #name),
+                    #krate::test_fn!(#ident),
+                    [#(#roots),*],
+                )
+            }
+        }
+        _ => quote! {
+            #krate::TestGroup::new(
+                #krate::default_group_name(::std::module_path!(), #name),
+                #krate::test_fn!(#ident),
+                (#root).resolve_data_source(),
+            )
+        },
+    };
+
+    Ok(quote! {
+        #item
+
+        const _: () = {
+            fn __datatest_group() -> #krate::TestGroup {
+                use #krate::data_source_kinds::*;
+
+                #new_group
+                #(#options)*
+            }
+
+            #krate::inventory::submit! {
+                #krate::Registration::new(__datatest_group)
+            }
+        };
+    })
+}
+
+/// Pairs a root in a list with its label: either the one it was given, as in
+/// `label = source`, or the name of the directory or archive.
+fn labelled_root(elem: &Expr) -> TokenStream2 {
+    if let Expr::Assign(assign) = elem {
+        if let Expr::Path(path) = &*assign.left {
+            if let Some(label) = path.path.get_ident() {
+                let label = label.to_string();
+                let source = &assign.right;
+                return quote!((#label.to_owned(), (#source).resolve_data_source()));
+            }
+        }
+    }
+    quote!({
+        let root = (#elem).resolve_data_source();
+        (root.default_label(), root)
+    })
+}
+
+/// Checks a regex if it's a string literal, reporting errors at the literal.
+fn check_regex(expr: &Expr) -> syn::Result<()> {
+    match expr {
+        // Literals forwarded through `macro_rules!` arrive wrapped in an
+        // invisible group.
+        Expr::Group(group) => check_regex(&group.expr),
+        Expr::Lit(ExprLit {
+            lit: Lit::Str(lit), ..
+        }) => {
+            let value = lit.value();
+            match fancy_regex::Regex::new(&value) {
+                Ok(_) => Ok(()),
+                Err(error) if value.starts_with('*') => Err(syn::Error::new(
+                    lit.span(),
+                    format!(
+                        "invalid regular expression: {error} (patterns are regexes, not globs: \
+                         use `.*` to match anything, or `glob` instead of `pattern`)"
+                    ),
+                )),
+                Err(error) => Err(syn::Error::new(
+                    lit.span(),
+                    format!("invalid regular expression: {error}"),
+                )),
+            }
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Checks a glob if it's a string literal, reporting errors at the literal.
+fn check_glob(expr: &Expr) -> syn::Result<()> {
+    match expr {
+        Expr::Group(group) => check_glob(&group.expr),
+        Expr::Lit(ExprLit {
+            lit: Lit::Str(lit), ..
+        }) => glob::glob_to_regex(&lit.value(), true)
+            .and_then(|regex| {
+                fancy_regex::Regex::new(&regex)
+                    .map(|_| ())
+                    .map_err(|error| error.to_string())
+            })
+            .map_err(|error| syn::Error::new(lit.span(), format!("invalid glob: {error}"))),
+        _ => Ok(()),
+    }
+}
+
+/// Parses an identifier naming one of `expected`, and returns the name of the
+/// corresponding enum variant.
+fn variant(expr: &Expr, expected: &[&str], what: &str) -> syn::Result<Ident> {
+    let ident = match expr {
+        Expr::Path(path) => path.path.get_ident(),
+        _ => None,
+    };
+    match ident {
+        Some(ident) if expected.iter().any(|expected| ident == expected) => {
+            let name = ident.to_string();
+            let variant = name[..1].to_uppercase() + &name[1..];
+            Ok(Ident::new(&variant, ident.span()))
+        }
+        _ => {
+            let mut list = String::new();
+            for (ix, name) in expected.iter().enumerate() {
+                match ix {
+                    0 => {}
+                    _ if expected.len() == 2 => list.push_str(" or "),
+                    _ if ix == expected.len() - 1 => list.push_str(", or "),
+                    _ => list.push_str(", "),
+                }
+                list.push_str(&format!("`{name}`"));
+            }
+            let expr = expr.to_token_stream();
+            Err(syn::Error::new_spanned(
+                &expr,
+                format!("unknown {what} `{expr}` (expected {list})"),
+            ))
+        }
+    }
+}
@@ -0,0 +1,307 @@
+// Copyright (c) The datatest-stable Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Reading fixtures from tar and zip archives.
+
+use camino::{Utf8Component, Utf8Path, Utf8PathBuf};
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{self, BufReader, Read},
+    sync::{Arc, OnceLock},
+};
+
+/// The members of an archive that are files, keyed by their paths.
+pub(crate) type Members = BTreeMap<Utf8PathBuf, Arc<[u8]>>;
+
+/// An archive on disk whose members are test fixtures.
+///
+/// This is passed as the `root` of a test group, and its members are treated
+/// like the files in a directory. As with `include_dir` sources, test functions
+/// must accept the contents of fixtures as an argument, and are passed paths
+/// relative to the root of the archive.
+///
+/// The format is determined by the extension of the archive:
+///
+/// * `.tar`, `.tar.gz` and `.tgz` archives require the `archive-tar` feature.
+/// * `.zip` archives require the `archive-zip` feature.
+///
+/// The archive is read into memory the first time its members are listed. To
+/// look up a single test by name, as nextest does, only that member is read.
+///
+/// See the [crate documentation](crate#reading-fixtures-from-archives) for an
+/// example.
+#[derive(Clone, Debug)]
+pub struct Archive {
+    inner: Arc<Inner>,
+}
+
+#[derive(Debug)]
+struct Inner {
+    path: Utf8PathBuf,
+    format: ArchiveFormat,
+    members: OnceLock<Members>,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum ArchiveFormat {
+    Tar,
+    TarGz,
+    Zip,
+}
+
+impl ArchiveFormat {
+    /// Determines the format from the extension of `path`, if it's one of the
+    /// supported formats.
+    fn from_path(path: &Utf8Path) -> Option<Self> {
+        let file_name = path.file_name().unwrap_or_default().to_ascii_lowercase();
+        if file_name.ends_with(".tar") {
+            Some(ArchiveFormat::Tar)
+        } else if file_name.ends_with(".tar.gz") || file_name.ends_with(".tgz") {
+            Some(ArchiveFormat::TarGz)
+        } else if file_name.ends_with(".zip") {
+            Some(ArchiveFormat::Zip)
+        } else {
+            None
+        }
+    }
+}
+
+impl Archive {
+    /// Returns true if `path` has the extension of one of the supported
+    /// formats, whether or not its feature is enabled.
+    pub(crate) fn has_archive_extension(path: &Utf8Path) -> bool {
+        ArchiveFormat::from_path(path).is_some()
+    }
+
+    /// Creates a data source for the archive at `path`.
+    ///
+    /// As with directories, relative paths are relative to the crate root
+    /// while running tests. The archive isn't opened until tests are listed
+    /// or run.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the extension of `path` isn't one of the supported formats,
+    /// or if the feature for its format isn't enabled.
+    pub fn new(path: impl AsRef<Utf8Path>) -> Self {
+        let path = path.as_ref();
+        let format = ArchiveFormat::from_path(path).unwrap_or_else(|| {
+            panic!(
+                "unknown archive format for '{path}' (expected a `.tar`, `.tar.gz`, `.tgz`, \
+                 or `.zip` file)"
+            )
+        });
+
+        let (feature, enabled) = match format {
+            ArchiveFormat::Tar | ArchiveFormat::TarGz => {
+                ("archive-tar", cfg!(feature = "archive-tar"))
+            }
+            ArchiveFormat::Zip => ("archive-zip", cfg!(feature = "archive-zip")),
+        };
+        if !enabled {
+            panic!("reading '{path}' requires the `{feature}` feature of datatest-stable");
+        }
+
+        Self {
+            inner: Arc::new(Inner {
+                path: path.to_owned(),
+                format,
+                members: OnceLock::new(),
+            }),
+        }
+    }
+
+    /// Returns the path to the archive.
+    pub fn path(&self) -> &Utf8Path {
+        &self.inner.path
+    }
+
+    /// Returns true if `self` and `other` are the same archive on disk.
+    pub(crate) fn same_as(&self, other: &Archive) -> bool {
+        self.inner.path == other.inner.path
+    }
+
+    /// Returns all members of the archive that are files, reading the archive
+    /// if it hasn't been read yet.
+    pub(crate) fn members(&self) -> io::Result<&Members> {
+        if let Some(members) = self.inner.members.get() {
+            return Ok(members);
+        }
+
+        let mut members = Members::new();
+        self.visit(&mut |path, reader| {
+            let mut contents = Vec::new();
+            reader.read_to_end(&mut contents)?;
+            members.insert(path, contents.into());
+            Ok(true)
+        })?;
+        // Another thread may have read the archive in the meantime, in which
+        // case its members are used instead.
+        Ok(self.inner.members.get_or_init(|| members))
+    }
+
+    /// Returns the contents of the member at `rel_path`, or `None` if there's
+    /// no such file in the archive.
+    ///
+    /// If the archive hasn't been read yet, only this member is read.
+    pub(crate) fn member(&self, rel_path: &Utf8Path) -> io::Result<Option<Arc<[u8]>>> {
+        if let Some(members) = self.inner.members.get() {
+            return Ok(members.get(rel_path).cloned());
+        }
+
+        let mut found = None;
+        self.visit(&mut |path, reader| {
+            if path != rel_path {
+                return Ok(true);
+            }
+            let mut contents = Vec::new();
+            reader.read_to_end(&mut contents)?;
+            found = Some(contents.into());
+            Ok(false)
+        })?;
+        Ok(found)
+    }
+
+    /// Calls `visit` with the path and contents of each file in the archive,
+    /// until it returns false.
+    fn visit(&self, visit: &mut VisitFn<'_>) -> io::Result<()> {
+        let path = &self.inner.path;
+        let describe = |error: io::Error| {
+            io::Error::new(
+                error.kind(),
+                format!("error reading archive '{path}': {error}"),
+            )
+        };
+
+        let file = BufReader::new(File::open(path).map_err(describe)?);
+        match self.inner.format {
+            #[cfg(feature = "archive-tar")]
+            ArchiveFormat::Tar => visit_tar(file, visit),
+            #[cfg(feature = "archive-tar")]
+            ArchiveFormat::TarGz => visit_tar(flate2::read::GzDecoder::new(file), visit),
+            #[cfg(feature = "archive-zip")]
+            ArchiveFormat::Zip => visit_zip(file, visit),
+            #[allow(unreachable_patterns)]
+            _ => unreachable!("the feature for the archive format was checked in the constructor"),
+        }
+        .map_err(describe)
+    }
+}
+
+/// Called with the path and contents of a member, and returns whether to
+/// continue to the next member.
+type VisitFn<'a> = dyn FnMut(Utf8PathBuf, &mut dyn Read) -> io::Result<bool> + 'a;
+
+#[cfg(feature = "archive-tar")]
+fn visit_tar(reader: impl Read, visit: &mut VisitFn<'_>) -> io::Result<()> {
+    let mut archive = tar::Archive::new(reader);
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let path = {
+            let path = entry.path()?;
+            let utf8_path = Utf8Path::from_path(&path).ok_or_else(|| {
+                invalid_data(format!("member '{}' has a non-UTF-8 path", path.display()))
+            })?;
+            member_path(utf8_path)?
+        };
+        if !visit(path, &mut entry)? {
+            break;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(feature = "archive-zip")]
+fn visit_zip(reader: impl Read + io::Seek, visit: &mut VisitFn<'_>) -> io::Result<()> {
+    let mut archive = zip::ZipArchive::new(reader)?;
+    for index in 0..archive.len() {
+        let mut file = archive.by_index(index)?;
+        if file.is_dir() {
+            continue;
+        }
+        let path = member_path(Utf8Path::new(file.name()))?;
+        if !visit(path, &mut file)? {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Normalizes the path of a member, which may start with `./`, into a path
+/// relative to the root of the archive with forward slashes as separators.
+fn member_path(path: &Utf8Path) -> io::Result<Utf8PathBuf> {
+    let mut names = Vec::new();
+    for component in path.components() {
+        match component {
+            Utf8Component::Normal(name) => names.push(name),
+            Utf8Component::CurDir => {}
+            _ => {
+                return Err(invalid_data(format!(
+                    "member '{path}' is outside the root of the archive"
+                )))
+            }
+        }
+    }
+    Ok(names.join("/").into())
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn member_paths() {
+        assert_eq!(member_path("./a/b.txt".into()).unwrap(), "a/b.txt");
+        assert_eq!(member_path("a/./b.txt".into()).unwrap(), "a/b.txt");
+        for path in ["../a.txt", "/a.txt", "a/../../b.txt"] {
+            member_path(path.into()).unwrap_err();
+        }
+    }
+
+    #[test]
+    fn read_members() {
+        let archives = [
+            ("tests/archives/files.tar.gz", cfg!(feature = "archive-tar")),
+            ("tests/archives/files.zip", cfg!(feature = "archive-zip")),
+        ];
+        for (path, _) in archives.into_iter().filter(|(_, enabled)| *enabled) {
+            let archive = Archive::new(path);
+            assert_eq!(
+                archive.member("nested/b.txt".into()).unwrap().as_deref(),
+                Some(&b"b\n"[..]),
+                "{path}"
+            );
+            assert_eq!(archive.member("nested".into()).unwrap(), None, "{path}");
+            // Looking up single members doesn't read the whole archive.
+            assert!(archive.inner.members.get().is_none(), "{path}");
+
+            let members: Vec<_> = archive.members().unwrap().keys().collect();
+            assert_eq!(
+                members,
+                [
+                    ".gitignore",
+                    "a.txt",
+                    "nested/b.txt",
+                    "nested/c.in",
+                    "nested/c.out",
+                    "skipped.txt"
+                ],
+                "{path}"
+            );
+        }
+    }
+
+    #[test]
+    #[should_panic = "unknown archive format for 'corpus.rar'"]
+    fn unknown_format() {
+        Archive::new("corpus.rar");
+    }
+}
@@ -0,0 +1,130 @@
+// Copyright (c) The datatest-stable Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::data_source::TestEntry;
+use camino::{Utf8Path, Utf8PathBuf};
+use camino_tempfile::Utf8TempDir;
+use std::sync::OnceLock;
+
+/// Information about a test case, passed to test functions that accept a
+/// `&TestContext`.
+///
+/// Test functions of the form `fn(&TestContext)`, `fn(&TestContext, String)`,
+/// and `fn(&TestContext, Vec<u8>)` are passed one of these instead of a path.
+/// See [*Test context*](crate#test-context).
+#[derive(Debug)]
+pub struct TestContext {
+    entry: TestEntry,
+    group_name: String,
+    root: Option<Utf8PathBuf>,
+    temp_dir: OnceLock<Utf8TempDir>,
+}
+
+impl TestContext {
+    pub(crate) fn new(entry: TestEntry, group_name: String, root: Option<Utf8PathBuf>) -> Self {
+        Self {
+            entry,
+            group_name,
+            root,
+            temp_dir: OnceLock::new(),
+        }
+    }
+
+    pub(crate) fn entry(&self) -> &TestEntry {
+        &self.entry
+    }
+
+    pub(crate) fn into_entry(self) -> TestEntry {
+        self.entry
+    }
+
+    /// Returns the name of the test group, which is the prefix of the test's
+    /// name.
+    pub fn group_name(&self) -> &str {
+        &self.group_name
+    }
+
+    /// Returns the root directory of the test group, as passed to `root`.
+    ///
+    /// If the group has several roots, this is the one that the test case is
+    /// in.
+    ///
+    /// Returns `None` if the root is an `include_dir` directory or an archive.
+    pub fn root(&self) -> Option<&Utf8Path> {
+        self.root.as_deref()
+    }
+
+    /// Returns the path to the test case relative to the root, using `/` as
+    /// the separator.
+    ///
+    /// This is the path that `pattern` and `exclude` are matched against.
+    pub fn rel_path(&self) -> &Utf8Path {
+        self.entry.match_path()
+    }
+
+    /// Returns the path to the test case, as it would be passed to a test
+    /// function that accepts a path.
+    ///
+    /// For directories on disk, this is the root joined with
+    /// [`rel_path`](Self::rel_path). For `include_dir` sources and archives,
+    /// it's the same as `rel_path`.
+    pub fn test_path(&self) -> &Utf8Path {
+        self.entry.test_path()
+    }
+
+    /// Returns the path to the test case on disk.
+    ///
+    /// Returns `None` if the root is an `include_dir` directory or an archive.
+    pub fn disk_path(&self) -> Option<&Utf8Path> {
+        self.entry.disk_path()
+    }
+
+    /// Returns the name of the section this test case corresponds to, if the
+    /// group splits files into sections with `sections = true`.
+    pub fn section_name(&self) -> Option<&str> {
+        self.entry.section_name()
+    }
+
+    /// Returns the text matched by the named capture group `name` in
+    /// `pattern`.
+    ///
+    /// Returns `None` if `pattern` has no group with that name, or if the
+    /// group didn't participate in the match.
+    pub fn capture(&self, name: &str) -> Option<&str> {
+        self.entry
+            .captures()
+            .iter()
+            .find(|(capture_name, _)| capture_name == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Iterates over the names and values of the named capture groups in
+    /// `pattern` that participated in the match, in the order they appear in
+    /// the pattern.
+    pub fn captures(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.entry
+            .captures()
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_str()))
+    }
+
+    /// Returns a scratch directory for this test case.
+    ///
+    /// The directory is empty and unique to the test case. It is created the
+    /// first time this is called, and deleted along with its contents when
+    /// the test finishes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the directory can't be created.
+    pub fn temp_dir(&self) -> &Utf8Path {
+        self.temp_dir
+            .get_or_init(|| {
+                camino_tempfile::Builder::new()
+                    .prefix("datatest-stable-")
+                    .tempdir()
+                    .unwrap_or_else(|error| panic!("failed to create temporary directory: {error}"))
+            })
+            .path()
+    }
+}
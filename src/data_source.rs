@@ -1,56 +1,315 @@
 // Copyright (c) The datatest-stable Contributors
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
+use crate::{
+    directives::{Directives, SIDECAR_SUFFIX},
+    ignore::{IgnoreFiles, IGNORE_FILES},
+    naming::{Candidate, CaseNames},
+    sections::{split_sections, Section},
+};
 use camino::{Utf8Component, Utf8Path, Utf8PathBuf};
 
+/// Returns the named groups captured by a group's pattern for a path, or
+/// `None` if the path isn't a test case in the group.
+type SelectFn<'a> = dyn Fn(&Utf8Path) -> Option<Vec<(String, String)>> + 'a;
+
+/// The location that a [`TestGroup`](crate::TestGroup) looks for fixtures in.
+///
+/// This is created from a path to a directory (as a `&str`, `String`,
+/// `&Utf8Path` or `Utf8PathBuf`), with the `include-dir` feature, from an
+/// `include_dir::Dir` embedded into the binary, or with the `archive-tar` or
+/// `archive-zip` features, from an `Archive` on disk.
 #[derive(Debug)]
-#[doc(hidden)]
 pub enum DataSource {
     // The path has had normalize_slashes applied to it.
+    #[doc(hidden)]
     Directory(Utf8PathBuf),
+    #[doc(hidden)]
     #[cfg(feature = "include-dir")]
     IncludeDir(std::borrow::Cow<'static, include_dir::Dir<'static>>),
+    #[doc(hidden)]
+    #[cfg(any(feature = "archive-tar", feature = "archive-zip"))]
+    Archive(crate::Archive),
+}
+
+impl From<&Utf8Path> for DataSource {
+    fn from(path: &Utf8Path) -> Self {
+        DataSource::Directory(normalize_slashes(path))
+    }
+}
+
+impl From<Utf8PathBuf> for DataSource {
+    fn from(path: Utf8PathBuf) -> Self {
+        path.as_path().into()
+    }
+}
+
+impl From<&str> for DataSource {
+    fn from(path: &str) -> Self {
+        Utf8Path::new(path).into()
+    }
+}
+
+impl From<String> for DataSource {
+    fn from(path: String) -> Self {
+        path.as_str().into()
+    }
+}
+
+#[cfg(feature = "include-dir")]
+impl From<include_dir::Dir<'static>> for DataSource {
+    fn from(dir: include_dir::Dir<'static>) -> Self {
+        DataSource::IncludeDir(std::borrow::Cow::Owned(dir))
+    }
+}
+
+#[cfg(feature = "include-dir")]
+impl From<&'static include_dir::Dir<'static>> for DataSource {
+    fn from(dir: &'static include_dir::Dir<'static>) -> Self {
+        DataSource::IncludeDir(std::borrow::Cow::Borrowed(dir))
+    }
+}
+
+#[cfg(any(feature = "archive-tar", feature = "archive-zip"))]
+impl From<crate::Archive> for DataSource {
+    fn from(archive: crate::Archive) -> Self {
+        DataSource::Archive(archive)
+    }
+}
+
+/// What each test case in a [`TestGroup`](crate::TestGroup) corresponds to.
+///
+/// This is set with the `unit` key in [`harness!`](crate::harness), or with
+/// [`TestGroup::unit`](crate::TestGroup::unit).
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum Unit {
+    /// Each file under the root, found recursively, is a test case. This is
+    /// the default.
+    #[default]
+    File,
+
+    /// Each immediate subdirectory of the root is a test case.
+    ///
+    /// The test function is passed the path to the directory. For
+    /// `include_dir` sources, it is also passed the
+    /// [`include_dir::Dir`](https://docs.rs/include_dir/latest/include_dir/struct.Dir.html).
+    Dir,
 }
 
 impl DataSource {
-    /// Iterates over all files in the data source.
+    /// Iterates over all test cases in the data source: files or immediate
+    /// subdirectories, depending on `unit`.
     ///
     /// This returns entries that have just been discovered, so they're expected
     /// to exist.
-    pub(crate) fn walk_files(&self) -> Box<dyn Iterator<Item = std::io::Result<TestEntry>> + '_> {
+    pub(crate) fn walk(
+        &self,
+        unit: Unit,
+        filter: WalkFilter,
+    ) -> Box<dyn Iterator<Item = std::io::Result<TestEntry>> + '_> {
         match self {
-            DataSource::Directory(path) => Box::new(iter_directory(path)),
+            DataSource::Directory(path) => Box::new(iter_directory(path, unit, filter)),
             #[cfg(feature = "include-dir")]
-            DataSource::IncludeDir(dir) => Box::new(iter_include_dir(dir)),
+            DataSource::IncludeDir(dir) => match unit {
+                Unit::File => Box::new(iter_include_dir(dir, filter)),
+                Unit::Dir => Box::new(iter_include_subdirs(dir, filter)),
+            },
+            #[cfg(any(feature = "archive-tar", feature = "archive-zip"))]
+            DataSource::Archive(archive) => match unit {
+                Unit::File => iter_archive(archive, filter),
+                // Archives don't have subdirectories that can be passed to test
+                // functions, so `unit = dir` is rejected when the group is
+                // added to a harness.
+                Unit::Dir => Box::new(std::iter::empty()),
+            },
         }
     }
 
     /// Finds a test path from the filter provided.
     ///
-    /// The path might or might not exist -- the caller should call `.exists()`
-    /// to ensure it does.
+    /// Names can map back to several paths, so `select` is used to filter
+    /// out paths that aren't test cases in the group. It returns the named
+    /// groups captured by the pattern for paths that are. The path might or might
+    /// not exist -- the caller should call `.exists()` to ensure it does.
     ///
     /// Used for `--exact` matches.
-    pub(crate) fn derive_exact(&self, filter: &str, test_name: &str) -> Option<TestEntry> {
+    pub(crate) fn derive_exact(
+        &self,
+        filter: &str,
+        test_name: &str,
+        names: &CaseNames,
+        unit: Unit,
+        sections: bool,
+        select: &SelectFn<'_>,
+    ) -> Option<TestEntry> {
+        let rest = filter.strip_prefix(test_name)?.strip_prefix("::")?;
+        if !sections {
+            return self.entry_named(rest, names, unit, select);
+        }
+
+        // The name of the section follows the name of the file. Both names and
+        // section names can contain `::`, so try each possible split, starting
+        // from the end.
+        rest.rmatch_indices("::").find_map(|(index, _)| {
+            let entry = self.entry_named(&rest[..index], names, unit, select)?;
+            if !entry.exists() {
+                return None;
+            }
+            let sections = entry.split_sections().ok()?;
+            let section_name = &rest[index + 2..];
+            sections
+                .into_iter()
+                .find(|entry| entry.section_name() == Some(section_name))
+        })
+    }
+
+    /// Returns the entry for the case named `name`, preferring one that
+    /// exists.
+    fn entry_named(
+        &self,
+        name: &str,
+        names: &CaseNames,
+        unit: Unit,
+        select: &SelectFn<'_>,
+    ) -> Option<TestEntry> {
+        let mut entries = names
+            .candidates(name)
+            .into_iter()
+            .flat_map(|candidate| match candidate {
+                Candidate::Path(path) => self.entry_for(path.as_str(), unit).into_iter().collect(),
+                Candidate::Stem(stem) => self.entries_with_stem(&stem, unit),
+            })
+            .filter_map(|entry| {
+                let captures = select(&entry.rel_path)?;
+                let entry = entry.with_captures(captures);
+                (names.name(&entry.rel_path, &entry.captures) == name).then_some(entry)
+            })
+            .peekable();
+        let first = entries.peek().cloned();
+        entries.find(TestEntry::exists).or(first)
+    }
+
+    /// Returns the entries in the directory containing `stem` whose paths
+    /// without their extensions are `stem`.
+    ///
+    /// Only that directory is listed, rather than the whole root.
+    fn entries_with_stem(&self, stem: &Utf8Path, unit: Unit) -> Vec<TestEntry> {
+        let parent = stem.parent().unwrap_or(Utf8Path::new(""));
+        let Some(stem_name) = stem.file_name() else {
+            return Vec::new();
+        };
+
+        let mut file_names: Vec<String> = match self {
+            DataSource::Directory(root) => match root.join(parent).read_dir_utf8() {
+                Ok(entries) => entries
+                    .filter_map(|entry| Some(entry.ok()?.file_name().to_owned()))
+                    .collect(),
+                Err(_) => Vec::new(),
+            },
+            #[cfg(feature = "include-dir")]
+            DataSource::IncludeDir(dir) => {
+                let entries = if parent.as_str().is_empty() {
+                    dir.entries()
+                } else {
+                    match dir.get_dir(parent) {
+                        Some(dir) => dir.entries(),
+                        None => return Vec::new(),
+                    }
+                };
+                entries
+                    .iter()
+                    .filter_map(|entry| Some(entry.path().file_name()?.to_str()?.to_owned()))
+                    .collect()
+            }
+            #[cfg(any(feature = "archive-tar", feature = "archive-zip"))]
+            DataSource::Archive(archive) => match archive.members() {
+                Ok(members) => members
+                    .keys()
+                    .filter(|path| path.parent() == Some(parent))
+                    .filter_map(|path| Some(path.file_name()?.to_owned()))
+                    .collect(),
+                Err(_) => Vec::new(),
+            },
+        };
+        file_names.sort_unstable();
+
+        file_names
+            .into_iter()
+            .filter(|file_name| {
+                let extension = Utf8Path::new(file_name).extension().unwrap_or_default();
+                if extension.is_empty() {
+                    file_name == stem_name
+                } else {
+                    file_name[..file_name.len() - extension.len() - 1] == *stem_name
+                }
+            })
+            .filter_map(|file_name| self.entry_for(parent.join(file_name).as_str(), unit))
+            .collect()
+    }
+
+    /// Returns the entry at `rel_path`, which might or might not exist.
+    fn entry_for(&self, rel_path: &str, unit: Unit) -> Option<TestEntry> {
         // include_dir 0.7.4 returns paths with forward slashes, including on
         // Windows. But that isn't part of the stable API it seems, so we call
         // `rel_path_to_forward_slashes` anyway.
-        let rel_path = rel_path_to_forward_slashes(
-            filter.strip_prefix(test_name)?.strip_prefix("::")?.as_ref(),
-        );
+        let rel_path = rel_path_to_forward_slashes(rel_path.as_ref());
+        if unit == Unit::Dir && rel_path.components().count() != 1 {
+            // Only immediate subdirectories are test cases.
+            return None;
+        }
+
+        let source = match (self, unit) {
+            (DataSource::Directory(path), Unit::File) => {
+                TestSource::Path(normalize_slashes(&path.join(&rel_path)))
+            }
+            (DataSource::Directory(path), Unit::Dir) => {
+                TestSource::Dir(normalize_slashes(&path.join(&rel_path)))
+            }
+            #[cfg(feature = "include-dir")]
+            (DataSource::IncludeDir(dir), Unit::File) => TestSource::IncludeDir {
+                root: dir.as_ref().clone(),
+                file: dir.get_file(&rel_path)?,
+            },
+            #[cfg(feature = "include-dir")]
+            (DataSource::IncludeDir(dir), Unit::Dir) => TestSource::IncludeSubdir {
+                root: dir.as_ref().clone(),
+                dir: dir.get_dir(&rel_path)?,
+            },
+            #[cfg(any(feature = "archive-tar", feature = "archive-zip"))]
+            (DataSource::Archive(archive), Unit::File) => TestSource::Archive {
+                archive: archive.clone(),
+                contents: archive
+                    .member(&rel_path)
+                    .unwrap_or_else(|error| panic!("{}", error))?,
+            },
+            #[cfg(any(feature = "archive-tar", feature = "archive-zip"))]
+            (DataSource::Archive(_), Unit::Dir) => return None,
+        };
+        Some(TestEntry {
+            source,
+            rel_path,
+            section: None,
+            expected: None,
+            captures: Vec::new(),
+        })
+    }
+
+    /// Returns the file at `rel_path`, if it exists.
+    pub(crate) fn existing_file(&self, rel_path: &str) -> Option<TestEntry> {
+        self.entry_for(rel_path, Unit::File)
+            .filter(|entry| entry.path_is_file())
+    }
+
+    /// Returns true if the directory or archive exists. Included directories
+    /// always exist.
+    pub(crate) fn exists(&self) -> bool {
         match self {
-            DataSource::Directory(path) => Some(TestEntry {
-                source: TestSource::Path(normalize_slashes(&path.join(&rel_path))),
-                rel_path,
-            }),
+            DataSource::Directory(path) => path.is_dir(),
             #[cfg(feature = "include-dir")]
-            DataSource::IncludeDir(dir) => {
-                let file = dir.get_file(&rel_path)?;
-                Some(TestEntry {
-                    source: TestSource::IncludeDir(file),
-                    rel_path,
-                })
-            }
+            DataSource::IncludeDir(_) => true,
+            #[cfg(any(feature = "archive-tar", feature = "archive-zip"))]
+            DataSource::Archive(archive) => archive.path().is_file(),
         }
     }
 
@@ -61,127 +320,531 @@ impl DataSource {
             DataSource::Directory(_) => false,
             #[cfg(feature = "include-dir")]
             DataSource::IncludeDir(_) => true,
+            #[cfg(any(feature = "archive-tar", feature = "archive-zip"))]
+            DataSource::Archive(_) => true,
+        }
+    }
+
+    /// Returns true if this is an archive.
+    pub(crate) fn is_archive(&self) -> bool {
+        match self {
+            #[cfg(any(feature = "archive-tar", feature = "archive-zip"))]
+            DataSource::Archive(_) => true,
+            _ => false,
         }
     }
 
-    pub(crate) fn display(&self) -> String {
+    /// Returns the path to the directory, if this is a directory on disk.
+    pub(crate) fn dir_path(&self) -> Option<&Utf8Path> {
         match self {
+            DataSource::Directory(path) => Some(path),
+            #[cfg(feature = "include-dir")]
+            DataSource::IncludeDir(_) => None,
+            #[cfg(any(feature = "archive-tar", feature = "archive-zip"))]
+            DataSource::Archive(_) => None,
+        }
+    }
+
+    /// If `self` is the same directory as `outer` or a subdirectory of it,
+    /// returns the path to `self` relative to `outer`.
+    ///
+    /// Included directories are only the same if they come from the same
+    /// `include_dir!` invocation, and are never nested. Likewise, archives are
+    /// only the same if they have the same path.
+    pub(crate) fn path_within(&self, outer: &DataSource) -> Option<Utf8PathBuf> {
+        match (self, outer) {
+            (DataSource::Directory(path), DataSource::Directory(outer)) => {
+                path.strip_prefix(outer).ok().map(Utf8Path::to_path_buf)
+            }
+            #[cfg(feature = "include-dir")]
+            (DataSource::IncludeDir(dir), DataSource::IncludeDir(outer)) => {
+                std::ptr::eq(dir.entries(), outer.entries()).then(Utf8PathBuf::new)
+            }
+            #[cfg(any(feature = "archive-tar", feature = "archive-zip"))]
+            (DataSource::Archive(archive), DataSource::Archive(outer)) => {
+                archive.same_as(outer).then(Utf8PathBuf::new)
+            }
+            #[cfg(any(
+                feature = "include-dir",
+                feature = "archive-tar",
+                feature = "archive-zip"
+            ))]
+            _ => None,
+        }
+    }
+
+    /// Returns the label for this source when it's one of several roots in a
+    /// list without labels: the name of the directory or archive.
+    ///
+    /// # Panics
+    ///
+    /// Panics if this is an `include_dir` directory, which has no name, or a
+    /// path without a final component, such as `..`.
+    #[doc(hidden)]
+    pub fn default_label(&self) -> String {
+        let name = match self {
+            DataSource::Directory(path) => path.file_name(),
+            #[cfg(feature = "include-dir")]
+            DataSource::IncludeDir(_) => {
+                panic!(
+                    "included directories have no name to label them with in a list of \
+                     roots -- give each root a label, as in `root = [label = source, ...]`"
+                )
+            }
+            #[cfg(any(feature = "archive-tar", feature = "archive-zip"))]
+            DataSource::Archive(archive) => archive.path().file_name(),
+        };
+        match name {
+            Some(name) => name.to_owned(),
+            None => panic!(
+                "{} has no name to label it with in a list of roots -- give each root \
+                 a label, as in `root = [label = source, ...]`",
+                self.display(None)
+            ),
+        }
+    }
+
+    /// Creates the source that a `root_env` variable set to `value` points at:
+    /// an archive if `value` has the extension of one, and a directory
+    /// otherwise.
+    pub(crate) fn from_root_env(value: &str) -> Self {
+        #[cfg(any(feature = "archive-tar", feature = "archive-zip"))]
+        if crate::Archive::has_archive_extension(Utf8Path::new(value)) {
+            return crate::Archive::new(value).into();
+        }
+        value.into()
+    }
+
+    /// Describes the source in messages, noting `root_env` if the group has
+    /// one.
+    pub(crate) fn display(&self, root_env: Option<&RootEnv>) -> String {
+        let source = match self {
             DataSource::Directory(path) => format!("directory: `{path}`"),
             #[cfg(feature = "include-dir")]
             DataSource::IncludeDir(_) => "included directory".to_string(),
+            #[cfg(any(feature = "archive-tar", feature = "archive-zip"))]
+            DataSource::Archive(archive) => format!("archive: `{}`", archive.path()),
+        };
+        match root_env {
+            Some(RootEnv { var, is_set: true }) => format!("{source} (from `${var}`)"),
+            Some(RootEnv { var, is_set: false }) => {
+                format!("{source} (set `${var}` to use another root)")
+            }
+            None => source,
         }
     }
 }
 
-fn iter_directory(root: &Utf8Path) -> impl Iterator<Item = std::io::Result<TestEntry>> + '_ {
-    walkdir::WalkDir::new(root)
-        .into_iter()
-        .filter(|res| {
-            // Continue to bubble up all errors to the parent.
-            res.as_ref().map_or(true, |entry| {
-                entry.file_type().is_file()
-                    && entry
-                        .file_name()
-                        .to_str()
-                        .is_some_and(|s| !s.starts_with('.')) // Skip hidden files
-            })
-        })
-        .map(move |res| match res {
-            Ok(entry) => {
-                let path = Utf8PathBuf::try_from(entry.into_path())
-                    .map_err(|error| error.into_io_error())?;
-                Ok(TestEntry::from_full_path(root, path))
+/// An environment variable that can override the roots of a test group.
+pub(crate) struct RootEnv {
+    pub(crate) var: String,
+    /// Whether the variable was set, in which case it replaced the roots.
+    pub(crate) is_set: bool,
+}
+
+fn iter_directory(
+    root: &Utf8Path,
+    unit: Unit,
+    filter: WalkFilter,
+) -> impl Iterator<Item = std::io::Result<TestEntry>> + '_ {
+    let walker = match unit {
+        Unit::File => walkdir::WalkDir::new(root),
+        Unit::Dir => walkdir::WalkDir::new(root).min_depth(1).max_depth(1),
+    };
+    let mut walker = walker.into_iter();
+    let mut ignore_files = filter.ignore_files.then(|| ignore_files_on_disk(root));
+
+    std::iter::from_fn(move || loop {
+        // Continue to bubble up all errors to the parent.
+        let entry = match walker.next()? {
+            Ok(entry) => entry,
+            Err(error) => return Some(Err(error.into())),
+        };
+        if entry.depth() == 0 {
+            // This is the root itself.
+            continue;
+        }
+        let file_type = entry.file_type();
+        let is_dir = file_type.is_dir();
+        let path = match Utf8PathBuf::try_from(entry.into_path()) {
+            Ok(path) => path,
+            Err(error) => return Some(Err(error.into_io_error())),
+        };
+        let entry = if is_dir {
+            TestEntry::dir_from_full_path(root, path)
+        } else {
+            TestEntry::from_full_path(root, path)
+        };
+
+        if let Some(ignore_files) = &mut ignore_files {
+            match ignore_files.is_ignored(entry.rel_path.as_str(), is_dir) {
+                Ok(true) => {
+                    if is_dir {
+                        walker.skip_current_dir();
+                    }
+                    continue;
+                }
+                Ok(false) => {}
+                Err(error) => return Some(Err(error)),
             }
-            Err(error) => Err(error.into()),
-        })
+        }
+
+        let is_unit = match unit {
+            Unit::File => file_type.is_file(),
+            Unit::Dir => is_dir,
+        };
+        if is_unit && filter.includes(&entry.rel_path) {
+            return Some(Ok(entry));
+        }
+    })
 }
 
 #[cfg(feature = "include-dir")]
 fn iter_include_dir<'a>(
     dir: &'a include_dir::Dir<'static>,
+    filter: WalkFilter,
 ) -> impl Iterator<Item = std::io::Result<TestEntry>> + 'a {
     // Need to maintain a stack to do a depth-first traversal.
-    struct IncludeDirIter<'a> {
-        stack: Vec<&'a include_dir::DirEntry<'a>>,
-    }
+    let mut stack: Vec<&'static include_dir::DirEntry<'static>> = dir.entries().iter().collect();
+    let mut ignore_files = filter.ignore_files.then(|| ignore_files_in_memory(dir));
 
-    impl<'a> Iterator for IncludeDirIter<'a> {
-        type Item = &'a include_dir::File<'a>;
+    std::iter::from_fn(move || {
+        while let Some(entry) = stack.pop() {
+            let rel_path = match include_rel_path(entry.path()) {
+                Ok(rel_path) => rel_path,
+                Err(error) => return Some(Err(error)),
+            };
+            let is_dir = matches!(entry, include_dir::DirEntry::Dir(_));
+            if let Some(ignore_files) = &mut ignore_files {
+                match ignore_files.is_ignored(rel_path.as_str(), is_dir) {
+                    Ok(true) => continue,
+                    Ok(false) => {}
+                    Err(error) => return Some(Err(error)),
+                }
+            }
 
-        fn next(&mut self) -> Option<Self::Item> {
-            while let Some(entry) = self.stack.pop() {
-                match entry {
-                    include_dir::DirEntry::File(file) => {
-                        return Some(file);
-                    }
-                    include_dir::DirEntry::Dir(dir) => {
-                        self.stack.extend(dir.entries());
+            match entry {
+                include_dir::DirEntry::File(file) => {
+                    if filter.includes(&rel_path) {
+                        return Some(Ok(TestEntry {
+                            source: TestSource::IncludeDir {
+                                root: dir.clone(),
+                                file,
+                            },
+                            rel_path,
+                            section: None,
+                            expected: None,
+                            captures: Vec::new(),
+                        }));
                     }
                 }
+                include_dir::DirEntry::Dir(subdir) => {
+                    stack.extend(subdir.entries());
+                }
             }
+        }
 
-            None
+        None
+    })
+}
+
+#[cfg(feature = "include-dir")]
+fn iter_include_subdirs<'a>(
+    dir: &'a include_dir::Dir<'static>,
+    filter: WalkFilter,
+) -> impl Iterator<Item = std::io::Result<TestEntry>> + 'a {
+    let mut ignore_files = filter.ignore_files.then(|| ignore_files_in_memory(dir));
+
+    dir.dirs().filter_map(move |subdir| {
+        let rel_path = match include_rel_path(subdir.path()) {
+            Ok(rel_path) => rel_path,
+            Err(error) => return Some(Err(error)),
+        };
+        if let Some(ignore_files) = &mut ignore_files {
+            match ignore_files.is_ignored(rel_path.as_str(), true) {
+                Ok(true) => return None,
+                Ok(false) => {}
+                Err(error) => return Some(Err(error)),
+            }
         }
-    }
+        filter.includes(&rel_path).then(|| {
+            Ok(TestEntry {
+                source: TestSource::IncludeSubdir {
+                    root: dir.clone(),
+                    dir: subdir,
+                },
+                rel_path,
+                section: None,
+                expected: None,
+                captures: Vec::new(),
+            })
+        })
+    })
+}
 
-    IncludeDirIter {
-        stack: dir.entries().iter().collect(),
-    }
-    .map(|file| {
-        // include_dir 0.7.4 returns paths with forward slashes, including on
-        // Windows. But that isn't part of the stable API it seems, so we call
-        // `rel_path_to_forward_slashes` anyway.
-        let rel_path = match file.path().try_into() {
-            Ok(path) => rel_path_to_forward_slashes(path),
-            Err(error) => {
-                return Err(error.into_io_error());
+#[cfg(feature = "include-dir")]
+fn include_rel_path(path: &std::path::Path) -> std::io::Result<Utf8PathBuf> {
+    // include_dir 0.7.4 returns paths with forward slashes, including on
+    // Windows. But that isn't part of the stable API it seems, so we call
+    // `rel_path_to_forward_slashes` anyway.
+    let path: &Utf8Path = path
+        .try_into()
+        .map_err(|error: camino::FromPathError| error.into_io_error())?;
+    Ok(rel_path_to_forward_slashes(path))
+}
+
+#[cfg(any(feature = "archive-tar", feature = "archive-zip"))]
+fn iter_archive(
+    archive: &crate::Archive,
+    filter: WalkFilter,
+) -> Box<dyn Iterator<Item = std::io::Result<TestEntry>> + '_> {
+    let members = match archive.members() {
+        Ok(members) => members,
+        Err(error) => return Box::new(std::iter::once(Err(error))),
+    };
+    let mut ignore_files = filter
+        .ignore_files
+        .then(|| IgnoreFiles::new(move |rel_path| ignore_file_in_archive(members, rel_path)));
+
+    Box::new(members.iter().filter_map(move |(rel_path, contents)| {
+        if let Some(ignore_files) = &mut ignore_files {
+            // Members are listed without their directories, so check whether
+            // each directory containing the member is ignored first.
+            let dirs = rel_path
+                .ancestors()
+                .skip(1)
+                .filter(|dir| !dir.as_str().is_empty());
+            let mut dirs: Vec<_> = dirs.collect();
+            dirs.reverse();
+            for dir in dirs {
+                match ignore_files.is_ignored(dir.as_str(), true) {
+                    Ok(true) => return None,
+                    Ok(false) => {}
+                    Err(error) => return Some(Err(error)),
+                }
             }
-        };
-        Ok(TestEntry {
-            source: TestSource::IncludeDir(file),
-            rel_path,
+            match ignore_files.is_ignored(rel_path.as_str(), false) {
+                Ok(true) => return None,
+                Ok(false) => {}
+                Err(error) => return Some(Err(error)),
+            }
+        }
+        filter.includes(rel_path).then(|| {
+            Ok(TestEntry {
+                source: TestSource::Archive {
+                    archive: archive.clone(),
+                    contents: contents.clone(),
+                },
+                rel_path: rel_path.clone(),
+                section: None,
+                expected: None,
+                captures: Vec::new(),
+            })
         })
+    }))
+}
+
+fn ignore_files_on_disk(root: &Utf8Path) -> IgnoreFiles<'_> {
+    IgnoreFiles::new(
+        move |rel_path| match std::fs::read_to_string(root.join(rel_path)) {
+            Ok(contents) => Ok(Some(contents)),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(error) => Err(error),
+        },
+    )
+}
+
+#[cfg(feature = "include-dir")]
+fn ignore_files_in_memory<'a>(dir: &'a include_dir::Dir<'static>) -> IgnoreFiles<'a> {
+    IgnoreFiles::new(move |rel_path| {
+        dir.get_file(rel_path)
+            .map(|file| {
+                file.contents_utf8().map(str::to_owned).ok_or_else(|| {
+                    std::io::Error::new(
+                        std::io::ErrorKind::InvalidData,
+                        format!("ignore file '{rel_path}' is not valid UTF-8"),
+                    )
+                })
+            })
+            .transpose()
     })
 }
 
-#[derive(Debug)]
+#[cfg(any(feature = "archive-tar", feature = "archive-zip"))]
+fn ignore_file_in_archive(
+    members: &crate::archive::Members,
+    rel_path: &str,
+) -> std::io::Result<Option<String>> {
+    members
+        .get(Utf8Path::new(rel_path))
+        .map(|contents| {
+            String::from_utf8(contents.to_vec()).map_err(|_| {
+                std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!("ignore file '{rel_path}' is not valid UTF-8"),
+                )
+            })
+        })
+        .transpose()
+}
+
+/// Which entries are skipped while walking a data source, in addition to
+/// those that don't match the pattern.
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct WalkFilter {
+    /// If true, hidden files and directories (with names starting with `.`)
+    /// are test cases too.
+    pub(crate) hidden: bool,
+    /// If true, paths ignored by `.gitignore`, `.ignore` and `.datatestignore`
+    /// files under the root are skipped.
+    pub(crate) ignore_files: bool,
+}
+
+impl WalkFilter {
+    /// Returns true if the test case at `rel_path` isn't skipped, other than
+    /// by ignore files.
+    fn includes(&self, rel_path: &Utf8Path) -> bool {
+        let file_name = rel_path.file_name().unwrap_or_default();
+        if self.ignore_files && IGNORE_FILES.contains(&file_name) {
+            // Ignore files are never test cases themselves.
+            return false;
+        }
+        self.hidden || !file_name.starts_with('.')
+    }
+}
+
+#[derive(Clone, Debug)]
 pub(crate) struct TestEntry {
     source: TestSource,
     rel_path: Utf8PathBuf,
+    /// For fixtures split into sections, the section this entry refers to.
+    section: Option<Section>,
+    /// The expected file paired with this entry, if it was found.
+    expected: Option<Box<TestEntry>>,
+    /// The named groups captured by the group's pattern.
+    captures: Vec<(String, String)>,
 }
 
 impl TestEntry {
     pub(crate) fn from_full_path(root: &Utf8Path, path: Utf8PathBuf) -> Self {
         let path = normalize_slashes(&path);
-        let rel_path =
-            rel_path_to_forward_slashes(path.strip_prefix(root).unwrap_or_else(|_| {
-                panic!("failed to strip root '{}' from path '{}'", root, path)
-            }));
+        let rel_path = strip_root(root, &path);
         Self {
             source: TestSource::Path(path),
             rel_path,
+            section: None,
+            expected: None,
+            captures: Vec::new(),
         }
     }
 
-    pub(crate) fn derive_test_name(&self, test_name: &str) -> String {
-        format!("{}::{}", test_name, self.rel_path)
+    /// Like `from_full_path`, but for a directory that forms a test case.
+    pub(crate) fn dir_from_full_path(root: &Utf8Path, path: Utf8PathBuf) -> Self {
+        let path = normalize_slashes(&path);
+        let rel_path = strip_root(root, &path);
+        Self {
+            source: TestSource::Dir(path),
+            rel_path,
+            section: None,
+            expected: None,
+            captures: Vec::new(),
+        }
+    }
+
+    /// Splits this file into one entry per `=== name ===` section.
+    pub(crate) fn split_sections(&self) -> crate::Result<Vec<TestEntry>> {
+        let contents = self.read()?;
+        let sections = split_sections(&contents).map_err(|err| {
+            format!(
+                "error splitting '{}' into sections: {err}",
+                self.test_path()
+            )
+        })?;
+        Ok(sections
+            .into_iter()
+            .map(|section| TestEntry {
+                section: Some(section),
+                ..self.clone()
+            })
+            .collect())
+    }
+
+    /// Pairs this entry with its expected file.
+    pub(crate) fn with_expected(self, expected: TestEntry) -> Self {
+        Self {
+            expected: Some(Box::new(expected)),
+            ..self
+        }
+    }
+
+    /// Records the named groups captured by the group's pattern.
+    pub(crate) fn with_captures(self, captures: Vec<(String, String)>) -> Self {
+        Self { captures, ..self }
+    }
+
+    /// Returns the names and values of the named groups captured by the
+    /// group's pattern.
+    pub(crate) fn captures(&self) -> &[(String, String)] {
+        &self.captures
+    }
+
+    /// Reads the expected file paired with this entry, if any.
+    pub(crate) fn read_expected(&self) -> crate::Result<Option<Vec<u8>>> {
+        self.expected.as_ref().map(|entry| entry.read()).transpose()
+    }
+
+    /// Reads the expected file paired with this entry as UTF-8, if any.
+    pub(crate) fn read_expected_as_string(&self) -> crate::Result<Option<String>> {
+        self.expected
+            .as_ref()
+            .map(|entry| entry.read_as_string())
+            .transpose()
+    }
+
+    /// Returns the name of the section this entry refers to, if any.
+    pub(crate) fn section_name(&self) -> Option<&str> {
+        self.section.as_ref().map(|section| section.name.as_str())
+    }
+
+    pub(crate) fn derive_test_name(&self, test_name: &str, names: &CaseNames) -> String {
+        let name = names.name(&self.rel_path, &self.captures);
+        match self.section_name() {
+            Some(section_name) => format!("{}::{}::{}", test_name, name, section_name),
+            None => format!("{}::{}", test_name, name),
+        }
     }
 
     pub(crate) fn read(&self) -> crate::Result<Vec<u8>> {
+        if let Some(section) = &self.section {
+            return Ok(section.contents.clone());
+        }
         match &self.source {
             TestSource::Path(path) => std::fs::read(path)
                 .map_err(|err| format!("error reading file '{path}': {err}").into()),
             #[cfg(feature = "include-dir")]
-            TestSource::IncludeDir(file) => Ok(file.contents().to_vec()),
+            TestSource::IncludeDir { file, .. } => Ok(file.contents().to_vec()),
+            #[cfg(any(feature = "archive-tar", feature = "archive-zip"))]
+            TestSource::Archive { contents, .. } => Ok(contents.to_vec()),
+            _ => Err(self.is_a_directory()),
         }
     }
 
     pub(crate) fn read_as_string(&self) -> crate::Result<String> {
+        if let Some(section) = &self.section {
+            return String::from_utf8(section.contents.clone()).map_err(|err| {
+                format!(
+                    "error reading section '{}' of '{}' as UTF-8: {err}",
+                    section.name,
+                    self.test_path()
+                )
+                .into()
+            });
+        }
         match &self.source {
             TestSource::Path(path) => std::fs::read_to_string(path)
                 .map_err(|err| format!("error reading file '{path}' as UTF-8: {err}").into()),
             #[cfg(feature = "include-dir")]
-            TestSource::IncludeDir(file) => {
+            TestSource::IncludeDir { file, .. } => {
                 let contents = file.contents().to_vec();
                 String::from_utf8(contents).map_err(|err| {
                     format!(
@@ -191,12 +854,28 @@ impl TestEntry {
                     .into()
                 })
             }
+            #[cfg(any(feature = "archive-tar", feature = "archive-zip"))]
+            TestSource::Archive { archive, contents } => String::from_utf8(contents.to_vec())
+                .map_err(|err| {
+                    format!(
+                        "error reading '{}' in archive '{}' as UTF-8: {err}",
+                        self.rel_path,
+                        archive.path()
+                    )
+                    .into()
+                }),
+            _ => Err(self.is_a_directory()),
         }
     }
 
+    fn is_a_directory(&self) -> Box<dyn std::error::Error> {
+        format!("error reading '{}': it is a directory", self.test_path()).into()
+    }
+
     /// Returns the path to match regexes against.
     ///
-    /// This is always the relative path to the file from the include directory.
+    /// This is always the relative path to the file or directory from the
+    /// include directory.
     pub(crate) fn match_path(&self) -> &Utf8Path {
         &self.rel_path
     }
@@ -204,39 +883,166 @@ impl TestEntry {
     /// Returns the path to the test data, as passed into the test function.
     ///
     /// For directories on disk, this is the relative path after being joined
-    /// with the include directory. For `include_dir` sources, this is the path
-    /// relative to the root of the include directory.
+    /// with the include directory. For `include_dir` sources and archives, this
+    /// is the path relative to the root of the include directory or archive.
     pub(crate) fn test_path(&self) -> &Utf8Path {
         match &self.source {
-            TestSource::Path(path) => path,
+            TestSource::Path(path) | TestSource::Dir(path) => path,
             #[cfg(feature = "include-dir")]
-            TestSource::IncludeDir(_) => {
+            TestSource::IncludeDir { .. } | TestSource::IncludeSubdir { .. } => {
                 // The UTF-8-encoded version of file.path is stored in `rel_path`.
                 &self.rel_path
             }
+            #[cfg(any(feature = "archive-tar", feature = "archive-zip"))]
+            TestSource::Archive { .. } => &self.rel_path,
         }
     }
 
     /// Returns the path to the file on disk.
     ///
-    /// If the data source is an `include_dir`, this will return `None`.
+    /// If the data source is an `include_dir` or an archive, this will return
+    /// `None`.
     pub(crate) fn disk_path(&self) -> Option<&Utf8Path> {
         match &self.source {
-            TestSource::Path(path) => Some(path),
+            TestSource::Path(path) | TestSource::Dir(path) => Some(path),
             #[cfg(feature = "include-dir")]
-            TestSource::IncludeDir(_) => None,
+            TestSource::IncludeDir { .. } | TestSource::IncludeSubdir { .. } => None,
+            #[cfg(any(feature = "archive-tar", feature = "archive-zip"))]
+            TestSource::Archive { .. } => None,
+        }
+    }
+
+    /// Returns the included directory that forms this test case, for
+    /// `Unit::Dir` entries from an `include_dir` source.
+    #[cfg(feature = "include-dir")]
+    pub(crate) fn included_dir(&self) -> Option<&'static include_dir::Dir<'static>> {
+        match &self.source {
+            TestSource::IncludeSubdir { dir, .. } => Some(dir),
+            _ => None,
+        }
+    }
+
+    /// Reads a file from the same in-memory data source as this entry, such as
+    /// an included directory or an archive.
+    ///
+    /// `rel_path` is relative to the root of the data source. Returns `None` if
+    /// this entry is on disk, or if the file doesn't exist.
+    pub(crate) fn in_memory_file(&self, rel_path: &Utf8Path) -> crate::Result<Option<Vec<u8>>> {
+        match &self.source {
+            TestSource::Path(_) | TestSource::Dir(_) => {
+                let _ = rel_path;
+                Ok(None)
+            }
+            #[cfg(feature = "include-dir")]
+            TestSource::IncludeDir { root, .. } | TestSource::IncludeSubdir { root, .. } => {
+                Ok(root.get_file(rel_path).map(|file| file.contents().to_vec()))
+            }
+            #[cfg(any(feature = "archive-tar", feature = "archive-zip"))]
+            TestSource::Archive { archive, .. } => {
+                Ok(archive.member(rel_path)?.map(|contents| contents.to_vec()))
+            }
+        }
+    }
+
+    /// Describes the in-memory data source this entry is part of, such as
+    /// "included directory".
+    pub(crate) fn in_memory_source(&self) -> String {
+        match &self.source {
+            TestSource::Path(_) | TestSource::Dir(_) => "directory".to_owned(),
+            #[cfg(feature = "include-dir")]
+            TestSource::IncludeDir { .. } | TestSource::IncludeSubdir { .. } => {
+                "included directory".to_owned()
+            }
+            #[cfg(any(feature = "archive-tar", feature = "archive-zip"))]
+            TestSource::Archive { archive, .. } => format!("archive '{}'", archive.path()),
+        }
+    }
+
+    /// Reads the directives for this entry from its sidecar file and, for
+    /// files, its first line.
+    pub(crate) fn read_directives(&self) -> crate::Result<Directives> {
+        let describe = |err| format!("error reading directives for '{}': {err}", self.test_path());
+
+        let mut directives = self.read_sidecar().map_err(describe)?;
+        let is_dir = match &self.source {
+            TestSource::Dir(_) => true,
+            #[cfg(feature = "include-dir")]
+            TestSource::IncludeSubdir { .. } => true,
+            _ => false,
+        };
+        if !is_dir {
+            if let Some(header) = Directives::from_header(&self.read()?).map_err(describe)? {
+                directives = directives.merge(header);
+            }
+        }
+        Ok(directives)
+    }
+
+    /// Reads the sidecar file next to this entry, if it exists.
+    fn read_sidecar(&self) -> std::result::Result<Directives, String> {
+        let contents = match self.disk_path() {
+            Some(path) => {
+                let sidecar = format!("{path}{SIDECAR_SUFFIX}");
+                match std::fs::read_to_string(&sidecar) {
+                    Ok(contents) => contents,
+                    Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                        return Ok(Directives::default())
+                    }
+                    Err(err) => return Err(format!("error reading '{sidecar}': {err}")),
+                }
+            }
+            None => {
+                let sidecar = format!("{}{SIDECAR_SUFFIX}", self.match_path());
+                match self
+                    .in_memory_file(sidecar.as_ref())
+                    .map_err(|err| format!("error reading '{sidecar}': {err}"))?
+                {
+                    Some(contents) => String::from_utf8(contents)
+                        .map_err(|_| format!("'{sidecar}' is not valid UTF-8"))?,
+                    None => return Ok(Directives::default()),
+                }
+            }
+        };
+
+        #[cfg(feature = "toml")]
+        {
+            Directives::from_sidecar(&contents)
+        }
+        #[cfg(not(feature = "toml"))]
+        {
+            let _ = contents;
+            Err(format!(
+                "found a `{SIDECAR_SUFFIX}` sidecar file, but reading it requires \
+                 the `toml` feature of datatest-stable"
+            ))
         }
     }
 
     /// Returns true if the path exists.
+    fn path_is_file(&self) -> bool {
+        match &self.source {
+            TestSource::Path(path) => path.is_file(),
+            TestSource::Dir(_) => false,
+            #[cfg(feature = "include-dir")]
+            TestSource::IncludeDir { .. } => true,
+            #[cfg(feature = "include-dir")]
+            TestSource::IncludeSubdir { .. } => false,
+            #[cfg(any(feature = "archive-tar", feature = "archive-zip"))]
+            TestSource::Archive { .. } => true,
+        }
+    }
+
     pub(crate) fn exists(&self) -> bool {
         match &self.source {
             TestSource::Path(path) => path.exists(),
+            TestSource::Dir(path) => path.is_dir(),
             #[cfg(feature = "include-dir")]
-            TestSource::IncludeDir(_) => {
-                // include_dir files are guaranteed to exist.
+            TestSource::IncludeDir { .. } | TestSource::IncludeSubdir { .. } => {
+                // include_dir entries are guaranteed to exist.
                 true
             }
+            #[cfg(any(feature = "archive-tar", feature = "archive-zip"))]
+            TestSource::Archive { .. } => true,
         }
     }
 }
@@ -271,6 +1077,14 @@ fn rel_path_to_forward_slashes(path: &Utf8Path) -> Utf8PathBuf {
     path.to_owned()
 }
 
+#[track_caller]
+fn strip_root(root: &Utf8Path, path: &Utf8Path) -> Utf8PathBuf {
+    rel_path_to_forward_slashes(
+        path.strip_prefix(root)
+            .unwrap_or_else(|_| panic!("failed to strip root '{}' from path '{}'", root, path)),
+    )
+}
+
 /// Returns true if this is a path with no root-dir or prefix components.
 ///
 /// On Windows, unlike `path.is_relative()`, this rejects paths like "C:temp"
@@ -283,14 +1097,36 @@ fn is_truly_relative(path: &Utf8Path) -> bool {
     })
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 #[doc(hidden)]
 pub(crate) enum TestSource {
     /// A data source on disk, with the path being the relative path to the file
     /// from the crate root.
     Path(Utf8PathBuf),
+    /// A directory on disk that forms a test case, with the path being
+    /// relative to the crate root as above.
+    Dir(Utf8PathBuf),
+    #[cfg(feature = "include-dir")]
+    IncludeDir {
+        /// The root of the included directory, used to look up sibling files
+        /// such as expected outputs.
+        root: include_dir::Dir<'static>,
+        file: &'static include_dir::File<'static>,
+    },
+    /// A subdirectory of an included directory that forms a test case.
     #[cfg(feature = "include-dir")]
-    IncludeDir(&'static include_dir::File<'static>),
+    IncludeSubdir {
+        /// The root of the included directory, as above.
+        root: include_dir::Dir<'static>,
+        dir: &'static include_dir::Dir<'static>,
+    },
+    /// A member of an archive, which has been read into memory.
+    #[cfg(any(feature = "archive-tar", feature = "archive-zip"))]
+    Archive {
+        /// The archive, used to look up sibling files such as expected outputs.
+        archive: crate::Archive,
+        contents: std::sync::Arc<[u8]>,
+    },
 }
 
 /// Polymorphic dispatch to resolve data sources
@@ -310,6 +1146,8 @@ pub mod data_source_kinds {
         pub trait AsDirectorySealed {}
         #[cfg(feature = "include-dir")]
         pub trait AsIncludeDirSealed {}
+        #[cfg(any(feature = "archive-tar", feature = "archive-zip"))]
+        pub trait AsArchiveSealed {}
     }
 
     // -- As directory ---
@@ -322,10 +1160,7 @@ pub mod data_source_kinds {
 
     impl<T: ToString> AsDirectory for T {
         fn resolve_data_source(self) -> DataSource {
-            let s = self.to_string();
-            let path = Utf8Path::new(&s);
-
-            DataSource::Directory(normalize_slashes(path))
+            self.to_string().into()
         }
     }
 
@@ -340,7 +1175,7 @@ pub mod data_source_kinds {
     #[cfg(feature = "include-dir")]
     impl AsIncludeDir for include_dir::Dir<'static> {
         fn resolve_data_source(self) -> DataSource {
-            DataSource::IncludeDir(std::borrow::Cow::Owned(self))
+            self.into()
         }
     }
 
@@ -350,7 +1185,24 @@ pub mod data_source_kinds {
     #[cfg(feature = "include-dir")]
     impl AsIncludeDir for &'static include_dir::Dir<'static> {
         fn resolve_data_source(self) -> DataSource {
-            DataSource::IncludeDir(std::borrow::Cow::Borrowed(self))
+            self.into()
+        }
+    }
+
+    // -- As archive ---
+
+    #[cfg(any(feature = "archive-tar", feature = "archive-zip"))]
+    pub trait AsArchive: private::AsArchiveSealed {
+        fn resolve_data_source(self) -> DataSource;
+    }
+
+    #[cfg(any(feature = "archive-tar", feature = "archive-zip"))]
+    impl private::AsArchiveSealed for crate::Archive {}
+
+    #[cfg(any(feature = "archive-tar", feature = "archive-zip"))]
+    impl AsArchive for crate::Archive {
+        fn resolve_data_source(self) -> DataSource {
+            self.into()
         }
     }
 }
@@ -419,12 +1271,227 @@ mod tests {
     }
 
     fn derive_test_name(root: &Utf8Path, path: &Utf8Path, test_name: &str) -> String {
-        TestEntry::from_full_path(root, path.to_owned()).derive_test_name(test_name)
+        TestEntry::from_full_path(root, path.to_owned())
+            .derive_test_name(test_name, &CaseNames::default())
     }
 
     fn derive_test_path(root: &Utf8Path, path: &str, test_name: &str) -> Option<Utf8PathBuf> {
         DataSource::Directory(root.to_owned())
-            .derive_exact(path, test_name)
+            .derive_exact(
+                path,
+                test_name,
+                &CaseNames::default(),
+                Unit::File,
+                false,
+                &|_| Some(Vec::new()),
+            )
             .map(|entry| entry.test_path().to_owned())
     }
+
+    #[test]
+    fn derive_exact_sections() {
+        let dir = camino_tempfile::Utf8TempDir::new().unwrap();
+        std::fs::write(
+            dir.path().join("a::b.txt"),
+            "=== one ===\n1\n=== two::2 ===\n2\n",
+        )
+        .unwrap();
+
+        let source = DataSource::from(dir.path());
+        let entry = TestEntry::from_full_path(dir.path(), dir.path().join("a::b.txt"));
+        for section in entry.split_sections().unwrap() {
+            let name = section.derive_test_name("t", &CaseNames::default());
+            let derived = source
+                .derive_exact(&name, "t", &CaseNames::default(), Unit::File, true, &|_| {
+                    Some(Vec::new())
+                })
+                .unwrap_or_else(|| panic!("{name} found"));
+            assert_eq!(derived.derive_test_name("t", &CaseNames::default()), name);
+            assert_eq!(derived.read().unwrap(), section.read().unwrap());
+        }
+
+        for missing in ["t::a::b.txt", "t::a::b.txt::three", "t::c.txt::one"] {
+            assert!(
+                source
+                    .derive_exact(
+                        missing,
+                        "t",
+                        &CaseNames::default(),
+                        Unit::File,
+                        true,
+                        &|_| Some(Vec::new())
+                    )
+                    .is_none(),
+                "{missing} not found"
+            );
+        }
+    }
+
+    #[test]
+    fn derive_exact_stems() {
+        let dir = camino_tempfile::Utf8TempDir::new().unwrap();
+        for path in [
+            "a.in",
+            "a.out",
+            "nested/b.c.in",
+            "nested/b.in.out",
+            "no-ext",
+        ] {
+            let path = dir.path().join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, "").unwrap();
+        }
+
+        let source = DataSource::from(dir.path());
+        let mut names = CaseNames::default();
+        names.set_template("{stem}").unwrap();
+        let derive = |name: &str, selects: &dyn Fn(&Utf8Path) -> bool| {
+            source
+                .derive_exact(name, "t", &names, Unit::File, false, &|path| {
+                    selects(path).then(Vec::new)
+                })
+                .map(|entry| entry.rel_path.into_string())
+        };
+        let is_input = |path: &Utf8Path| path.extension() == Some("in");
+
+        assert_eq!(derive("t::a", &is_input).as_deref(), Some("a.in"));
+        assert_eq!(derive("t::a", &|_| true).as_deref(), Some("a.in"));
+        assert_eq!(
+            derive("t::a", &|path| !is_input(path)).as_deref(),
+            Some("a.out")
+        );
+        assert_eq!(
+            derive("t::nested/b.c", &is_input).as_deref(),
+            Some("nested/b.c.in")
+        );
+        assert_eq!(derive("t::nested/b", &is_input), None);
+        assert_eq!(derive("t::no-ext", &|_| true).as_deref(), Some("no-ext"));
+        assert_eq!(derive("t::missing", &|_| true), None);
+    }
+
+    #[test]
+    fn dir_unit_only_immediate_subdirs() {
+        let dir = camino_tempfile::Utf8TempDir::new().unwrap();
+        for subdir in ["case1", "case2/nested", ".hidden"] {
+            std::fs::create_dir_all(dir.path().join(subdir)).unwrap();
+        }
+        std::fs::write(dir.path().join("file.txt"), "").unwrap();
+        std::fs::write(dir.path().join("case1/input.txt"), "").unwrap();
+
+        let source = DataSource::from(dir.path());
+        let mut names: Vec<_> = source
+            .walk(Unit::Dir, WalkFilter::default())
+            .map(|entry| entry.unwrap().derive_test_name("t", &CaseNames::default()))
+            .collect();
+        names.sort();
+        assert_eq!(names, ["t::case1", "t::case2"]);
+
+        for name in &names {
+            let entry = source
+                .derive_exact(name, "t", &CaseNames::default(), Unit::Dir, false, &|_| {
+                    Some(Vec::new())
+                })
+                .unwrap();
+            assert!(entry.exists(), "{name} exists");
+        }
+        assert!(source
+            .derive_exact(
+                "t::case2/nested",
+                "t",
+                &CaseNames::default(),
+                Unit::Dir,
+                false,
+                &|_| Some(Vec::new())
+            )
+            .is_none());
+        let file = source
+            .derive_exact(
+                "t::file.txt",
+                "t",
+                &CaseNames::default(),
+                Unit::Dir,
+                false,
+                &|_| Some(Vec::new()),
+            )
+            .unwrap();
+        assert!(!file.exists(), "files aren't directory test cases");
+    }
+
+    #[test]
+    fn walk_filter() {
+        let dir = camino_tempfile::Utf8TempDir::new().unwrap();
+        for (path, contents) in [
+            (
+                ".gitignore",
+                "*.swp
+/out/
+",
+            ),
+            (
+                ".datatestignore",
+                "!keep.swp
+",
+            ),
+            (".hidden.txt", ""),
+            ("a.txt", ""),
+            ("a.txt.swp", ""),
+            ("keep.swp", ""),
+            ("out/a.txt", ""),
+            (
+                "nested/.ignore",
+                "*.txt
+",
+            ),
+            ("nested/b.txt", ""),
+            ("nested/b.json", ""),
+            ("nested/out/c.json", ""),
+        ] {
+            let path = dir.path().join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, contents).unwrap();
+        }
+
+        let source = DataSource::from(dir.path());
+        let walk = |hidden, ignore_files| {
+            let mut paths: Vec<_> = source
+                .walk(
+                    Unit::File,
+                    WalkFilter {
+                        hidden,
+                        ignore_files,
+                    },
+                )
+                .map(|entry| entry.unwrap().rel_path.into_string())
+                .collect();
+            paths.sort();
+            paths
+        };
+
+        assert_eq!(
+            walk(false, false),
+            [
+                "a.txt",
+                "a.txt.swp",
+                "keep.swp",
+                "nested/b.json",
+                "nested/b.txt",
+                "nested/out/c.json",
+                "out/a.txt",
+            ]
+        );
+        assert_eq!(
+            walk(false, true),
+            ["a.txt", "keep.swp", "nested/b.json", "nested/out/c.json"]
+        );
+        assert_eq!(
+            walk(true, true),
+            [
+                ".hidden.txt",
+                "a.txt",
+                "keep.swp",
+                "nested/b.json",
+                "nested/out/c.json"
+            ]
+        );
+    }
 }
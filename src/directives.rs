@@ -0,0 +1,161 @@
+// Copyright (c) The datatest-stable Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Per-fixture directives that mark tests as ignored or expected to fail.
+
+/// The suffix appended to a fixture's path to obtain its sidecar file.
+pub(crate) const SIDECAR_SUFFIX: &str = ".datatest.toml";
+
+/// The marker that introduces directives in a fixture's first line.
+const HEADER_MARKER: &str = "datatest:";
+
+/// Comment markers that may surround a header directive, as (start, end) pairs.
+const COMMENT_MARKERS: &[(&str, &str)] = &[
+    ("//", ""),
+    ("#", ""),
+    ("--", ""),
+    (";", ""),
+    ("/*", "*/"),
+    ("<!--", "-->"),
+];
+
+/// Directives that apply to a single test.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub(crate) struct Directives {
+    /// The test is ignored unless `--ignored` or `--include-ignored` is passed in.
+    pub(crate) ignore: bool,
+    /// The test passes if the test function returns an error or panics, and
+    /// fails otherwise.
+    pub(crate) should_fail: bool,
+}
+
+impl Directives {
+    /// Parses directives from the first line of a fixture, e.g.
+    /// `// datatest: ignore, should-fail`.
+    ///
+    /// Returns `Ok(None)` if the first line isn't a directive.
+    pub(crate) fn from_header(contents: &[u8]) -> Result<Option<Self>, String> {
+        let first_line = contents.split(|&b| b == b'\n').next().unwrap_or_default();
+        let Ok(first_line) = std::str::from_utf8(first_line) else {
+            return Ok(None);
+        };
+
+        let first_line = first_line.trim();
+        let Some(directives) = COMMENT_MARKERS.iter().find_map(|(start, end)| {
+            first_line
+                .strip_prefix(start)?
+                .strip_suffix(end)?
+                .trim_start()
+                .strip_prefix(HEADER_MARKER)
+        }) else {
+            return Ok(None);
+        };
+
+        let mut ret = Self::default();
+        for directive in directives
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|directive| !directive.is_empty())
+        {
+            match directive {
+                "ignore" => ret.ignore = true,
+                "should-fail" => ret.should_fail = true,
+                other => {
+                    return Err(format!(
+                        "unknown directive '{other}' (expected `ignore` or `should-fail`)"
+                    ))
+                }
+            }
+        }
+        Ok(Some(ret))
+    }
+
+    /// Parses directives from the contents of a sidecar file.
+    #[cfg(feature = "toml")]
+    pub(crate) fn from_sidecar(contents: &str) -> Result<Self, String> {
+        let table: toml::Table = toml::from_str(contents).map_err(|err| err.to_string())?;
+
+        let mut ret = Self::default();
+        for (key, value) in table {
+            let field = match key.as_str() {
+                "ignore" => &mut ret.ignore,
+                "should-fail" => &mut ret.should_fail,
+                other => {
+                    return Err(format!(
+                        "unknown key '{other}' (expected `ignore` or `should-fail`)"
+                    ))
+                }
+            };
+            *field = value
+                .as_bool()
+                .ok_or_else(|| format!("expected '{key}' to be a boolean, found {value}"))?;
+        }
+        Ok(ret)
+    }
+
+    /// Combines directives from several sources.
+    pub(crate) fn merge(self, other: Self) -> Self {
+        Self {
+            ignore: self.ignore || other.ignore,
+            should_fail: self.should_fail || other.should_fail,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn header_directives() {
+        for (contents, expected) in [
+            (&b"// datatest: ignore\nfoo"[..], Some((true, false))),
+            (b"# datatest: should-fail", Some((false, true))),
+            (b"  --datatest:ignore,should-fail  \n", Some((true, true))),
+            (b"/* datatest: should-fail ignore */", Some((true, true))),
+            (b"<!-- datatest: ignore -->\n", Some((true, false))),
+            (b"// datatest:\n", Some((false, false))),
+            (b"// not a directive: ignore\n", None),
+            (b"foo\n// datatest: ignore\n", None),
+            (b"", None),
+        ] {
+            let expected = expected.map(|(ignore, should_fail)| Directives {
+                ignore,
+                should_fail,
+            });
+            assert_eq!(
+                Directives::from_header(contents).unwrap(),
+                expected,
+                "for {:?}",
+                String::from_utf8_lossy(contents)
+            );
+        }
+    }
+
+    #[test]
+    fn unknown_header_directive() {
+        assert_eq!(
+            Directives::from_header(b"# datatest: ignore, flaky").unwrap_err(),
+            "unknown directive 'flaky' (expected `ignore` or `should-fail`)"
+        );
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn sidecar_directives() {
+        assert_eq!(
+            Directives::from_sidecar("ignore = true\nshould-fail = false\n").unwrap(),
+            Directives {
+                ignore: true,
+                should_fail: false
+            }
+        );
+        assert_eq!(
+            Directives::from_sidecar("flaky = true").unwrap_err(),
+            "unknown key 'flaky' (expected `ignore` or `should-fail`)"
+        );
+        assert_eq!(
+            Directives::from_sidecar("ignore = 1").unwrap_err(),
+            "expected 'ignore' to be a boolean, found 1"
+        );
+    }
+}
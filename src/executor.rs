@@ -0,0 +1,137 @@
+// Copyright (c) The datatest-stable Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::Result;
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{Arc, OnceLock},
+    task::{Context, Poll, Wake, Waker},
+    thread::{self, Thread},
+};
+
+/// A future returned by an async test function.
+pub type TestFuture<'a> = Pin<Box<dyn Future<Output = Result<()>> + 'a>>;
+
+/// Runs the futures returned by async test functions to completion.
+///
+/// One executor is shared by all async tests in a process, and is only
+/// created if one of them runs. By default, it's a multi-threaded Tokio
+/// runtime with the `tokio` feature, and a [`BlockingExecutor`] otherwise.
+/// Use [`Harness::executor`](crate::Harness::executor) or `executor` in
+/// [`harness!`](crate::harness) to replace it.
+pub trait Executor: Send + Sync {
+    /// Blocks the current thread until `future` completes, and returns its
+    /// result.
+    ///
+    /// This may be called from several test threads at once.
+    fn block_on<'a>(&self, future: TestFuture<'a>) -> Result<()>;
+}
+
+/// A minimal executor that polls each future on the thread running the test,
+/// parking the thread while the future is pending.
+///
+/// This is enough for futures that don't depend on a particular runtime, such
+/// as those from `futures` or `async-std`. Futures that need a Tokio runtime
+/// must use one as the executor instead, which requires the `tokio` feature.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct BlockingExecutor;
+
+impl Executor for BlockingExecutor {
+    fn block_on<'a>(&self, mut future: TestFuture<'a>) -> Result<()> {
+        let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+        let mut cx = Context::from_waker(&waker);
+        loop {
+            match future.as_mut().poll(&mut cx) {
+                Poll::Ready(result) => return result,
+                // Wakeups may be spurious, in which case the future is polled
+                // again and returns pending.
+                Poll::Pending => thread::park(),
+            }
+        }
+    }
+}
+
+struct ThreadWaker(Thread);
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl Executor for tokio::runtime::Runtime {
+    fn block_on<'a>(&self, future: TestFuture<'a>) -> Result<()> {
+        tokio::runtime::Runtime::block_on(self, future)
+    }
+}
+
+static EXECUTOR: OnceLock<Box<dyn Executor>> = OnceLock::new();
+
+/// Sets the executor for async tests in this process.
+///
+/// # Panics
+///
+/// Panics if an executor was already set.
+pub(crate) fn set_executor(executor: Box<dyn Executor>) {
+    if EXECUTOR.set(executor).is_err() {
+        panic!("the executor for async tests can only be set once per process");
+    }
+}
+
+/// Runs `future` to completion on the executor, creating the default one if
+/// none was set.
+pub(crate) fn block_on<'a>(future: impl Future<Output = Result<()>> + 'a) -> Result<()> {
+    EXECUTOR
+        .get_or_init(default_executor)
+        .block_on(Box::pin(future))
+}
+
+#[cfg(feature = "tokio")]
+fn default_executor() -> Box<dyn Executor> {
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .expect("created Tokio runtime for async tests");
+    Box::new(runtime)
+}
+
+#[cfg(not(feature = "tokio"))]
+fn default_executor() -> Box<dyn Executor> {
+    Box::new(BlockingExecutor)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+
+    #[test]
+    fn blocking_executor() {
+        // A future that's woken from another thread.
+        let (sender, receiver) = mpsc::channel();
+        let future = Box::pin(std::future::poll_fn(move |cx| match receiver.try_recv() {
+            Ok(()) => Poll::Ready(Ok(())),
+            Err(_) => {
+                let waker = cx.waker().clone();
+                let sender = sender.clone();
+                thread::spawn(move || {
+                    sender.send(()).unwrap();
+                    waker.wake();
+                });
+                Poll::Pending
+            }
+        }));
+        BlockingExecutor.block_on(future).unwrap();
+
+        let error = BlockingExecutor
+            .block_on(Box::pin(async { Err("failed".into()) }))
+            .unwrap_err();
+        assert_eq!(error.to_string(), "failed");
+    }
+}
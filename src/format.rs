@@ -0,0 +1,203 @@
+// Copyright (c) The datatest-stable Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::Result;
+use camino::Utf8Path;
+use serde::de::DeserializeOwned;
+use std::fmt;
+
+/// A data format that fixtures can be deserialized from.
+///
+/// Test functions that accept a type implementing
+/// [`DeserializeOwned`](serde::de::DeserializeOwned) have each fixture parsed
+/// in one of these formats. By default, the format is determined by the file
+/// extension, but it can be overridden per test group with the `format` key in
+/// [`harness!`](crate::harness).
+///
+/// Each format is gated behind a cargo feature of the same name.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum Format {
+    /// JSON, parsed with [`serde_json`]. Used for `.json` files.
+    #[cfg(feature = "json")]
+    Json,
+
+    /// TOML, parsed with [`toml`]. Used for `.toml` files.
+    #[cfg(feature = "toml")]
+    Toml,
+
+    /// YAML, parsed with [`serde_norway`], a maintained fork of `serde_yaml`. Used for `.yaml` and `.yml` files.
+    #[cfg(feature = "yaml")]
+    Yaml,
+}
+
+impl Format {
+    /// Determines the format from a file extension, if it is recognized and
+    /// the corresponding feature is enabled.
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        match extension {
+            #[cfg(feature = "json")]
+            "json" => Some(Format::Json),
+            #[cfg(feature = "toml")]
+            "toml" => Some(Format::Toml),
+            #[cfg(feature = "yaml")]
+            "yaml" | "yml" => Some(Format::Yaml),
+            _ => None,
+        }
+    }
+
+    /// Deserializes `contents` in this format, reporting parse errors along
+    /// with the file name and the location of the error.
+    pub(crate) fn deserialize<T: DeserializeOwned>(
+        self,
+        path: &Utf8Path,
+        contents: &[u8],
+    ) -> Result<T> {
+        let error = match self {
+            #[cfg(feature = "json")]
+            Format::Json => match serde_json::from_slice(contents) {
+                Ok(value) => return Ok(value),
+                Err(error) => ParseError {
+                    location: (error.line() > 0).then(|| (error.line(), error.column())),
+                    message: error.to_string(),
+                },
+            },
+            #[cfg(feature = "toml")]
+            Format::Toml => {
+                let contents = std::str::from_utf8(contents)
+                    .map_err(|err| format!("error reading file '{path}' as UTF-8: {err}"))?;
+                match toml::from_str(contents) {
+                    Ok(value) => return Ok(value),
+                    Err(error) => ParseError {
+                        location: error.span().map(|span| line_column(contents, span.start)),
+                        message: error.message().to_owned(),
+                    },
+                }
+            }
+            #[cfg(feature = "yaml")]
+            Format::Yaml => match serde_norway::from_slice(contents) {
+                Ok(value) => return Ok(value),
+                Err(error) => ParseError {
+                    location: error.location().map(|loc| (loc.line(), loc.column())),
+                    message: error.to_string(),
+                },
+            },
+        };
+
+        Err(error.describe(self, path).into())
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            #[cfg(feature = "json")]
+            Format::Json => f.write_str("JSON"),
+            #[cfg(feature = "toml")]
+            Format::Toml => f.write_str("TOML"),
+            #[cfg(feature = "yaml")]
+            Format::Yaml => f.write_str("YAML"),
+        }
+    }
+}
+
+struct ParseError {
+    /// The 1-based line and column of the error, if known.
+    location: Option<(usize, usize)>,
+    message: String,
+}
+
+impl ParseError {
+    fn describe(self, format: Format, path: &Utf8Path) -> String {
+        match self.location {
+            Some((line, column)) => {
+                // serde_json and serde_norway include the location at the end of
+                // their messages -- strip it to avoid repeating it.
+                let suffix = format!(" at line {line} column {column}");
+                let message = self.message.strip_suffix(&suffix).unwrap_or(&self.message);
+                format!(
+                    "error parsing '{path}' as {format} at line {line}, column {column}: {message}"
+                )
+            }
+            None => format!("error parsing '{path}' as {format}: {}", self.message),
+        }
+    }
+}
+
+/// Converts a byte offset into a 1-based line and column.
+#[cfg(feature = "toml")]
+fn line_column(contents: &str, offset: usize) -> (usize, usize) {
+    let before = &contents[..offset.min(contents.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before
+        .rfind('\n')
+        .map_or(before.len(), |i| before.len() - i - 1)
+        + 1;
+    (line, column)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Fixture {
+        name: String,
+        count: u32,
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn json_error_location() {
+        let error = Format::Json
+            .deserialize::<Fixture>(
+                "a.json".into(),
+                b"{\n  \"name\": \"x\",\n  \"count\": -1\n}",
+            )
+            .unwrap_err();
+        let message = error.to_string();
+        assert!(
+            message.starts_with("error parsing 'a.json' as JSON at line 3, column 13: "),
+            "message: {message}"
+        );
+        assert!(!message.contains("at line 3 column"), "message: {message}");
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn toml_error_location() {
+        let fixture = Format::Toml
+            .deserialize::<Fixture>("a.toml".into(), b"name = \"x\"\ncount = 3\n")
+            .expect("valid TOML");
+        assert_eq!(
+            fixture,
+            Fixture {
+                name: "x".to_owned(),
+                count: 3
+            }
+        );
+
+        let error = Format::Toml
+            .deserialize::<Fixture>("a.toml".into(), b"name = \"x\"\ncount = \"3\"\n")
+            .unwrap_err();
+        let message = error.to_string();
+        assert!(
+            message.starts_with("error parsing 'a.toml' as TOML at line 2, column 9: "),
+            "message: {message}"
+        );
+    }
+
+    #[cfg(feature = "yaml")]
+    #[test]
+    fn yaml_error_location() {
+        let error = Format::Yaml
+            .deserialize::<Fixture>("a.yaml".into(), b"name: x\ncount: [1]\n")
+            .unwrap_err();
+        let message = error.to_string();
+        assert!(
+            message.starts_with("error parsing 'a.yaml' as YAML at line 2, column 8: "),
+            "message: {message}"
+        );
+    }
+}
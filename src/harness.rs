@@ -0,0 +1,680 @@
+// Copyright (c) The datatest-stable Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+#[cfg(any(feature = "json", feature = "toml", feature = "yaml"))]
+use crate::Format;
+use crate::{
+    data_source::{RootEnv, WalkFilter},
+    naming::CaseNames,
+    orphans::OrphanCheck,
+    pairing::{OnMissing, Template},
+    pattern::Pattern,
+    runner::ExpectedArg,
+    DataSource, Executor, TestFn, Unit,
+};
+use camino::Utf8Path;
+use std::process::ExitCode;
+
+/// A test harness built up at runtime, as an alternative to
+/// [`harness!`](crate::harness).
+///
+/// This is useful when the set of test groups isn't known until the harness
+/// starts, for example to create one group per subdirectory of a corpus. See
+/// the [crate documentation](crate#building-a-harness-at-runtime) for an
+/// example.
+#[derive(Default)]
+pub struct Harness {
+    groups: Vec<TestGroup>,
+    orphans: Option<OrphanCheck>,
+    executor: Option<Box<dyn Executor>>,
+}
+
+impl Harness {
+    /// Creates a new harness with no test groups.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a group of tests to the harness.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the test function can't be used with the group's root and
+    /// unit. For example, test data embedded via `include_dir` must be passed
+    /// to the test function, so test functions that only accept a path can't
+    /// be used with it.
+    ///
+    /// Also panics if the group's `expected` or `case_name` template uses a
+    /// capture group that `pattern` doesn't have, or if another group in the
+    /// harness has the same name.
+    pub fn group(mut self, mut group: TestGroup) -> Self {
+        group.check_test_fn();
+        group.check_captures();
+        if self
+            .groups
+            .iter()
+            .any(|other| other.test_name == group.test_name)
+        {
+            panic!(
+                "more than one test group is named '{}' -- give each group a unique name \
+                 with `name = \"...\"` in `harness!`, or in `TestGroup::new`",
+                group.test_name
+            );
+        }
+        self.groups.push(group);
+        self
+    }
+
+    /// Enables a check for fixture files under the groups' roots that no group
+    /// matched.
+    ///
+    /// See [`OrphanCheck`] for details.
+    pub fn orphans(mut self, check: OrphanCheck) -> Self {
+        self.orphans = Some(check);
+        self
+    }
+
+    /// Sets the executor that runs async test functions.
+    ///
+    /// By default, async tests run on a multi-threaded Tokio runtime with the
+    /// `tokio` feature, and on a [`BlockingExecutor`](crate::BlockingExecutor)
+    /// otherwise. See [`Executor`] for details.
+    ///
+    /// # Panics
+    ///
+    /// [`run`](Self::run) panics if another harness in the same process
+    /// already set an executor.
+    pub fn executor(mut self, executor: impl Executor + 'static) -> Self {
+        self.executor = Some(Box::new(executor));
+        self
+    }
+
+    /// Parses command-line arguments, then runs all tests in the harness.
+    ///
+    /// With the `macros` feature, groups declared with
+    /// [`#[files]`](crate::files) are added to the harness first.
+    ///
+    /// The returned [`ExitCode`] indicates whether all tests passed, and should
+    /// be returned from `main`.
+    pub fn run(mut self) -> ExitCode {
+        #[cfg(feature = "macros")]
+        for group in crate::registry::registered_groups() {
+            self = self.group(group);
+        }
+        if let Some(executor) = self.executor.take() {
+            crate::executor::set_executor(executor);
+        }
+        crate::runner::run(&self.groups, self.orphans.as_ref())
+    }
+}
+
+/// A test function run against every fixture in a directory that matches a
+/// pattern.
+///
+/// This is the runtime equivalent of one `{ test = ..., root = ..., ... }`
+/// entry in [`harness!`](crate::harness). Each key supported by the macro has
+/// a method of the same name here.
+pub struct TestGroup {
+    pub(crate) test: TestFn,
+    pub(crate) test_name: String,
+    pub(crate) roots: Vec<Root>,
+    root_env: Option<RootEnv>,
+    pub(crate) optional: bool,
+    pub(crate) allow_empty: bool,
+    pub(crate) pattern: Option<Pattern>,
+    pub(crate) exclude: Vec<String>,
+    pub(crate) unit: Unit,
+    pub(crate) hidden: Option<bool>,
+    pub(crate) gitignore: bool,
+    pub(crate) sections: bool,
+    pub(crate) directives: bool,
+    pub(crate) expected: Option<Template>,
+    pub(crate) on_missing: OnMissing,
+    pub(crate) names: CaseNames,
+}
+
+impl TestGroup {
+    /// Creates a new group that runs `test` against every file under `root`.
+    ///
+    /// `name` is used as the prefix for the names of tests in this group,
+    /// similar to the name of the test function in `harness!`. `test` is
+    /// created with [`test_fn!`](crate::test_fn).
+    pub fn new(name: impl Into<String>, test: TestFn, root: impl Into<DataSource>) -> Self {
+        Self::with_root_list(
+            name.into(),
+            test,
+            vec![Root {
+                label: None,
+                source: root.into(),
+            }],
+        )
+    }
+
+    /// Creates a new group that runs `test` against every file under each of
+    /// several roots.
+    ///
+    /// Each root is given a label, which follows the group's name in the names
+    /// of its tests, as in `name::label::path`. This keeps the names of tests
+    /// from different roots apart, even if the roots contain the same paths.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `roots` is empty, or if a label is empty, contains `::`, or
+    /// is used for more than one root.
+    pub fn with_roots<L: Into<String>>(
+        name: impl Into<String>,
+        test: TestFn,
+        roots: impl IntoIterator<Item = (L, DataSource)>,
+    ) -> Self {
+        let name = name.into();
+        let mut labelled: Vec<Root> = Vec::new();
+        for (label, source) in roots {
+            let label = label.into();
+            if label.is_empty() || label.contains("::") {
+                panic!(
+                    "invalid label '{label}' for a root of '{name}' -- labels must be \
+                     non-empty, and can't contain `::`"
+                );
+            }
+            if labelled
+                .iter()
+                .any(|root| root.label.as_deref() == Some(label.as_str()))
+            {
+                panic!(
+                    "more than one root of '{name}' is labelled '{label}' -- give each \
+                     root a unique label"
+                );
+            }
+            labelled.push(Root {
+                label: Some(label),
+                source,
+            });
+        }
+        if labelled.is_empty() {
+            panic!("no roots were given for '{name}'");
+        }
+        Self::with_root_list(name, test, labelled)
+    }
+
+    fn with_root_list(test_name: String, test: TestFn, roots: Vec<Root>) -> Self {
+        Self {
+            test,
+            test_name,
+            roots,
+            root_env: None,
+            optional: false,
+            allow_empty: false,
+            pattern: None,
+            exclude: Vec::new(),
+            unit: Unit::File,
+            hidden: None,
+            gitignore: false,
+            sections: false,
+            directives: false,
+            expected: None,
+            on_missing: OnMissing::Fail,
+            names: CaseNames::default(),
+        }
+    }
+
+    /// Replaces the name passed to [`new`](Self::new), which is used as the
+    /// prefix for the names of tests in this group.
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.test_name = name.into();
+        self
+    }
+
+    /// Lets the environment variable `var` override the roots of this group at
+    /// runtime.
+    ///
+    /// If `var` is set to a non-empty value, it's used as the path to a root
+    /// that replaces the roots passed to [`new`](Self::new) or
+    /// [`with_roots`](Self::with_roots). Otherwise, the roots are kept. Either
+    /// way, messages about the root, such as the one when no test cases are
+    /// found, mention `var`.
+    ///
+    /// `var` is read when this method is called, not when the harness runs.
+    /// `harness!` calls it while setting up the harness in `main`, so for
+    /// groups declared there, this makes no difference.
+    ///
+    /// The path is to a directory, or with the `archive-tar` or `archive-zip`
+    /// features, to an [`Archive`](crate::Archive) if it has the extension of
+    /// one. As with `root`, relative paths are relative to the crate root. If
+    /// the group had several roots, the names of its tests no longer have
+    /// labels.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `var` is set to a value that isn't valid UTF-8.
+    pub fn root_env(self, var: impl Into<String>) -> Self {
+        let var = var.into();
+        let value = match std::env::var(&var) {
+            Ok(value) => Some(value),
+            Err(std::env::VarError::NotPresent) => None,
+            Err(std::env::VarError::NotUnicode(value)) => panic!(
+                "`${var}` was set to override the root of '{}', but its value {value:?} isn't \
+                 valid UTF-8",
+                self.test_name
+            ),
+        };
+        self.root_env_with(var, value)
+    }
+
+    /// Implements [`root_env`](Self::root_env), given the value of `var`
+    /// rather than reading it from the environment.
+    pub(crate) fn root_env_with(mut self, var: String, value: Option<String>) -> Self {
+        let value = value.filter(|value| !value.is_empty());
+        let is_set = value.is_some();
+        if let Some(value) = value {
+            self.roots = vec![Root {
+                label: None,
+                source: DataSource::from_root_env(&value),
+            }];
+        }
+        self.root_env = Some(RootEnv { var, is_set });
+        self
+    }
+
+    /// If true, a root that doesn't exist is skipped rather than causing a
+    /// panic, for corpora that are only present on some machines.
+    ///
+    /// In place of the tests under a missing root, a single test named
+    /// `name::(missing root)` (or `name::label::(missing root)` for a group
+    /// with several roots) is reported as ignored, with the reason why.
+    ///
+    /// Defaults to false.
+    pub fn optional(mut self, optional: bool) -> Self {
+        self.optional = optional;
+        self
+    }
+
+    /// If true, the group may have no test cases.
+    ///
+    /// By default, the harness reports an error and exits without running any
+    /// tests if no files under the root match, which catches typos in
+    /// patterns. This turns that off, for example for a directory of known
+    /// regressions that's empty when all is well.
+    ///
+    /// Defaults to false.
+    pub fn allow_empty(mut self, allow_empty: bool) -> Self {
+        self.allow_empty = allow_empty;
+        self
+    }
+
+    /// Sets the regular expression that paths relative to the root must match.
+    ///
+    /// Defaults to `.*`, matching every file.
+    ///
+    /// # Panics
+    ///
+    /// Panics if [`glob`](Self::glob) was also called.
+    pub fn pattern(mut self, pattern: impl Into<String>) -> Self {
+        self.set_pattern(Pattern::Regex(pattern.into()));
+        self
+    }
+
+    /// Sets a glob that paths relative to the root must match, as an
+    /// alternative to [`pattern`](Self::pattern).
+    ///
+    /// Unlike a regex, a glob must match the whole path. `*` matches within a
+    /// single path component, while `**` matches across components, so
+    /// `**/*.txt` matches `.txt` files at any depth. See the [crate
+    /// documentation](crate#globs) for the full syntax.
+    ///
+    /// # Panics
+    ///
+    /// Panics if [`pattern`](Self::pattern) was also called.
+    pub fn glob(mut self, glob: impl Into<String>) -> Self {
+        self.set_pattern(Pattern::Glob(glob.into()));
+        self
+    }
+
+    /// Adds a regular expression for paths to exclude, even if they match the
+    /// pattern.
+    ///
+    /// This can be called several times to exclude paths matching any of
+    /// several patterns.
+    pub fn exclude(mut self, pattern: impl Into<String>) -> Self {
+        self.exclude.push(pattern.into());
+        self
+    }
+
+    /// Sets what each test case corresponds to: a file (the default), or an
+    /// immediate subdirectory of the root.
+    pub fn unit(mut self, unit: Unit) -> Self {
+        self.unit = unit;
+        self
+    }
+
+    /// If true, hidden files and directories (those with names starting with
+    /// `.`) are test cases too.
+    ///
+    /// Defaults to false for directories on disk, and to true for in-memory
+    /// sources such as `include_dir` directories, which have always included
+    /// hidden files.
+    pub fn hidden(mut self, hidden: bool) -> Self {
+        self.hidden = Some(hidden);
+        self
+    }
+
+    /// If true, skips paths that are ignored by `.gitignore`, `.ignore` or
+    /// `.datatestignore` files under the root.
+    ///
+    /// Ignore files use `.gitignore` syntax, and apply to the directory they're
+    /// in and its descendants. Rules in `.datatestignore` take precedence over
+    /// `.ignore`, which take precedence over `.gitignore`. Ignore files outside
+    /// the root aren't read. See the [crate
+    /// documentation](crate#ignore-files) for more.
+    pub fn gitignore(mut self, gitignore: bool) -> Self {
+        self.gitignore = gitignore;
+        self
+    }
+
+    /// If true, splits each file into several test cases, one per section.
+    ///
+    /// Each section starts with a `=== name ===` header line, and becomes a
+    /// test named `group::path/to/file.txt::name`. The test function is passed
+    /// the contents of the section rather than of the whole file, so it must
+    /// accept file contents as an argument.
+    pub fn sections(mut self, sections: bool) -> Self {
+        self.sections = sections;
+        self
+    }
+
+    /// If true, reads per-fixture directives that mark tests as ignored or
+    /// expected to fail.
+    ///
+    /// Directives are read from a `// datatest: ignore, should-fail` comment
+    /// on the first line of each file (or section), and from a sidecar file
+    /// `<file>.datatest.toml` next to it. See the [crate
+    /// documentation](crate#per-fixture-directives) for more.
+    pub fn directives(mut self, directives: bool) -> Self {
+        self.directives = directives;
+        self
+    }
+
+    /// Pairs each input file with an expected file, whose path relative to
+    /// the root is given by `template`.
+    ///
+    /// The template can contain the placeholders `{path}`, `{stem}`,
+    /// `{file_name}`, `{file_stem}`, and `{extension}`, which are replaced with
+    /// parts of the input's relative path. For example, `{stem}.out` pairs
+    /// `nested/foo.in` with `nested/foo.out`. Any other `{name}` is replaced
+    /// with the text matched by the named group `name` in
+    /// [`pattern`](Self::pattern). See the [crate
+    /// documentation](crate#paired-input-and-expected-files) for more.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the template is invalid. [`Harness::group`] panics if the
+    /// template uses a capture group that the pattern doesn't have.
+    pub fn expected(mut self, template: impl AsRef<str>) -> Self {
+        let template = template.as_ref();
+        self.expected = Some(Template::parse(template).unwrap_or_else(|error| {
+            panic!(
+                "invalid `expected` template '{}' for '{}': {}",
+                template, self.test_name, error
+            )
+        }));
+        self
+    }
+
+    /// Sets what to do when an input file doesn't have an expected file.
+    ///
+    /// Defaults to [`OnMissing::Fail`].
+    pub fn on_missing(mut self, on_missing: OnMissing) -> Self {
+        self.on_missing = on_missing;
+        self
+    }
+
+    /// Sets a template for the names of test cases, such as `{stem}` to leave
+    /// out file extensions.
+    ///
+    /// The name of each test is the name of the group, followed by `::` and the
+    /// expanded template. The template must contain exactly one `{path}` or
+    /// `{stem}` placeholder, so that tests can be looked up by name without
+    /// scanning the root. Defaults to `{path}`.
+    ///
+    /// Alternatively, the template can use only named groups from
+    /// [`pattern`](Self::pattern), such as `{dialect}::{case}` with the pattern
+    /// `^(?P<dialect>mysql|pg)/(?P<case>.+)\.sql$`. In that case, the pattern
+    /// must be anchored with `^` and `$`, consist only of literal text and
+    /// named groups, and every group must appear in the template, so that
+    /// paths can be rebuilt from names. [`strip_prefix`](Self::strip_prefix)
+    /// has no effect on such templates.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the template is invalid. [`Harness::group`] panics if the
+    /// template uses capture groups that don't fit the pattern as described
+    /// above.
+    pub fn case_name(mut self, template: impl AsRef<str>) -> Self {
+        let template = template.as_ref();
+        self.names.set_template(template).unwrap_or_else(|error| {
+            panic!(
+                "invalid `case_name` template '{}' for '{}': {}",
+                template, self.test_name, error
+            )
+        });
+        self
+    }
+
+    /// Derives the names of test cases with a function, as an alternative to
+    /// [`case_name`](Self::case_name).
+    ///
+    /// `to_name` returns the name of the case for a path relative to the root,
+    /// and `from_name` must be its inverse, returning the path for a name. The
+    /// inverse is used to look up tests by name, as nextest does, without
+    /// scanning the root.
+    pub fn case_name_fn(
+        mut self,
+        to_name: impl Fn(&Utf8Path) -> String + Send + Sync + 'static,
+        from_name: impl Fn(&str) -> Option<String> + Send + Sync + 'static,
+    ) -> Self {
+        self.names.set_custom(to_name, from_name);
+        self
+    }
+
+    /// Removes a leading directory from paths before deriving the names of
+    /// test cases.
+    ///
+    /// For example, with `strip_prefix("cases")`, the file `cases/foo.txt` is
+    /// named `foo.txt`. Paths outside the directory are left as they are.
+    pub fn strip_prefix(mut self, prefix: impl AsRef<str>) -> Self {
+        self.names.set_strip_prefix(prefix.as_ref());
+        self
+    }
+
+    /// Overrides the format that fixtures are deserialized from.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the test function doesn't accept a deserialized value as an
+    /// argument.
+    #[cfg(any(feature = "json", feature = "toml", feature = "yaml"))]
+    pub fn format(mut self, format: Format) -> Self {
+        match &mut self.test {
+            TestFn::Deserialize(f) => f.format = Some(format),
+            _ => panic!(
+                "`format` was specified for '{}', but it doesn't \
+                 accept a deserialized value as an argument",
+                self.test_name
+            ),
+        }
+        self
+    }
+
+    pub(crate) fn walk_filter(&self, source: &DataSource) -> WalkFilter {
+        WalkFilter {
+            hidden: self.hidden.unwrap_or_else(|| source.is_in_memory()),
+            ignore_files: self.gitignore,
+        }
+    }
+
+    fn set_pattern(&mut self, pattern: Pattern) {
+        match (&self.pattern, &pattern) {
+            (Some(Pattern::Regex(_)), Pattern::Glob(_))
+            | (Some(Pattern::Glob(_)), Pattern::Regex(_)) => panic!(
+                "`pattern` and `glob` were both specified for '{}', but they \
+                 are mutually exclusive",
+                self.test_name
+            ),
+            _ => self.pattern = Some(pattern),
+        }
+    }
+
+    /// Checks the capture groups used by templates against the pattern, and
+    /// prepares `case_name` templates that use them to be inverted.
+    fn check_captures(&mut self) {
+        if let Some(template) = &self.expected {
+            let mut names = template.capture_names().peekable();
+            if names.peek().is_some() {
+                let group_names = match &self.pattern {
+                    Some(pattern @ Pattern::Regex(_)) => pattern
+                        .compile()
+                        .capture_names()
+                        .flatten()
+                        .map(str::to_owned)
+                        .collect(),
+                    _ => Vec::new(),
+                };
+                if let Some(name) = names.find(|name| !group_names.iter().any(|n| n == name)) {
+                    panic!(
+                        "invalid `expected` template for '{}': `pattern` has no capture \
+                         group named `{}`",
+                        self.test_name, name
+                    );
+                }
+            }
+        }
+
+        if let Err(error) = self.names.bind_pattern(self.pattern.as_ref()) {
+            panic!(
+                "invalid `case_name` template for '{}': {}",
+                self.test_name, error
+            );
+        }
+    }
+
+    fn check_test_fn(&self) {
+        if self.sections && !self.test.loads_data() {
+            panic!(
+                "`sections` was specified for '{}', but it doesn't \
+                 accept file contents as an argument",
+                self.test_name
+            );
+        }
+        if self.sections && self.unit == Unit::Dir {
+            panic!(
+                "`sections` was specified for '{}', but it can't be \
+                 combined with `unit = dir`",
+                self.test_name
+            );
+        }
+
+        match (&self.expected, self.test.expected_arg()) {
+            (Some(_), None) => panic!(
+                "`expected` was specified for '{}', but it doesn't accept \
+                 the contents of an expected file as an argument",
+                self.test_name
+            ),
+            (None, Some(_)) => panic!(
+                "'{}' accepts the contents of an expected file as an \
+                 argument, so it requires `expected`",
+                self.test_name
+            ),
+            (Some(_), Some(ExpectedArg::Required)) if self.on_missing == OnMissing::PassNone => {
+                panic!(
+                    "`on_missing = none` was specified for '{}', but it \
+                     doesn't accept an `Option` for the expected file",
+                    self.test_name
+                )
+            }
+            _ => {}
+        }
+        if self.expected.is_some() && self.sections {
+            panic!(
+                "`sections` was specified for '{}', but it can't be \
+                 combined with `expected`",
+                self.test_name
+            );
+        }
+
+        match self.unit {
+            Unit::File => {
+                if self.test.loads_included_dir() {
+                    panic!(
+                        "'{}' accepts an included directory as an argument, \
+                         so it requires `unit = dir`",
+                        self.test_name
+                    );
+                }
+                // include_dir data sources aren't compatible with test
+                // functions that don't accept the contents as an argument.
+                if !self.test.loads_data()
+                    && self.roots.iter().any(|root| root.source.is_in_memory())
+                {
+                    panic!(
+                        "test data for '{}' is stored in memory, so it \
+                         must accept file contents as an argument",
+                        self.test_name
+                    );
+                }
+            }
+            Unit::Dir => {
+                if self.roots.iter().any(|root| root.source.is_archive()) {
+                    panic!(
+                        "`unit = dir` was specified for '{}', but its root is an \
+                         archive, whose members can only be test cases as files",
+                        self.test_name
+                    );
+                }
+                if self.test.loads_data() {
+                    panic!(
+                        "`unit = dir` was specified for '{}', but it \
+                         accepts file contents as an argument",
+                        self.test_name
+                    );
+                }
+                for root in &self.roots {
+                    match (self.test.loads_included_dir(), root.source.is_in_memory()) {
+                        (false, true) => panic!(
+                            "test data for '{}' is stored in memory, so it \
+                             must accept the included directory as an argument",
+                            self.test_name
+                        ),
+                        (true, false) => panic!(
+                            "'{}' accepts an included directory as an argument, \
+                             but its root is on disk",
+                            self.test_name
+                        ),
+                        _ => {}
+                    }
+                }
+            }
+        }
+    }
+
+    /// Describes `root` in messages, along with how to override it if
+    /// [`root_env`](Self::root_env) was called.
+    pub(crate) fn display_root(&self, root: &Root) -> String {
+        root.source.display(self.root_env.as_ref())
+    }
+}
+
+/// One of the roots of a test group, along with its label if the group has
+/// several roots.
+pub(crate) struct Root {
+    pub(crate) label: Option<String>,
+    pub(crate) source: DataSource,
+}
+
+impl Root {
+    /// Returns the prefix for the names of tests under this root.
+    pub(crate) fn test_name(&self, group_name: &str) -> String {
+        match &self.label {
+            Some(label) => format!("{group_name}::{label}"),
+            None => group_name.to_owned(),
+        }
+    }
+}
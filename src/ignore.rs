@@ -0,0 +1,210 @@
+// Copyright (c) The datatest-stable Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Support for `.gitignore`-style ignore files in fixture roots.
+
+use crate::pattern::glob_to_regex;
+use std::collections::HashMap;
+
+/// The names of ignore files, in increasing order of precedence.
+pub(crate) const IGNORE_FILES: &[&str] = &[".gitignore", ".ignore", ".datatestignore"];
+
+/// Reads the file at a path relative to the root, returning `Ok(None)` if it
+/// doesn't exist.
+type ReadFn<'a> = dyn Fn(&str) -> std::io::Result<Option<String>> + 'a;
+
+/// Ignore rules read lazily from the ignore files under a root.
+pub(crate) struct IgnoreFiles<'a> {
+    read: Box<ReadFn<'a>>,
+    /// Rules from the ignore files in each directory, keyed by the path of the
+    /// directory relative to the root.
+    rules: HashMap<String, Rules>,
+}
+
+impl<'a> IgnoreFiles<'a> {
+    pub(crate) fn new(read: impl Fn(&str) -> std::io::Result<Option<String>> + 'a) -> Self {
+        Self {
+            read: Box::new(read),
+            rules: HashMap::new(),
+        }
+    }
+
+    /// Returns true if `rel_path` is ignored by the ignore files in its
+    /// ancestors.
+    ///
+    /// The directories containing `rel_path` are assumed to not be ignored
+    /// themselves, as is the case while walking a root with ignored
+    /// directories skipped.
+    pub(crate) fn is_ignored(&mut self, rel_path: &str, is_dir: bool) -> std::io::Result<bool> {
+        let dirs = std::iter::once("").chain(
+            rel_path
+                .match_indices('/')
+                .map(|(index, _)| &rel_path[..index]),
+        );
+        let dirs: Vec<_> = dirs.collect();
+
+        // Rules in deeper directories take precedence.
+        for dir in dirs.into_iter().rev() {
+            let path_in_dir = if dir.is_empty() {
+                rel_path
+            } else {
+                &rel_path[dir.len() + 1..]
+            };
+            if let Some(ignored) = self.rules(dir)?.matches(path_in_dir, is_dir) {
+                return Ok(ignored);
+            }
+        }
+        Ok(false)
+    }
+
+    fn rules(&mut self, dir: &str) -> std::io::Result<&Rules> {
+        if !self.rules.contains_key(dir) {
+            let mut rules = Rules::default();
+            for name in IGNORE_FILES {
+                let path = if dir.is_empty() {
+                    (*name).to_owned()
+                } else {
+                    format!("{dir}/{name}")
+                };
+                if let Some(contents) = (self.read)(&path)? {
+                    rules.parse(&contents).map_err(|error| {
+                        std::io::Error::new(
+                            std::io::ErrorKind::InvalidData,
+                            format!("error parsing ignore file '{path}': {error}"),
+                        )
+                    })?;
+                }
+            }
+            self.rules.insert(dir.to_owned(), rules);
+        }
+        Ok(&self.rules[dir])
+    }
+}
+
+/// The rules from the ignore files in a single directory.
+#[derive(Default)]
+struct Rules {
+    rules: Vec<Rule>,
+}
+
+struct Rule {
+    regex: fancy_regex::Regex,
+    /// Whether the rule starts with `!`, re-including paths.
+    negated: bool,
+    /// Whether the rule ends with `/`, only matching directories.
+    dir_only: bool,
+    /// Whether the rule contains a `/`, and is matched against the whole path
+    /// rather than just the file name.
+    anchored: bool,
+}
+
+impl Rules {
+    /// Parses the lines of an ignore file, appending them to the existing
+    /// rules.
+    fn parse(&mut self, contents: &str) -> Result<(), String> {
+        for (index, line) in contents.lines().enumerate() {
+            let line = trim_trailing_spaces(line);
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (negated, line) = match line.strip_prefix('!') {
+                Some(line) => (true, line),
+                None => (false, line),
+            };
+            let (dir_only, line) = match line.strip_suffix('/') {
+                Some(line) => (true, line),
+                None => (false, line),
+            };
+            let anchored = line.contains('/');
+            let line = line.strip_prefix('/').unwrap_or(line);
+
+            let regex = glob_to_regex(line, false)
+                .and_then(|regex| fancy_regex::Regex::new(&regex).map_err(|err| err.to_string()))
+                .map_err(|error| format!("line {}: {error}", index + 1))?;
+            self.rules.push(Rule {
+                regex,
+                negated,
+                dir_only,
+                anchored,
+            });
+        }
+        Ok(())
+    }
+
+    /// Returns `Some(true)` if the path is ignored, `Some(false)` if it is
+    /// explicitly re-included, and `None` if no rule matches it.
+    fn matches(&self, rel_path: &str, is_dir: bool) -> Option<bool> {
+        let file_name = rel_path.rsplit('/').next().unwrap_or(rel_path);
+        // The last matching rule wins.
+        self.rules.iter().rev().find_map(|rule| {
+            if rule.dir_only && !is_dir {
+                return None;
+            }
+            let path = if rule.anchored { rel_path } else { file_name };
+            // Globs never use backtracking features, so matching can't fail.
+            let is_match = rule.regex.is_match(path).unwrap_or(false);
+            is_match.then_some(!rule.negated)
+        })
+    }
+}
+
+fn trim_trailing_spaces(line: &str) -> &str {
+    let trimmed = line.trim_end_matches(' ');
+    // A trailing space is kept if it's escaped with a backslash.
+    if trimmed.ends_with('\\') && trimmed.len() < line.len() {
+        &line[..trimmed.len() + 1]
+    } else {
+        trimmed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ignore_rules() {
+        let files: HashMap<&str, &str> = [
+            (".gitignore", "# comment\n*.swp\n/out/\nbuild\n!keep.swp\n"),
+            (".datatestignore", "*.orig\nnested/*.txt\n"),
+            ("nested/.ignore", "!*.orig\n"),
+            ("nested/deeper/.datatestignore", "\\#*\n"),
+        ]
+        .into_iter()
+        .collect();
+        let mut ignore = IgnoreFiles::new(|path| Ok(files.get(path).map(|s| s.to_string())));
+
+        for (path, is_dir, expected) in [
+            ("a.txt", false, false),
+            ("a.swp", false, true),
+            ("dir/a.swp", false, true),
+            ("keep.swp", false, false),
+            ("out", true, true),
+            ("out", false, false),
+            ("dir/out", true, false),
+            ("build", false, true),
+            ("dir/build", true, true),
+            ("a.orig", false, true),
+            ("nested/a.orig", false, false),
+            ("nested/a.txt", false, true),
+            ("nested/deeper/a.txt", false, false),
+            ("nested/deeper/#a", false, true),
+        ] {
+            assert_eq!(
+                ignore.is_ignored(path, is_dir).unwrap(),
+                expected,
+                "{path} (is_dir: {is_dir})"
+            );
+        }
+    }
+
+    #[test]
+    fn invalid_rule() {
+        let mut ignore = IgnoreFiles::new(|path| Ok((path == ".ignore").then(|| "[abc\n".into())));
+        assert_eq!(
+            ignore.is_ignored("a", false).unwrap_err().to_string(),
+            "error parsing ignore file '.ignore': line 1: unclosed `[`"
+        );
+    }
+}
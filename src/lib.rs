@@ -38,6 +38,9 @@
 //!   }
 //!   ```
 //!
+//!   `test` and `root` must come first, in that order. The other arguments can follow in any
+//!   order.
+//!
 //! * `test` - The test function to be executed on each matching input. This function can be one
 //!   of:
 //!   * `fn(&Path) -> datatest_stable::Result<()>`
@@ -49,6 +52,37 @@
 //!   * `fn(&P, Vec<u8>) -> datatest_stable::Result<()>` where `P` is `Path` or `Utf8Path`. If the
 //!     extra `Vec<u8>` parameter is specified, the contents of the file will be loaded and passed
 //!     in as a `Vec<u8>` (erroring out if that failed).
+//!   * `fn(&P, C, &Snapshot) -> datatest_stable::Result<()>` where `P` is `Path` or `Utf8Path`,
+//!     and `C` is `String` or `Vec<u8>`. The contents are loaded as above, and a [`Snapshot`] is
+//!     passed in to compare output against an expected file. See [*Golden-file
+//!     snapshots*](#golden-file-snapshots) below.
+//!   * `fn(&P, C, C) -> datatest_stable::Result<()>` or `fn(&P, C, Option<C>) ->
+//!     datatest_stable::Result<()>`, where `P` is `Path` or `Utf8Path`, and `C` is `String` or
+//!     `Vec<u8>`. The contents of the file and of its paired expected file are passed in. Requires
+//!     `expected`; see [*Paired input and expected files*](#paired-input-and-expected-files) below.
+//!   * `fn(&P, T) -> datatest_stable::Result<()>` where `P` is `Path` or `Utf8Path`, and `T`
+//!     implements [`serde::de::DeserializeOwned`]. The contents of the file will be deserialized
+//!     into a `T`, and parse errors are reported along with the line and column where they
+//!     occurred. Requires one of the `json`, `toml`, or `yaml` features. See [*Deserializing
+//!     fixtures*](#deserializing-fixtures) below.
+//!   * `fn(&TestContext) -> datatest_stable::Result<()>`, or `fn(&TestContext, C) ->
+//!     datatest_stable::Result<()>` where `C` is `String` or `Vec<u8>`. Instead of a path, a
+//!     [`TestContext`] is passed in, which provides the test case's paths, the group name, and the
+//!     named groups captured by `pattern`. See [*Test context*](#test-context) below.
+//!
+//!   Instead of `datatest_stable::Result<()>`, test functions can return `()` and fail by
+//!   panicking (for example, with `assert!`), or return `Result<(), E>` for any `E` that implements
+//!   [`Debug`](std::fmt::Debug), such as `anyhow::Result<()>` or `Result<(), String>`. Errors that
+//!   convert into a `Box<dyn Error>` are reported using their `Display` implementation, followed by
+//!   their sources. Other errors are reported using `Debug`.
+//!
+//!   The first four shapes can also be `async fn`s. See [*Async test
+//!   functions*](#async-test-functions) below.
+//!
+//!   `test` is usually the path to a function, but it can be any expression that evaluates to a
+//!   closure of one of these shapes, as long as the closure is `Send + Sync + 'static`. Groups with
+//!   such a `test` must be given a `name`. See [*Sharing state between
+//!   tests*](#sharing-state-between-tests) below.
 //!
 //! * `root` - The path to the root directory where the input files (fixtures)
 //!   live. Relative paths are resolved relative to the crate root (the directory where the crate's
@@ -58,15 +92,99 @@
 //!   [`Display`](std::fmt::Display), such as `&str`, or a function call that
 //!   returns a [`Utf8PathBuf`](camino::Utf8PathBuf).
 //!
+//!   `root` can also be a list of sources, to run the same tests against
+//!   several roots. See [*Multiple roots*](#multiple-roots) below.
+//!
 //! * `pattern` - a regex used to match against and select each file to be tested. Extended regexes
 //!   with lookaround and backtracking are supported via the [`fancy_regex`] crate.
 //!
 //!   `pattern` is an arbitrary expression that implements [`Display`](std::fmt::Display), such as
-//!   `&str`, or a function call that returns a `String`.
+//!   `&str`, or a function call that returns a `String`. With the `macros` feature, a string
+//!   literal `pattern` is checked at compile time, and an invalid regex (including glob syntax
+//!   like `"*.txt"`) is reported as an error pointing at the literal. Other invalid regexes cause
+//!   a panic when the harness starts.
 //!
 //!   `pattern` is optional, and defaults to `r".*"` (match all files).
 //!
-//! The three parameters can be repeated if you have multiple sets of data-driven tests to be run:
+//! * `glob` - a glob used to select files, as an alternative to `pattern`. Unlike a regex, a glob
+//!   must match the whole relative path, so `glob = "**/*.txt"` selects `.txt` files at any depth.
+//!   See [*Globs*](#globs) below.
+//!
+//!   As with `pattern`, string literals are checked at compile time with the `macros` feature.
+//!
+//!   `pattern` and `glob` are mutually exclusive.
+//!
+//! * `exclude` - a regex, or an array of regexes, for files to skip even if they match `pattern`.
+//!   Like `pattern`, these are matched against the relative path of each file. This is often
+//!   easier to read than lookaround in `pattern`:
+//!
+//!   ```rust,ignore
+//!   { test = my_test, root = "tests/files", pattern = r"\.txt$", exclude = [r"\.skip\.txt$", "^wip/"] },
+//!   ```
+//!
+//!   As with `pattern`, string literals are checked at compile time with the `macros` feature.
+//!
+//!   `exclude` is optional, and by default no files are excluded.
+//!
+//! * `gitignore` - if `true`, files ignored by `.gitignore`, `.ignore`, or `.datatestignore` files
+//!   under `root` are skipped. Optional, and defaults to `false`. See [*Ignore
+//!   files*](#ignore-files) below.
+//!
+//! * `root_env` - the name of an environment variable that, if set to a non-empty value when the
+//!   harness starts, replaces `root` with the directory at that path, or the archive if the path
+//!   ends in an archive extension such as `.tar.gz`. This lets the same test binary run against a
+//!   small checked-in corpus locally, and a larger one elsewhere:
+//!
+//!   ```rust,ignore
+//!   { test = my_test, root = "tests/corpus", pattern = r"\.txt$", root_env = "MY_CORPUS" },
+//!   ```
+//!
+//!   Messages about the root, such as the one when no test cases are found, say whether it came
+//!   from the variable. Optional, and by default `root` can't be overridden.
+//!
+//! * `optional` - if `true`, a `root` that doesn't exist is skipped instead of causing a panic,
+//!   for corpora that are only checked out on some machines. A single test named
+//!   `my_test::(missing root)` is reported as ignored in its place, with the reason why. Optional,
+//!   and defaults to `false`.
+//!
+//! * `allow_empty` - if `true`, the group may have no test cases. By default, a group that finds no
+//!   test cases is reported as an error, and the harness exits without running any tests, which
+//!   catches typos in `pattern`. Optional, and defaults to `false`.
+//!
+//! * `hidden` - if `true`, hidden files and directories (those with names starting with `.`) are
+//!   test cases too. Optional, and defaults to `false` for directories on disk and `true` for
+//!   `include_dir` directories and archives.
+//!
+//! * `format` - for test functions that accept a deserialized value, the format to parse files
+//!   as: one of `json`, `toml`, or `yaml`. Optional, and by default the format is determined by
+//!   the file extension.
+//!
+//! * `unit` - what each test case corresponds to: `file` (the default), or `dir` to treat each
+//!   immediate subdirectory of `root` as a test case. See [*Directories as test
+//!   cases*](#directories-as-test-cases) below.
+//!
+//! * `sections` - if `true`, each file is split into several test cases, one per `=== name ===`
+//!   section. Optional, and defaults to `false`. See [*Multiple test cases per
+//!   file*](#multiple-test-cases-per-file) below.
+//!
+//! * `expected` - a template for the path to an expected file paired with each input, such as
+//!   `"{stem}.out"`, and `on_missing` - what to do if the expected file doesn't exist: `fail` (the
+//!   default), `skip`, or `none`. See [*Paired input and expected
+//!   files*](#paired-input-and-expected-files) below.
+//!
+//! * `directives` - if `true`, fixtures can mark themselves as ignored or expected to fail.
+//!   Optional, and defaults to `false`. See [*Per-fixture directives*](#per-fixture-directives)
+//!   below.
+//!
+//! * `name` - the prefix for the names of tests in this group. Defaults to the path passed as
+//!   `test`, such as `my_test` or `module::my_test`, and is required if `test` is any other
+//!   expression. Each group must have a unique name. See [*Test names*](#test-names) below.
+//!
+//! * `case_name` - a template for the names of test cases, such as `"{stem}"`, and
+//!   `strip_prefix` - a directory to remove from the start of names. Optional. See [*Test
+//!   names*](#test-names) below.
+//!
+//! The parameters can be repeated if you have multiple sets of data-driven tests to be run:
 //!
 //! ```rust,ignore
 //! datatest_stable::harness! {
@@ -77,6 +195,12 @@
 //!
 //! Trailing commas are optional.
 //!
+//! Before the test groups, `orphans = { ... }` enables a check for files that
+//! no group matched. See [*Checking for unmatched
+//! files*](#checking-for-unmatched-files) below. After that, `executor = ...`
+//! sets the executor for async tests; see [*Async test
+//! functions*](#async-test-functions) below.
+//!
 //! ## Relative and absolute paths
 //!
 //! The `pattern` argument is tested against the **relative** path of each file,
@@ -92,6 +216,22 @@
 //! including on Windows, and all absolute paths use the platform's native
 //! separator throughout.
 //!
+//! ## Globs
+//!
+//! `glob` is matched against the same `/`-separated relative path as `pattern`, and supports:
+//!
+//! * `*`, which matches any sequence of characters within a path component, and `?`, which matches
+//!   any single character within a path component.
+//! * `**`, which matches across path components. `**/` also matches zero components, so
+//!   `**/*.txt` matches both `a.txt` and `dir/a.txt`.
+//! * `[abc]`, `[a-z]` and `[!abc]`, which match one character in (or not in) a set.
+//! * `{a,b}`, which matches either alternative, e.g. `cases/{pass,fail}/*.rs`.
+//! * `\`, which escapes the following character.
+//!
+//! ```rust,ignore
+//! { test = my_test, root = "tests/files", glob = "**/*.{json,toml}" },
+//! ```
+//!
 //! ## Examples
 //!
 //! This is an example test. Use it with `harness = false`.
@@ -127,6 +267,413 @@
 //! * The pattern `r"^.*/*"` will match `foo/bar.txt`.
 //! * `my_test` and `my_test_utf8` will be called with `"path/to/fixtures/foo/bar.txt"`.
 //!
+//! ## Test names
+//!
+//! By default, each test is named after its test function and its path relative
+//! to `root`, such as `my_test::foo/bar.txt`. `name` replaces the part before
+//! `::`, which is useful to leave out module paths, or to run the same function
+//! against several roots:
+//!
+//! ```rust,ignore
+//! datatest_stable::harness! {
+//!     { test = parser::check, root = "tests/valid", name = "valid" },
+//!     { test = parser::check, root = "tests/invalid", name = "invalid" },
+//! }
+//! ```
+//!
+//! Group names must be unique, and the harness panics at startup if two
+//! groups have the same name. If two tests would get the same name, the
+//! harness reports an error and exits without running any tests.
+//!
+//! For deep fixture trees, the paths in names can get long. `case_name` sets a
+//! template for the part after `::`, and `strip_prefix` removes a leading
+//! directory first:
+//!
+//! ```rust,ignore
+//! datatest_stable::harness! {
+//!     // `cases/parse/foo.txt` is named `my_test::parse/foo`.
+//!     { test = my_test, root = "tests", glob = "cases/**/*.txt", strip_prefix = "cases", case_name = "{stem}" },
+//! }
+//! ```
+//!
+//! The template must contain exactly one `{path}` or `{stem}` placeholder,
+//! along with any literal text. This lets the harness look tests up by name
+//! without scanning the whole root, which nextest does for each test it runs.
+//! With `{stem}`, only the directory containing the file is listed.
+//!
+//! Alternatively, the template can be made up of named capture groups from
+//! `pattern`. This requires the pattern to be anchored with `^` and `$` and to
+//! consist only of literal text and named groups, and the template to use every
+//! group, so that the path can be rebuilt from the name:
+//!
+//! ```rust,ignore
+//! datatest_stable::harness! {
+//!     // `pg/select.sql` is named `my_test::select (pg)`.
+//!     {
+//!         test = my_test,
+//!         root = "tests/queries",
+//!         pattern = r"^(?P<dialect>mysql|pg)/(?P<case>[^/]+)\.sql$",
+//!         case_name = "{case} ({dialect})",
+//!     },
+//! }
+//! ```
+//!
+//! For anything else, `case_name` also accepts a pair of functions: one from a
+//! path relative to the root to a name, and its inverse.
+//!
+//! ```rust,ignore
+//! datatest_stable::harness! {
+//!     {
+//!         test = my_test,
+//!         root = "tests/cases",
+//!         case_name = (
+//!             |path: &Utf8Path| path.as_str().replace('/', "."),
+//!             |name: &str| Some(name.replace('.', "/")),
+//!         ),
+//!     },
+//! }
+//! ```
+//!
+//! ## Multiple roots
+//!
+//! If the same kind of fixture lives in several places, `root` can be a list of
+//! sources rather than one. Each root gets a label, which follows the group's
+//! name in the names of its tests, so that the same path under two roots gives
+//! two different tests:
+//!
+//! ```rust,ignore
+//! datatest_stable::harness! {
+//!     // Tests are named `check::files::a.txt`, `check::spec-tests::a.txt`, etc.
+//!     { test = check, root = ["tests/files", "third_party/spec-tests"], pattern = r"\.txt$" },
+//! }
+//! ```
+//!
+//! By default, roots are labelled with the names of their directories or
+//! archives. To choose labels, or to use `include_dir` directories, which have
+//! no name, write each root as `label = source`:
+//!
+//! ```rust,ignore
+//! datatest_stable::harness! {
+//!     {
+//!         test = check,
+//!         root = [local = "tests/files", spec = include_dir!("$CARGO_MANIFEST_DIR/third_party/spec-tests")],
+//!     },
+//! }
+//! ```
+//!
+//! Labels must be unique within the group. All other keys, such as `pattern`
+//! and `expected`, apply to each root, and `expected` files are looked up in the
+//! same root as their test case. Paths passed to test functions are joined
+//! with the root that the test case is in.
+//!
+//! ## Sharing state between tests
+//!
+//! Test functions can be closures that capture state. This is useful when
+//! every test in a group depends on something expensive to build, such as a
+//! compiled grammar or a loaded configuration:
+//!
+//! ```rust
+//! use datatest_stable::Utf8Path;
+//! # struct Grammar;
+//! # impl Grammar {
+//! #     fn load() -> Self { Grammar }
+//! #     fn parse(&self, _: &str) -> datatest_stable::Result<()> { Ok(()) }
+//! # }
+//!
+//! fn parse_test() -> impl Fn(&Utf8Path, String) -> datatest_stable::Result<()> + Send + Sync {
+//!     let grammar = Grammar::load();
+//!     move |_path, contents| grammar.parse(&contents)
+//! }
+//!
+//! datatest_stable::harness! {
+//!     { test = parse_test(), root = "path/to/fixtures", pattern = r"^.*\.txt$", name = "parse" },
+//! }
+//! ```
+//!
+//! The `test` expression is evaluated once per process, before any tests are
+//! run, and the resulting closure is shared by all the tests in the group. (With
+//! nextest, each test is run in its own process, so the setup is performed
+//! once per test. Consider initializing expensive state lazily if only some
+//! groups need it.)
+//!
+//! Since `parse_test()` isn't a path to a function, the group must be given a
+//! `name`, which is used as the prefix for test names -- `parse::a.txt` and so
+//! on in the example above. Names taken from arbitrary expressions could
+//! contain spaces or other characters that get in the way of test filters.
+//!
+//! ## Directories as test cases
+//!
+//! Some test cases are made up of several files, such as an input tree along
+//! with expected output and arguments. With `unit = dir`, each immediate
+//! subdirectory of `root` is a test case, and the test function is called with
+//! the path to the subdirectory:
+//!
+//! ```rust
+//! use datatest_stable::Utf8Path;
+//!
+//! fn my_test(path: &Utf8Path) -> datatest_stable::Result<()> {
+//!     let args = std::fs::read_to_string(path.join("args"))?;
+//!     let expected = std::fs::read_to_string(path.join("expected.txt"))?;
+//!     // ... run the test with the `input` directory
+//!     Ok(())
+//! }
+//!
+//! datatest_stable::harness! {
+//!     { test = my_test, root = "path/to/cases", unit = dir },
+//! }
+//! ```
+//!
+//! `pattern` is matched against the name of each subdirectory, and test names
+//! are of the form `my_test::case_name`. Hidden subdirectories are skipped.
+//!
+//! Test functions in this mode accept a path only: `fn(&Path)` or
+//! `fn(&Utf8Path)`. For [embedded directories](#embedding-directories-at-compile-time),
+//! they must instead accept the path along with the `include_dir::Dir`, as in
+//! `fn(&Utf8Path, &include_dir::Dir) -> datatest_stable::Result<()>`. Note that
+//! `include_dir` looks up files by their path relative to the root of the
+//! embedded directory, so use e.g. `dir.get_file(path.join("args"))`.
+//!
+//! ## Multiple test cases per file
+//!
+//! With `sections = true`, each file is split into sections that are run as
+//! separate test cases. Each section starts with a header line of the form
+//! `=== name ===`:
+//!
+//! ```text
+//! === addition ===
+//! 1 + 2
+//!
+//! === nested parentheses ===
+//! ((1 + 2) * 3)
+//! ```
+//!
+//! The test function is called once per section, with the path to the file
+//! and the contents of the section, not including the header or trailing blank
+//! lines. Test names are of the form `my_test::path/to/file.txt::addition`.
+//!
+//! Since the contents of each section are passed in, the test function must
+//! accept file contents as an argument. Snapshots work as usual, with a
+//! separate expected file per section: `path/to/file.txt.addition.expected`.
+//!
+//! Files that don't consist of well-formed sections, or that have duplicate
+//! section names, are reported as errors while listing tests, and the harness
+//! exits without running any tests.
+//!
+//! ## Per-fixture directives
+//!
+//! With `directives = true`, individual fixtures can be marked as ignored or
+//! expected to fail, without having to exclude them with `pattern`. Directives
+//! are read from a comment on the first line of the file:
+//!
+//! ```text
+//! // datatest: ignore
+//! ```
+//!
+//! The comment can start with `//`, `#`, `--`, `;`, `/*`, or `<!--`, and can
+//! list several comma-separated directives:
+//!
+//! * `ignore` - the test is ignored, and is only run with `--ignored` or
+//!   `--include-ignored`, as with `#[ignore]` in libtest.
+//! * `should-fail` - the test passes if the test function returns an error or
+//!   panics, and fails if it succeeds.
+//!
+//! The first line is passed to the test function as part of the contents as
+//! usual. For files that can't contain comments, directives can instead be
+//! placed in a sidecar file named `<file>.datatest.toml`, such as
+//! `input.json.datatest.toml`:
+//!
+//! ```toml
+//! ignore = false
+//! should-fail = true
+//! ```
+//!
+//! Reading sidecar files requires the `toml` feature. Sidecar files are never
+//! treated as test cases themselves. With [`sections =
+//! true`](#multiple-test-cases-per-file), the first line of each section is
+//! checked for directives, and the sidecar file applies to every section in
+//! the file. With [`unit = dir`](#directories-as-test-cases), only sidecar
+//! files are supported, such as `case.datatest.toml` next to the `case`
+//! directory.
+//!
+//! Unknown directives, and sidecar files that can't be parsed, are reported
+//! as errors while listing tests, as with malformed sections.
+//!
+//! ## Paired input and expected files
+//!
+//! Some test suites keep each input next to a file with its expected output,
+//! such as `foo.in` and `foo.out`. With `expected`, each input file is paired
+//! with an expected file, and the test function is passed the contents of
+//! both:
+//!
+//! ```rust
+//! use datatest_stable::Utf8Path;
+//!
+//! fn my_test(path: &Utf8Path, input: String, expected: String) -> datatest_stable::Result<()> {
+//!     // ... compare the output for `input` against `expected`
+//!
+//!     Ok(())
+//! }
+//!
+//! datatest_stable::harness! {
+//!     { test = my_test, root = "path/to/fixtures", glob = "**/*.in", expected = "{stem}.out" },
+//! }
+//! ```
+//!
+//! `expected` is a path relative to `root`, in which these placeholders are
+//! replaced with parts of the input's relative path, here `cases/foo.rs`:
+//!
+//! | Placeholder    | Value          |
+//! |----------------|----------------|
+//! | `{path}`       | `cases/foo.rs` |
+//! | `{stem}`       | `cases/foo`    |
+//! | `{file_name}`  | `foo.rs`       |
+//! | `{file_stem}`  | `foo`          |
+//! | `{extension}`  | `rs`           |
+//!
+//! For example, `expected = "expected/{file_stem}.stderr"` pairs
+//! `cases/foo.rs` with `expected/foo.stderr`. Any other `{name}` is replaced
+//! with the text matched by the named group `name` in `pattern`, or with
+//! nothing if the group didn't participate in the match. Use `{{` and `}}` for
+//! literal braces.
+//!
+//! If an input's expected file doesn't exist, `on_missing` determines what
+//! happens:
+//!
+//! * `on_missing = fail` (the default): the test fails.
+//! * `on_missing = skip`: the input isn't a test case.
+//! * `on_missing = none`: the test function is passed `None`. This requires a
+//!   test function that accepts `Option<String>` or `Option<Vec<u8>>`.
+//!
+//! As with snapshots, `pattern` or `glob` should only match input files, not
+//! expected files. Pairing works with both directories on disk and
+//! `include_dir` sources, but can't be combined with `sections`.
+//!
+//! ## Ignore files
+//!
+//! Fixture trees can accumulate files that aren't test cases, such as editor
+//! swap files or outputs left behind by earlier runs. With `gitignore = true`,
+//! files and directories are skipped if they're ignored by a `.gitignore`,
+//! `.ignore`, or `.datatestignore` file in `root` or any of its
+//! subdirectories:
+//!
+//! ```rust,ignore
+//! datatest_stable::harness! {
+//!     { test = my_test, root = "tests/fixtures", gitignore = true },
+//! }
+//! ```
+//!
+//! Ignore files use [`.gitignore` syntax](https://git-scm.com/docs/gitignore),
+//! and apply to the directory they're in and everything below it. Rules in
+//! deeper directories take precedence, as do rules in `.datatestignore` over
+//! `.ignore`, and `.ignore` over `.gitignore`. A `.datatestignore` file is
+//! useful for skipping files in tests without hiding them from Git.
+//!
+//! Ignore files outside `root`, such as one at the root of the repository,
+//! aren't read. Ignore files are never treated as test cases themselves, and
+//! they're also read from `include_dir` sources.
+//!
+//! ## Checking for unmatched files
+//!
+//! A typo in a pattern, or a fixture added with the wrong extension, can cause
+//! tests to silently not run. To catch this, the harness can check for files
+//! under the roots of its test groups that no group matched. The check is
+//! configured with `orphans = { ... }` before the test groups:
+//!
+//! ```rust,ignore
+//! datatest_stable::harness! {
+//!     orphans = { action = fail, allow = [r"(^|/)README\.md$"] },
+//!     { test = parse, root = "tests/fixtures", glob = "parse/**/*.txt" },
+//!     { test = format, root = "tests/fixtures", glob = "format/**/*.txt" },
+//! }
+//! ```
+//!
+//! With `action = fail`, unmatched files are reported as an error, and the
+//! harness exits with a failure without running any tests. With `action =
+//! warn`, it prints a warning instead. Files
+//! matching any of the `allow` regexes, such as READMEs or helper files, are
+//! never reported.
+//!
+//! Expected files, snapshots, directive sidecar files, and files inside
+//! directories that are test cases count as matched. Groups with the same
+//! root, or with roots nested inside each other, are checked together. See
+//! [`OrphanCheck`] for details, and for use with [`Harness`].
+//!
+//! ## Deserializing fixtures
+//!
+//! With the `json`, `toml`, or `yaml` features enabled, test functions can
+//! accept any type that implements
+//! [`DeserializeOwned`](serde::de::DeserializeOwned) in place of the contents:
+//!
+#![cfg_attr(all(feature = "json", feature = "toml"), doc = "```rust")]
+#![cfg_attr(not(all(feature = "json", feature = "toml")), doc = "```rust,ignore")]
+//! use datatest_stable::Utf8Path;
+//! use serde::Deserialize;
+//!
+//! #[derive(Deserialize)]
+//! struct Case {
+//!     input: String,
+//!     expected: String,
+//! }
+//!
+//! fn my_test(path: &Utf8Path, case: Case) -> datatest_stable::Result<()> {
+//!     // ... write test here
+//!     Ok(())
+//! }
+//!
+//! fn my_test_txt(path: &Utf8Path, case: Case) -> datatest_stable::Result<()> {
+//!     // ... write test here
+//!     Ok(())
+//! }
+//!
+//! datatest_stable::harness! {
+//!     { test = my_test, root = "path/to/fixtures", pattern = r"^.*\.(json|toml)$" },
+//!     // JSON stored in files with a different extension.
+//!     { test = my_test_txt, root = "path/to/fixtures", pattern = r"^.*\.txt$", format = json },
+//! }
+//! ```
+//!
+//! By default, the format is determined by the extension of each file: `.json`
+//! for JSON, `.toml` for TOML, and `.yaml` or `.yml` for YAML. Files with other
+//! extensions fail unless `format` is specified.
+//!
+//! Test functions that accept a `String` or a `Vec<u8>` are always passed the
+//! raw contents of the file, even though those types implement
+//! `Deserialize`.
+//!
+//! ## Golden-file snapshots
+//!
+//! Test functions can take a [`Snapshot`] as their last argument to compare
+//! the output they produce against an expected file stored next to each
+//! fixture. For a fixture `foo/bar.txt`, the expected file is
+//! `foo/bar.txt.expected`.
+//!
+//! ```rust
+//! use datatest_stable::{Snapshot, Utf8Path};
+//!
+//! fn my_test(path: &Utf8Path, contents: String, snapshot: &Snapshot) -> datatest_stable::Result<()> {
+//!     let output = contents.to_uppercase();
+//!     snapshot.check(&output)
+//! }
+//!
+//! datatest_stable::harness! {
+//!     { test = my_test, root = "path/to/fixtures", pattern = r"^.*\.txt$" },
+//! }
+//! ```
+//!
+//! If the output doesn't match, the test fails with a unified diff. To create
+//! or update expected files, run the tests with `DATATEST_BLESS=1`:
+//!
+//! ```sh
+//! DATATEST_BLESS=1 cargo nextest run
+//! ```
+//!
+//! Note that the pattern should not match the `.expected` files themselves.
+//!
+//! Blessing is only supported for directories on disk. With [embedded
+//! directories](#embedding-directories-at-compile-time), expected files are
+//! read from the embedded data, and setting `DATATEST_BLESS` causes tests to
+//! fail.
+//!
 //! ## Embedding directories at compile time
 //!
 //! With the `include-dir` feature enabled, you can use the
@@ -223,10 +770,200 @@
 //! [`Dir`](include_dir::Dir). Your test should be prepared to handle either
 //! case.
 //!
+//! ## Reading fixtures from archives
+//!
+//! Large corpora are often distributed as archives, with many small files that
+//! are noisy to check into a repository. With the `archive-tar` or
+//! `archive-zip` features, an [`Archive`] on disk can be used as the root of a
+//! test group, and its members are test cases like the files in a directory:
+//!
+#![cfg_attr(
+    all(feature = "archive-tar", feature = "archive-zip"),
+    doc = "```rust,no_run"
+)]
+#![cfg_attr(
+    not(all(feature = "archive-tar", feature = "archive-zip")),
+    doc = "```rust,ignore"
+)]
+//! use datatest_stable::{Archive, Utf8Path};
+//!
+//! fn my_test(path: &Utf8Path, contents: String) -> datatest_stable::Result<()> {
+//!     // ... write test here
+//!     Ok(())
+//! }
+//!
+//! datatest_stable::harness! {
+//!     { test = my_test, root = Archive::new("tests/corpus.tar.gz"), pattern = r"\.json$" },
+//!     { test = my_test, root = Archive::new("tests/corpus.zip"), name = "zip" },
+//! }
+//! ```
+//!
+//! `.tar`, `.tar.gz` and `.tgz` archives require the `archive-tar` feature,
+//! and `.zip` archives require the `archive-zip` feature. As with [embedded
+//! directories](#embedding-directories-at-compile-time), test functions must
+//! accept the contents of each file as an argument, and are passed paths
+//! relative to the root of the archive. Paired expected files, ignore files
+//! and sidecar files are read from the archive as well, but `unit = dir` isn't
+//! supported.
+//!
+//! The whole archive is read into memory when tests are listed. When nextest
+//! runs a single test, only the members it needs are read.
+//!
+//! ## Building a harness at runtime
+//!
+//! `harness!` requires the set of test groups to be known at compile time. If
+//! it isn't, write `main` yourself and build a [`Harness`] instead. Each
+//! [`TestGroup`] corresponds to one `{ test = ..., root = ..., ... }` entry in
+//! `harness!`, and supports the same options as methods.
+//!
+//! For example, to create one group per subdirectory of a corpus:
+//!
+//! ```rust,no_run
+//! use datatest_stable::{test_fn, Harness, TestGroup, Utf8Path};
+//! use std::process::ExitCode;
+//!
+//! fn my_test(path: &Utf8Path, contents: String) -> datatest_stable::Result<()> {
+//!     // ... write test here
+//!     Ok(())
+//! }
+//!
+//! fn main() -> ExitCode {
+//!     let mut harness = Harness::new();
+//!     for entry in Utf8Path::new("tests/corpus").read_dir_utf8().unwrap() {
+//!         let dir = entry.unwrap().into_path();
+//!         let name = format!("corpus::{}", dir.file_name().unwrap());
+//!         harness = harness.group(TestGroup::new(name, test_fn!(my_test), dir).pattern(r"\.txt$"));
+//!     }
+//!     harness.run()
+//! }
+//! ```
+//!
+//! Test functions are passed in through the [`test_fn!`] macro, which accepts
+//! the same functions and closures as `test` in `harness!`. Unlike with
+//! `harness!`, the name of each group must be specified explicitly.
+//!
+//! As with `harness!`, the test target must have `harness = false` set in
+//! `Cargo.toml`.
+//!
+//! ## Declaring tests with an attribute
+//!
+//! With the `macros` feature, test groups can be declared next to their test
+//! functions with the `files` attribute, instead of in `harness!`. The
+//! attribute accepts the same arguments as a `harness!` entry other than
+//! `test`, in any order:
+//!
+//! ```rust,ignore
+//! use datatest_stable::Utf8Path;
+//!
+//! #[datatest_stable::files(root = "tests/files", pattern = r"\.txt$")]
+//! fn my_test(path: &Utf8Path, contents: String) -> datatest_stable::Result<()> {
+//!     // ... write test here
+//!     Ok(())
+//! }
+//!
+//! datatest_stable::harness! {}
+//! ```
+//!
+//! The attribute expands to code that refers to `::datatest_stable`. If the
+//! crate is only available under another path, such as a re-export from
+//! another crate, pass that path as `crate = my_crate::datatest_stable`.
+//!
+//! `harness!` (or [`Harness::run`]) still generates `main`, and runs groups
+//! declared with the attribute along with any passed to it directly. Each
+//! group is named after the path to its function from the crate root, such as
+//! `parser::my_test`, unless `name` is specified.
+//!
+//! Mistakes in the arguments, such as unknown keys or invalid regexes passed to
+//! `pattern` and `exclude` as string literals, are reported as compiler errors
+//! that point at the offending argument. `harness!` is a `macro_rules!` macro,
+//! so that it works without this feature, and most of its errors point at the
+//! whole invocation instead. With this feature, the literal regexes and globs
+//! passed to `harness!` are also checked, with errors pointing at them.
+//!
+//! ## Test context
+//!
+//! Test functions that need more than the path to a fixture can accept a
+//! [`TestContext`] as their first argument. It provides:
+//!
+//! * the path relative to `root` that `pattern` was matched against, along with
+//!   `root` itself and the path on disk;
+//! * the name of the test group;
+//! * the section name, with `sections = true`;
+//! * the text matched by each named capture group in `pattern`;
+//! * a scratch directory for the test case, from [`TestContext::temp_dir`].
+//!
+//! The scratch directory is created the first time `temp_dir` is called, so
+//! tests that don't need one don't pay for it. Each test case gets its own
+//! directory, which is deleted when the test finishes, whether it passes or
+//! fails.
+//!
+//! Capture groups are useful when the path to a fixture encodes information
+//! about it. They're captured for both directories on disk and `include_dir`
+//! sources, and can also be used in `expected` and `case_name` templates:
+//!
+//! ```rust
+//! use datatest_stable::TestContext;
+//!
+//! fn run_query(context: &TestContext, query: String) -> datatest_stable::Result<()> {
+//!     let dialect = context.capture("dialect").unwrap();
+//!     // ... run `query` against a database of this dialect
+//!     Ok(())
+//! }
+//!
+//! datatest_stable::harness! {
+//!     { test = run_query, root = "path/to/fixtures", pattern = r"^(?P<dialect>mysql|pg)/.+\.sql$" },
+//! }
+//! ```
+//!
+//! ## Async test functions
+//!
+//! Test functions can be `async fn`s that take a `&Path` or `&Utf8Path`,
+//! optionally followed by the contents of the file as a `String` or `Vec<u8>`:
+//!
+//! ```rust
+//! use datatest_stable::Utf8Path;
+//!
+//! async fn my_test(path: &Utf8Path, contents: String) -> datatest_stable::Result<()> {
+//!     // ... write test here, awaiting as needed
+//!     Ok(())
+//! }
+//!
+//! datatest_stable::harness! {
+//!     { test = my_test, root = "path/to/fixtures" },
+//! }
+//! ```
+//!
+//! Each test blocks on its future using an [`Executor`] shared by all async
+//! tests in the process. By default, this is a multi-threaded Tokio runtime
+//! with the `tokio` feature, and a [`BlockingExecutor`] otherwise, which can
+//! run futures that don't depend on a particular runtime. To use another
+//! executor, pass `executor = ...` to `harness!` after `orphans`, or call
+//! [`Harness::executor`]:
+//!
+//! ```rust
+//! # async fn my_test(path: &datatest_stable::Utf8Path) -> datatest_stable::Result<()> { Ok(()) }
+//! datatest_stable::harness! {
+//!     executor = datatest_stable::BlockingExecutor,
+//!     { test = my_test, root = "path/to/fixtures" },
+//! }
+//! ```
+//!
 //! # Features
 //!
 //! * `include-dir`: Enables the `include_dir!` macro, which allows embedding
 //!   directories at compile time. This feature is disabled by default.
+//! * `archive-tar`, `archive-zip`: Enable [reading fixtures from
+//!   archives](#reading-fixtures-from-archives) in the respective formats.
+//!   These features are disabled by default.
+//! * `json`, `toml`, `yaml`: Enable [deserializing
+//!   fixtures](#deserializing-fixtures) in the respective formats. These
+//!   features are disabled by default. `toml` also enables [sidecar
+//!   files](#per-fixture-directives) for directives.
+//! * `macros`: Enables the [`files`](#declaring-tests-with-an-attribute) attribute, for declaring
+//!   test groups next to their test functions. This feature is disabled by default.
+//! * `tokio`: Runs [async test functions](#async-test-functions) on a multi-threaded Tokio
+//!   runtime by default, and allows using a Tokio runtime as an [`Executor`]. This feature is
+//!   disabled by default.
 //!
 //! # Minimum supported Rust version (MSRV)
 //!
@@ -242,22 +979,65 @@
 #![warn(missing_docs)]
 #![cfg_attr(doc_cfg, feature(doc_cfg))]
 
+#[cfg(any(feature = "archive-tar", feature = "archive-zip"))]
+mod archive;
+mod context;
 mod data_source;
+mod directives;
+mod executor;
+#[cfg(any(feature = "json", feature = "toml", feature = "yaml"))]
+mod format;
+mod harness;
+mod ignore;
 mod macros;
+mod naming;
+mod orphans;
+mod pairing;
+mod pattern;
+#[cfg(feature = "macros")]
+mod registry;
 mod runner;
+mod sections;
+mod snapshot;
 
 /// The result type for `datatest-stable` tests.
 pub type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
 
+#[cfg(any(feature = "archive-tar", feature = "archive-zip"))]
+pub use self::archive::Archive;
+pub use self::context::TestContext;
+#[doc(hidden)]
+pub use self::data_source::data_source_kinds;
+pub use self::data_source::{DataSource, Unit};
+pub use self::executor::{BlockingExecutor, Executor, TestFuture};
+#[cfg(any(feature = "json", feature = "toml", feature = "yaml"))]
+pub use self::format::Format;
+pub use self::harness::{Harness, TestGroup};
+pub use self::orphans::{OrphanAction, OrphanCheck};
+pub use self::pairing::OnMissing;
+/// Not part of the public API, just used for macros.
+#[cfg(feature = "macros")]
 #[doc(hidden)]
-pub use self::data_source::{data_source_kinds, DataSource};
+pub use self::registry::{default_group_name, Registration};
 /// Not part of the public API, just used for macros.
 #[doc(hidden)]
-pub use self::runner::{runner, test_kinds, Requirements, TestFn};
+pub use self::runner::test_kinds;
+pub use self::runner::TestFn;
+pub use self::snapshot::Snapshot;
 /// A re-export of this type from the `camino` crate, since it forms part of function signatures.
 #[doc(no_inline)]
 pub use camino::Utf8Path;
+/// Not part of the public API, just used for macros.
+#[cfg(feature = "macros")]
+#[doc(hidden)]
+pub use datatest_stable_macros::check_pattern_literal;
+#[cfg(feature = "macros")]
+pub use datatest_stable_macros::files;
 /// A re-export of `include_dir!` from the `include_dir` crate, for convenience.
 #[cfg(feature = "include-dir")]
 #[doc(no_inline)]
 pub use include_dir::include_dir;
+/// Not part of the public API, just used for macros.
+#[cfg(feature = "macros")]
+#[doc(hidden)]
+pub use inventory;
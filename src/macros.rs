@@ -7,18 +7,26 @@
 /// a target](https://doc.rust-lang.org/cargo/reference/manifest.html#configuring-a-target)).
 #[macro_export]
 macro_rules! harness {
-    ( $( { $($args:tt)* } ),+ $(,)* ) => {
-        fn main() -> $crate::Result<()> {
-            let mut requirements = Vec::new();
+    (
+        $( orphans = { $($orphans:tt)* } $(,)? )?
+        $( executor = $executor:expr, )?
+        $( { $($args:tt)* } ),* $(,)*
+    ) => {
+        fn main() -> ::std::process::ExitCode {
+            let mut harness = $crate::Harness::new();
             use $crate::data_source_kinds::*;
-            use $crate::test_kinds::*;
 
             $(
-                $crate::harness_collect!(@gather_test requirements, { $($args)*, } => { });
-            )+
+                harness = harness.orphans($crate::harness_collect!(@orphans { $($orphans)* }));
+            )?
+            $(
+                harness = harness.executor($executor);
+            )?
+            $(
+                $crate::harness_collect!(@gather_test harness, { $($args)*, } => { });
+            )*
 
-            $crate::runner(&requirements);
-            Ok(())
+            harness.run()
         }
     };
     ( $( $name:path, $root:expr, $pattern:expr ),+ $(,)* ) => {
@@ -39,26 +47,62 @@ note: patterns are now evaluated relative to the provided root, not to the crate
     }
 }
 
+/// Creates a [`TestFn`](crate::TestFn) for use with
+/// [`TestGroup::new`](crate::TestGroup::new).
+///
+/// The argument is an expression evaluating to a test function or closure,
+/// which can have any of the shapes accepted by `test` in [`harness!`].
+#[macro_export]
+macro_rules! test_fn {
+    ($test:expr $(,)?) => {{
+        #[allow(unused_imports)]
+        use $crate::test_kinds::*;
+
+        // `test` may be an arbitrary expression that sets up shared state,
+        // so evaluate it exactly once.
+        let test = $test;
+        let tag = (&test).kind();
+        let conclude = (&tag.output()).conclude();
+        tag.resolve(test, conclude)
+    }};
+}
+
 #[macro_export]
 #[doc(hidden)]
 macro_rules! harness_collect {
-    // Gather `test`
+    // Gather `test`, when it's a path that the group can be named after
     (@gather_test
-        $requirements:expr,
+        $harness:expr,
         // Note: here and below, rest always ends with at least 1 comma
-        { test = $test:path, $($rest:tt)* } =>
+        { test = $first:ident $(:: $segment:ident)*, $($rest:tt)* } =>
         { }
     ) => {
         $crate::harness_collect!(@gather_root
-            $requirements,
+            $harness,
             { $($rest)* } =>
-            { test = $test, }
+            {
+                test = $first $(:: $segment)*,
+                name = [concat!(stringify!($first) $(, "::", stringify!($segment))*)],
+            }
+        );
+    };
+
+    // Gather `test`, when it's any other expression: `name` must be given
+    (@gather_test
+        $harness:expr,
+        { test = $test:expr, $($rest:tt)* } =>
+        { }
+    ) => {
+        $crate::harness_collect!(@gather_root
+            $harness,
+            { $($rest)* } =>
+            { test = $test, name = [], }
         );
     };
 
     // `test` not found
     (@gather_test
-        $requirements:expr,
+        $harness:expr,
         { $key:ident $($rest:tt)* } =>
         { }
     ) => {
@@ -67,7 +111,7 @@ macro_rules! harness_collect {
 
     // No remaining arguments
     (@gather_test
-        $requirements:expr,
+        $harness:expr,
         { $(,)* } =>
         { }
     ) => {
@@ -76,29 +120,44 @@ macro_rules! harness_collect {
 
     // Something that isn't an identifier
     (@gather_test
-        $requirements:expr,
+        $harness:expr,
         { $($rest:tt)* } =>
         { }
     ) => {
         compile_error!(concat!("expected `test`, found non-identifier token: (rest: ", stringify!($($rest)*), ")"));
     };
 
+    // Gather `root`, with a list of roots
+    (@gather_root
+        $harness:expr,
+        { root = [$($roots:tt)*], $($rest:tt)* } =>
+        { $($collected:tt)* }
+    ) => {
+        $crate::harness_collect!(@gather_options
+            $harness,
+            { $($rest)* } =>
+            { $($collected)* root = [$($roots)*], pattern = [], } =>
+            { }
+        );
+    };
+
     // Gather `root`
     (@gather_root
-        $requirements:expr,
+        $harness:expr,
         { root = $root:expr, $($rest:tt)* } =>
         { $($collected:tt)* }
     ) => {
-        $crate::harness_collect!(@gather_pattern
-            $requirements,
+        $crate::harness_collect!(@gather_options
+            $harness,
             { $($rest)* } =>
-            { $($collected)* root = $root, }
+            { $($collected)* root = { $root }, pattern = [], } =>
+            { }
         );
     };
 
     // `root` not found
     (@gather_root
-        $requirements:expr,
+        $harness:expr,
         { $key:ident $($rest:tt)* } =>
         { $($collected:tt)* }
     ) => {
@@ -107,88 +166,557 @@ macro_rules! harness_collect {
 
     // No remaining arguments
     (@gather_root
-        $requirements:expr,
+        $harness:expr,
         { $(,)* } =>
-        { $($collected:tt)* }
+        { test = $test:expr, $($collected:tt)* }
     ) => {
-        compile_error!(concat!("expected `root`, but ran out of arguments (collected: ", stringify!($($collected)*), ")"));
+        compile_error!(concat!("expected `root`, but ran out of arguments (collected: test = ", stringify!($test), ",)"));
     };
 
     // Something that isn't an identifier
     (@gather_root
-        $requirements:expr,
+        $harness:expr,
         { $($rest:tt)* } =>
         { $($collected:tt)* }
     ) => {
         compile_error!(concat!("expected `root`, found non-identifier token (rest: ", stringify!($($rest)*), ")"));
     };
 
-    // Gather pattern
-    (@gather_pattern
-        $requirements:expr,
+    // Gather optional arguments, in any order. Each one is turned into a
+    // method call on `TestGroup`.
+
+    // `format`
+    (@gather_options
+        $harness:expr,
+        { format = $format:ident, $($rest:tt)* } =>
+        { $($collected:tt)* } =>
+        { $($options:tt)* }
+    ) => {
+        $crate::harness_collect!(@gather_options
+            $harness,
+            { $($rest)* } =>
+            { $($collected)* } =>
+            { $($options)* .format($crate::harness_collect!(@format $format)) }
+        );
+    };
+
+    // `pattern` or `glob`, when one of them was already specified
+    (@gather_options
+        $harness:expr,
+        { pattern = $value:expr, $($rest:tt)* } =>
+        { test = $test:expr, name = [$($name:expr)?], root = $root:tt, pattern = [$kind:ident $pattern:expr], } =>
+        { $($options:tt)* }
+    ) => {
+        $crate::harness_collect!(@duplicate_pattern pattern $kind);
+    };
+    (@gather_options
+        $harness:expr,
+        { glob = $value:expr, $($rest:tt)* } =>
+        { test = $test:expr, name = [$($name:expr)?], root = $root:tt, pattern = [$kind:ident $pattern:expr], } =>
+        { $($options:tt)* }
+    ) => {
+        $crate::harness_collect!(@duplicate_pattern glob $kind);
+    };
+
+    // `pattern`, with a literal regex
+    (@gather_options
+        $harness:expr,
+        { pattern = $pattern:literal, $($rest:tt)* } =>
+        { test = $test:expr, name = [$($name:expr)?], root = $root:tt, pattern = [], } =>
+        { $($options:tt)* }
+    ) => {
+        $crate::harness_collect!(@check_literal regex $pattern);
+        $crate::harness_collect!(@gather_options
+            $harness,
+            { $($rest)* } =>
+            { test = $test, name = [$($name)?], root = $root, pattern = [pattern $pattern], } =>
+            { $($options)* }
+        );
+    };
+
+    // `pattern`
+    (@gather_options
+        $harness:expr,
         { pattern = $pattern:expr, $($rest:tt)* } =>
-        { $($collected:tt)* }
+        { test = $test:expr, name = [$($name:expr)?], root = $root:tt, pattern = [], } =>
+        { $($options:tt)* }
     ) => {
-        $crate::harness_collect!(@finish
-            $requirements,
+        $crate::harness_collect!(@gather_options
+            $harness,
+            { $($rest)* } =>
+            { test = $test, name = [$($name)?], root = $root, pattern = [pattern $pattern], } =>
+            { $($options)* }
+        );
+    };
+
+    // `glob`, with a literal glob
+    (@gather_options
+        $harness:expr,
+        { glob = $glob:literal, $($rest:tt)* } =>
+        { test = $test:expr, name = [$($name:expr)?], root = $root:tt, pattern = [], } =>
+        { $($options:tt)* }
+    ) => {
+        $crate::harness_collect!(@check_literal glob $glob);
+        $crate::harness_collect!(@gather_options
+            $harness,
+            { $($rest)* } =>
+            { test = $test, name = [$($name)?], root = $root, pattern = [glob $glob], } =>
+            { $($options)* }
+        );
+    };
+
+    // `glob`
+    (@gather_options
+        $harness:expr,
+        { glob = $glob:expr, $($rest:tt)* } =>
+        { test = $test:expr, name = [$($name:expr)?], root = $root:tt, pattern = [], } =>
+        { $($options:tt)* }
+    ) => {
+        $crate::harness_collect!(@gather_options
+            $harness,
+            { $($rest)* } =>
+            { test = $test, name = [$($name)?], root = $root, pattern = [glob $glob], } =>
+            { $($options)* }
+        );
+    };
+
+    // `exclude`, with several literal patterns
+    (@gather_options
+        $harness:expr,
+        { exclude = [$($exclude:literal),* $(,)?], $($rest:tt)* } =>
+        { $($collected:tt)* } =>
+        { $($options:tt)* }
+    ) => {
+        $( $crate::harness_collect!(@check_literal regex $exclude); )*
+        $crate::harness_collect!(@gather_options
+            $harness,
+            { $($rest)* } =>
+            { $($collected)* } =>
+            { $($options)* $(.exclude($exclude.to_string()))* }
+        );
+    };
+
+    // `exclude`, with several patterns
+    (@gather_options
+        $harness:expr,
+        { exclude = [$($exclude:expr),* $(,)?], $($rest:tt)* } =>
+        { $($collected:tt)* } =>
+        { $($options:tt)* }
+    ) => {
+        $crate::harness_collect!(@gather_options
+            $harness,
+            { $($rest)* } =>
+            { $($collected)* } =>
+            { $($options)* $(.exclude($exclude.to_string()))* }
+        );
+    };
+
+    // `exclude`, with a single literal pattern
+    (@gather_options
+        $harness:expr,
+        { exclude = $exclude:literal, $($rest:tt)* } =>
+        { $($collected:tt)* } =>
+        { $($options:tt)* }
+    ) => {
+        $crate::harness_collect!(@check_literal regex $exclude);
+        $crate::harness_collect!(@gather_options
+            $harness,
+            { $($rest)* } =>
+            { $($collected)* } =>
+            { $($options)* .exclude($exclude.to_string()) }
+        );
+    };
+
+    // `exclude`, with a single pattern
+    (@gather_options
+        $harness:expr,
+        { exclude = $exclude:expr, $($rest:tt)* } =>
+        { $($collected:tt)* } =>
+        { $($options:tt)* }
+    ) => {
+        $crate::harness_collect!(@gather_options
+            $harness,
+            { $($rest)* } =>
+            { $($collected)* } =>
+            { $($options)* .exclude($exclude.to_string()) }
+        );
+    };
+
+    // `unit`
+    (@gather_options
+        $harness:expr,
+        { unit = $unit:ident, $($rest:tt)* } =>
+        { $($collected:tt)* } =>
+        { $($options:tt)* }
+    ) => {
+        $crate::harness_collect!(@gather_options
+            $harness,
+            { $($rest)* } =>
+            { $($collected)* } =>
+            { $($options)* .unit($crate::harness_collect!(@unit $unit)) }
+        );
+    };
+
+    // `hidden`
+    (@gather_options
+        $harness:expr,
+        { hidden = $hidden:expr, $($rest:tt)* } =>
+        { $($collected:tt)* } =>
+        { $($options:tt)* }
+    ) => {
+        $crate::harness_collect!(@gather_options
+            $harness,
+            { $($rest)* } =>
+            { $($collected)* } =>
+            { $($options)* .hidden($hidden) }
+        );
+    };
+
+    // `gitignore`
+    (@gather_options
+        $harness:expr,
+        { gitignore = $gitignore:expr, $($rest:tt)* } =>
+        { $($collected:tt)* } =>
+        { $($options:tt)* }
+    ) => {
+        $crate::harness_collect!(@gather_options
+            $harness,
+            { $($rest)* } =>
+            { $($collected)* } =>
+            { $($options)* .gitignore($gitignore) }
+        );
+    };
+
+    // `root_env`
+    (@gather_options
+        $harness:expr,
+        { root_env = $root_env:expr, $($rest:tt)* } =>
+        { $($collected:tt)* } =>
+        { $($options:tt)* }
+    ) => {
+        $crate::harness_collect!(@gather_options
+            $harness,
             { $($rest)* } =>
-            { $($collected)* pattern = $pattern, }
+            { $($collected)* } =>
+            { $($options)* .root_env($root_env) }
         );
     };
 
-    // `pattern` not found
-    (@gather_pattern
-        $requirements:expr,
+    // `optional`
+    (@gather_options
+        $harness:expr,
+        { optional = $optional:expr, $($rest:tt)* } =>
+        { $($collected:tt)* } =>
+        { $($options:tt)* }
+    ) => {
+        $crate::harness_collect!(@gather_options
+            $harness,
+            { $($rest)* } =>
+            { $($collected)* } =>
+            { $($options)* .optional($optional) }
+        );
+    };
+
+    // `allow_empty`
+    (@gather_options
+        $harness:expr,
+        { allow_empty = $allow_empty:expr, $($rest:tt)* } =>
+        { $($collected:tt)* } =>
+        { $($options:tt)* }
+    ) => {
+        $crate::harness_collect!(@gather_options
+            $harness,
+            { $($rest)* } =>
+            { $($collected)* } =>
+            { $($options)* .allow_empty($allow_empty) }
+        );
+    };
+
+    // `expected`
+    (@gather_options
+        $harness:expr,
+        { expected = $expected:expr, $($rest:tt)* } =>
+        { $($collected:tt)* } =>
+        { $($options:tt)* }
+    ) => {
+        $crate::harness_collect!(@gather_options
+            $harness,
+            { $($rest)* } =>
+            { $($collected)* } =>
+            { $($options)* .expected($expected.to_string()) }
+        );
+    };
+
+    // `on_missing`
+    (@gather_options
+        $harness:expr,
+        { on_missing = $on_missing:ident, $($rest:tt)* } =>
+        { $($collected:tt)* } =>
+        { $($options:tt)* }
+    ) => {
+        $crate::harness_collect!(@gather_options
+            $harness,
+            { $($rest)* } =>
+            { $($collected)* } =>
+            { $($options)* .on_missing($crate::harness_collect!(@on_missing $on_missing)) }
+        );
+    };
+
+    // `sections`
+    (@gather_options
+        $harness:expr,
+        { sections = $sections:expr, $($rest:tt)* } =>
+        { $($collected:tt)* } =>
+        { $($options:tt)* }
+    ) => {
+        $crate::harness_collect!(@gather_options
+            $harness,
+            { $($rest)* } =>
+            { $($collected)* } =>
+            { $($options)* .sections($sections) }
+        );
+    };
+
+    // `directives`
+    (@gather_options
+        $harness:expr,
+        { directives = $directives:expr, $($rest:tt)* } =>
+        { $($collected:tt)* } =>
+        { $($options:tt)* }
+    ) => {
+        $crate::harness_collect!(@gather_options
+            $harness,
+            { $($rest)* } =>
+            { $($collected)* } =>
+            { $($options)* .directives($directives) }
+        );
+    };
+
+    // `name`, which replaces the name taken from `test`
+    (@gather_options
+        $harness:expr,
+        { name = $name:expr, $($rest:tt)* } =>
+        { test = $test:expr, name = [$($default:expr)?], $($collected:tt)* } =>
+        { $($options:tt)* }
+    ) => {
+        $crate::harness_collect!(@gather_options
+            $harness,
+            { $($rest)* } =>
+            { test = $test, name = [$name], $($collected)* } =>
+            { $($options)* }
+        );
+    };
+
+    // `case_name` with a function and its inverse
+    (@gather_options
+        $harness:expr,
+        { case_name = ($to_name:expr, $from_name:expr $(,)?), $($rest:tt)* } =>
+        { $($collected:tt)* } =>
+        { $($options:tt)* }
+    ) => {
+        $crate::harness_collect!(@gather_options
+            $harness,
+            { $($rest)* } =>
+            { $($collected)* } =>
+            { $($options)* .case_name_fn($to_name, $from_name) }
+        );
+    };
+
+    // `case_name` with a template
+    (@gather_options
+        $harness:expr,
+        { case_name = $case_name:expr, $($rest:tt)* } =>
+        { $($collected:tt)* } =>
+        { $($options:tt)* }
+    ) => {
+        $crate::harness_collect!(@gather_options
+            $harness,
+            { $($rest)* } =>
+            { $($collected)* } =>
+            { $($options)* .case_name($case_name) }
+        );
+    };
+
+    // `strip_prefix`
+    (@gather_options
+        $harness:expr,
+        { strip_prefix = $strip_prefix:expr, $($rest:tt)* } =>
+        { $($collected:tt)* } =>
+        { $($options:tt)* }
+    ) => {
+        $crate::harness_collect!(@gather_options
+            $harness,
+            { $($rest)* } =>
+            { $($collected)* } =>
+            { $($options)* .strip_prefix($strip_prefix) }
+        );
+    };
+
+    // Unknown argument
+    (@gather_options
+        $harness:expr,
         { $key:ident $($rest:tt)* } =>
-        { $($collected:tt)* }
+        { $($collected:tt)* } =>
+        { $($options:tt)* }
     ) => {
-        compile_error!(concat!("expected `pattern`, found `", stringify!($key), "`"));
+        compile_error!(concat!("unexpected argument `", stringify!($key), "`"));
     };
 
-    // `pattern` not found: no remaining arguments
-    (@gather_pattern
-        $requirements:expr,
-        { $(,)* } =>
-        { $($collected:tt)* }
+    // No more optional arguments
+    (@gather_options
+        $harness:expr,
+        { $($rest:tt)* } =>
+        { $($collected:tt)* } =>
+        { $($options:tt)* }
     ) => {
         $crate::harness_collect!(@finish
-            $requirements,
-            { } =>
-            { $($collected)* pattern = ".*", }
+            $harness,
+            { $($rest)* } =>
+            { $($collected)* } =>
+            { $($options)* }
         );
     };
 
-    // Something that isn't an identifier
-    (@gather_pattern
-        $requirements:expr,
-        { $($rest:tt)* } =>
-        { $($collected:tt)* }
+    // Errors for `pattern` or `glob` given after one of them
+    (@duplicate_pattern pattern pattern) => {
+        compile_error!("`pattern` was specified more than once");
+    };
+    (@duplicate_pattern glob glob) => {
+        compile_error!("`glob` was specified more than once");
+    };
+    (@duplicate_pattern $key:ident $kind:ident) => {
+        compile_error!("`pattern` and `glob` are mutually exclusive -- specify only one of them");
+    };
+
+    // Checks a literal regex or glob at compile time, if the `macros` feature
+    // is enabled. Otherwise it's checked when the harness runs.
+    (@check_literal $kind:ident $value:literal) => {
+        $crate::check_pattern_literal!($kind, $value);
+    };
+
+    // Formats for `format`
+    (@format json) => { $crate::Format::Json };
+    (@format toml) => { $crate::Format::Toml };
+    (@format yaml) => { $crate::Format::Yaml };
+    (@format $format:ident) => {
+        compile_error!(concat!("unknown format `", stringify!($format), "` (expected `json`, `toml`, or `yaml`)"))
+    };
+
+    // Units for `unit`
+    (@unit file) => { $crate::Unit::File };
+    (@unit dir) => { $crate::Unit::Dir };
+    (@unit $unit:ident) => {
+        compile_error!(concat!("unknown unit `", stringify!($unit), "` (expected `file` or `dir`)"))
+    };
+
+    // Policies for `on_missing`
+    (@on_missing fail) => { $crate::OnMissing::Fail };
+    (@on_missing skip) => { $crate::OnMissing::Skip };
+    (@on_missing none) => { $crate::OnMissing::PassNone };
+    (@on_missing $on_missing:ident) => {
+        compile_error!(concat!("unknown policy `", stringify!($on_missing), "` (expected `fail`, `skip`, or `none`)"))
+    };
+
+    // The orphan check
+    (@orphans { action = $action:ident $(, allow = [$($allow:expr),* $(,)?])? $(,)? }) => {
+        $crate::OrphanCheck::new($crate::harness_collect!(@orphan_action $action))
+            $($(.allow($allow.to_string()))*)?
+    };
+    (@orphans { $($orphans:tt)* }) => {
+        compile_error!("expected `orphans = { action = fail, allow = [...] }`, with `action` set to `fail` or `warn`")
+    };
+
+    // Actions for the orphan check
+    (@orphan_action fail) => { $crate::OrphanAction::Fail };
+    (@orphan_action warn) => { $crate::OrphanAction::Warn };
+    (@orphan_action $action:ident) => {
+        compile_error!(concat!("unknown action `", stringify!($action), "` (expected `fail` or `warn`)"))
+    };
+
+    // Finish - `test` isn't a path, and `name` wasn't given
+    (@finish
+        $harness:expr,
+        { $(,)* } =>
+        { test = $test:expr, name = [], $($collected:tt)* } =>
+        { $($options:tt)* }
     ) => {
-        compile_error!(concat!("expected `pattern`, found non-identifier token (rest: ", stringify!($($rest)*), ")"));
+        compile_error!("`test` is not a path to a function, so `name` must be given to name its tests");
     };
 
     // Finish - no more arguments allowed
     (@finish
-        $requirements:expr,
+        $harness:expr,
         { $(,)* } =>
-        { test = $test:path, root = $root:expr, pattern = $pattern:expr, }
+        { test = $test:expr, name = [$name:expr], root = $root:tt, pattern = [$($kind:ident $pattern:expr)?], } =>
+        { $($options:tt)* }
     ) => {
-        $requirements.push(
-            $crate::Requirements::new(
-                $test.kind().resolve($test),
-                stringify!($test).to_string(),
-                $root.resolve_data_source(),
-                $pattern.to_string()
-            )
+        $harness = $harness.group(
+            $crate::harness_collect!(@new_group $name, $test, $root)
+            $(.$kind($pattern.to_string()))?
+            $($options)*
         );
     };
 
+    // Creates a group with a single root
+    (@new_group $name:expr, $test:expr, { $root:expr }) => {
+        $crate::TestGroup::new(
+            $name,
+            $crate::test_fn!($test),
+            $root.resolve_data_source(),
+        )
+    };
+
+    // Creates a group with a list of roots
+    (@new_group $name:expr, $test:expr, [ $(,)? ]) => {
+        compile_error!("`root` must contain at least one source")
+    };
+    (@new_group $name:expr, $test:expr, [ $($roots:tt)+ ]) => {
+        $crate::TestGroup::with_roots(
+            $name,
+            $crate::test_fn!($test),
+            $crate::harness_collect!(@roots [] $($roots)+),
+        )
+    };
+
+    // Labels each root in a list, either with the label it was given or with
+    // the name of the directory or archive
+    (@roots [$($labelled:tt)*] $label:ident = $root:expr $(, $($rest:tt)*)?) => {
+        $crate::harness_collect!(@roots
+            [$($labelled)* (stringify!($label).to_owned(), $root.resolve_data_source()),]
+            $($($rest)*)?
+        )
+    };
+    (@roots [$($labelled:tt)*] $root:expr $(, $($rest:tt)*)?) => {
+        $crate::harness_collect!(@roots
+            [$($labelled)* {
+                let root = $root.resolve_data_source();
+                (root.default_label(), root)
+            },]
+            $($($rest)*)?
+        )
+    };
+    (@roots [$($labelled:tt)*]) => {
+        [$($labelled)*]
+    };
+
     // Finish - unexpected extra arguments
     (@finish
-        $requirements:expr,
+        $harness:expr,
         { $($unexpected:tt)+ } =>
-        { $($collected:tt)* }
+        { $($collected:tt)* } =>
+        { $($options:tt)* }
     ) => {
         compile_error!(concat!("unexpected extra arguments: ", stringify!($($unexpected)+)));
     };
 }
+
+/// Not part of the public API, just used for macros.
+///
+/// Without the `macros` feature, literal regexes and globs aren't checked at
+/// compile time, and invalid ones are reported when the harness runs.
+#[cfg(not(feature = "macros"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! check_pattern_literal {
+    ($kind:ident, $value:expr) => {};
+}
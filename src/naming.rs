@@ -0,0 +1,373 @@
+// Copyright (c) The datatest-stable Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Deriving the names of test cases from their paths, and back.
+
+use crate::{
+    pairing::{PathPart, Template},
+    pattern::{split_named_groups, Pattern, PatternPart},
+};
+use camino::{Utf8Path, Utf8PathBuf};
+use std::sync::Arc;
+
+type ToNameFn = dyn Fn(&Utf8Path) -> String + Send + Sync;
+type FromNameFn = dyn Fn(&str) -> Option<String> + Send + Sync;
+
+/// How the name of each test case in a group is derived from its path.
+///
+/// The name of a test is the name of the group, followed by `::` and the name
+/// of the case. Every scheme here can be inverted, so that a test can be found
+/// from its name without scanning the whole root, as nextest does with
+/// `--exact`.
+#[derive(Clone, Default)]
+pub(crate) struct CaseNames {
+    /// A directory to remove from the start of paths before deriving names.
+    strip_prefix: Option<Utf8PathBuf>,
+    kind: CaseNamesKind,
+}
+
+#[derive(Clone, Default)]
+enum CaseNamesKind {
+    /// The path relative to the root, e.g. `nested/foo.txt`.
+    #[default]
+    Path,
+    /// A template with exactly one `{path}` or `{stem}` placeholder, or, until
+    /// [`CaseNames::bind_pattern`] is called, one with capture groups.
+    Template(Template),
+    /// A template whose placeholders are all capture groups in the pattern,
+    /// which is split into literal text and named groups so that paths can be
+    /// rebuilt from names.
+    Captures {
+        template: Template,
+        /// Matches names, with one group per placeholder in the template.
+        regex: fancy_regex::Regex,
+        parts: Vec<PatternPart>,
+    },
+    /// A function from paths to names, along with its inverse.
+    Custom {
+        to_name: Arc<ToNameFn>,
+        from_name: Arc<FromNameFn>,
+    },
+}
+
+/// A path that may correspond to a test case name.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) enum Candidate {
+    /// The path relative to the root.
+    Path(Utf8PathBuf),
+    /// The path relative to the root without its extension. Any file or
+    /// directory with this stem is a candidate.
+    Stem(Utf8PathBuf),
+}
+
+impl CaseNames {
+    /// Parses a template for names, which must have exactly one `{path}` or
+    /// `{stem}` placeholder, or only capture group placeholders.
+    ///
+    /// Templates with capture groups are checked by
+    /// [`bind_pattern`](Self::bind_pattern).
+    pub(crate) fn set_template(&mut self, template: &str) -> Result<(), String> {
+        let template = Template::parse(template)?;
+        if template.capture_names().next().is_none() && !template.is_invertible() {
+            return Err(
+                "case name templates must contain exactly one `{path}` or `{stem}` \
+                        placeholder, so that names can be mapped back to paths"
+                    .to_owned(),
+            );
+        }
+        self.kind = CaseNamesKind::Template(template);
+        Ok(())
+    }
+
+    /// Checks a template with capture groups against the group's pattern,
+    /// and prepares it to be inverted.
+    ///
+    /// Paths are rebuilt from the text captured by each group, so the pattern
+    /// must be made up of literal text and named groups, and the template must
+    /// use every group.
+    pub(crate) fn bind_pattern(&mut self, pattern: Option<&Pattern>) -> Result<(), String> {
+        let template = match &self.kind {
+            CaseNamesKind::Template(template) if template.capture_names().next().is_some() => {
+                template.clone()
+            }
+            _ => return Ok(()),
+        };
+        if !template.only_captures() {
+            return Err(
+                "case name templates with capture groups can't also use `{path}`, \
+                        `{stem}`, `{file_name}`, `{file_stem}`, or `{extension}`"
+                    .to_owned(),
+            );
+        }
+        let parts = match pattern {
+            Some(Pattern::Regex(pattern)) => split_named_groups(pattern),
+            _ => None,
+        }
+        .ok_or(
+            "case name templates with capture groups require a `pattern` made up of literal \
+             text and named groups, anchored with `^` and `$`, so that names can be mapped \
+             back to paths",
+        )?;
+
+        let group_regex = |name: &str| {
+            parts.iter().find_map(|part| match part {
+                PatternPart::Group {
+                    name: group_name,
+                    regex,
+                } if group_name == name => Some(regex.clone()),
+                _ => None,
+            })
+        };
+        if let Some(name) = template
+            .capture_names()
+            .find(|name| group_regex(name).is_none())
+        {
+            return Err(format!("`pattern` has no capture group named `{name}`"));
+        }
+        for part in &parts {
+            if let PatternPart::Group { name, .. } = part {
+                if !template.capture_names().any(|used| used == name) {
+                    return Err(format!(
+                        "case name templates must use every named group in `pattern`, so \
+                         that names can be mapped back to paths, but `{{{name}}}` isn't used"
+                    ));
+                }
+            }
+        }
+
+        let regex = template.to_regex(|name| group_regex(name).unwrap_or_default());
+        let regex = fancy_regex::Regex::new(&regex)
+            .map_err(|error| format!("error combining `pattern` with the template: {error}"))?;
+        self.kind = CaseNamesKind::Captures {
+            template,
+            regex,
+            parts,
+        };
+        Ok(())
+    }
+
+    pub(crate) fn set_custom(
+        &mut self,
+        to_name: impl Fn(&Utf8Path) -> String + Send + Sync + 'static,
+        from_name: impl Fn(&str) -> Option<String> + Send + Sync + 'static,
+    ) {
+        self.kind = CaseNamesKind::Custom {
+            to_name: Arc::new(to_name),
+            from_name: Arc::new(from_name),
+        };
+    }
+
+    pub(crate) fn set_strip_prefix(&mut self, prefix: &str) {
+        let prefix = prefix.trim_end_matches('/');
+        self.strip_prefix = (!prefix.is_empty()).then(|| prefix.into());
+    }
+
+    /// Returns the name of the case at `rel_path`, relative to the root.
+    ///
+    /// `captures` are the named groups captured by the pattern.
+    pub(crate) fn name(&self, rel_path: &Utf8Path, captures: &[(String, String)]) -> String {
+        let rel_path = match &self.strip_prefix {
+            Some(prefix) => rel_path.strip_prefix(prefix).unwrap_or(rel_path),
+            None => rel_path,
+        };
+        match &self.kind {
+            CaseNamesKind::Path => rel_path.to_string(),
+            CaseNamesKind::Template(template) | CaseNamesKind::Captures { template, .. } => {
+                template.expand(rel_path, captures)
+            }
+            CaseNamesKind::Custom { to_name, .. } => to_name(rel_path),
+        }
+    }
+
+    /// Returns the paths that the case named `name` may be at.
+    ///
+    /// The name of each candidate must be checked against `name`, since paths
+    /// outside `strip_prefix` aren't stripped.
+    pub(crate) fn candidates(&self, name: &str) -> Vec<Candidate> {
+        let candidate = match &self.kind {
+            CaseNamesKind::Path => Some(Candidate::Path(name.into())),
+            CaseNamesKind::Template(template) => template.invert(name).map(|part| match part {
+                PathPart::Path(path) => Candidate::Path(path.into()),
+                PathPart::Stem(stem) => Candidate::Stem(stem.into()),
+            }),
+            CaseNamesKind::Captures {
+                template,
+                regex,
+                parts,
+            } => {
+                // Paths are rebuilt from the pattern, so `strip_prefix` doesn't
+                // apply.
+                return invert_captures(template, regex, parts, name)
+                    .map(|path| Candidate::Path(path.into()))
+                    .into_iter()
+                    .collect();
+            }
+            CaseNamesKind::Custom { from_name, .. } => {
+                from_name(name).map(|path| Candidate::Path(path.into()))
+            }
+        };
+        let Some(candidate) = candidate else {
+            return Vec::new();
+        };
+
+        match &self.strip_prefix {
+            Some(prefix) => {
+                let prefixed = match &candidate {
+                    Candidate::Path(path) => Candidate::Path(prefix.join(path)),
+                    Candidate::Stem(stem) => Candidate::Stem(prefix.join(stem)),
+                };
+                vec![prefixed, candidate]
+            }
+            None => vec![candidate],
+        }
+    }
+}
+
+/// Returns the path matched by the pattern split into `parts` whose case is
+/// named `name`.
+fn invert_captures(
+    template: &Template,
+    regex: &fancy_regex::Regex,
+    parts: &[PatternPart],
+    name: &str,
+) -> Option<String> {
+    let captures = regex.captures(name).ok()??;
+    let mut values: Vec<(&str, &str)> = Vec::new();
+    for (ix, capture_name) in template.capture_names().enumerate() {
+        let value = captures.get(ix + 1)?.as_str();
+        match values.iter().find(|(seen, _)| *seen == capture_name) {
+            // A group used twice must have captured the same text both times.
+            Some((_, seen_value)) if *seen_value != value => return None,
+            Some(_) => {}
+            None => values.push((capture_name, value)),
+        }
+    }
+
+    parts
+        .iter()
+        .map(|part| match part {
+            PatternPart::Literal(literal) => Some(literal.as_str()),
+            PatternPart::Group { name, .. } => values
+                .iter()
+                .find(|(capture_name, _)| capture_name == name)
+                .map(|(_, value)| *value),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn invert_names() {
+        let mut stem = CaseNames::default();
+        stem.set_template("{stem}").unwrap();
+        let mut prefixed = CaseNames::default();
+        prefixed.set_template("case-{path}!").unwrap();
+        prefixed.set_strip_prefix("cases/");
+        let mut custom = CaseNames::default();
+        custom.set_custom(
+            |path| path.as_str().replace('/', "."),
+            |name| Some(name.replace('.', "/")),
+        );
+
+        for (names, rel_path, name, candidates) in [
+            (
+                &CaseNames::default(),
+                "a/b.txt",
+                "a/b.txt",
+                vec![Candidate::Path("a/b.txt".into())],
+            ),
+            (&stem, "a/b.txt", "a/b", vec![Candidate::Stem("a/b".into())]),
+            (
+                &prefixed,
+                "cases/a/b.txt",
+                "case-a/b.txt!",
+                vec![
+                    Candidate::Path("cases/a/b.txt".into()),
+                    Candidate::Path("a/b.txt".into()),
+                ],
+            ),
+            (
+                &prefixed,
+                "other/b.txt",
+                "case-other/b.txt!",
+                vec![
+                    Candidate::Path("cases/other/b.txt".into()),
+                    Candidate::Path("other/b.txt".into()),
+                ],
+            ),
+            (&custom, "a/b", "a.b", vec![Candidate::Path("a/b".into())]),
+        ] {
+            assert_eq!(names.name(Utf8Path::new(rel_path), &[]), name, "{rel_path}");
+            assert_eq!(names.candidates(name), candidates, "{name}");
+        }
+
+        assert_eq!(prefixed.candidates("a/b.txt"), []);
+    }
+
+    #[test]
+    fn invert_capture_names() {
+        let pattern = Pattern::Regex(r"^(?P<dialect>mysql|pg)/(?P<case>.+)\.sql$".to_owned());
+        let mut names = CaseNames::default();
+        names.set_template("{dialect}::{case}").unwrap();
+        names.bind_pattern(Some(&pattern)).unwrap();
+
+        let captures = [
+            ("dialect".to_owned(), "pg".to_owned()),
+            ("case".to_owned(), "a/b".to_owned()),
+        ];
+        assert_eq!(
+            names.name(Utf8Path::new("pg/a/b.sql"), &captures),
+            "pg::a/b"
+        );
+        assert_eq!(
+            names.candidates("pg::a/b"),
+            [Candidate::Path("pg/a/b.sql".into())]
+        );
+        assert_eq!(names.candidates("sqlite::a"), []);
+
+        let mut repeated = CaseNames::default();
+        repeated.set_template("{case}-{dialect}-{case}").unwrap();
+        repeated.bind_pattern(Some(&pattern)).unwrap();
+        assert_eq!(
+            repeated.candidates("a-mysql-a"),
+            [Candidate::Path("mysql/a.sql".into())]
+        );
+        assert_eq!(repeated.candidates("a-mysql-b"), []);
+    }
+
+    #[test]
+    fn invalid_capture_templates() {
+        let pattern = Pattern::Regex(r"^(?P<dialect>mysql|pg)/(?P<case>.+)\.sql$".to_owned());
+        for (template, pattern) in [
+            ("{dialect}-{stem}", Some(pattern.clone())),
+            ("{case}", Some(pattern.clone())),
+            ("{dialect}-{case}-{missing}", Some(pattern)),
+            (
+                "{case}",
+                Some(Pattern::Regex(r"(?P<case>.+)\.sql$".to_owned())),
+            ),
+            ("{case}", Some(Pattern::Glob("*.sql".to_owned()))),
+            ("{case}", None),
+        ] {
+            let mut names = CaseNames::default();
+            names.set_template(template).unwrap();
+            assert!(
+                names.bind_pattern(pattern.as_ref()).is_err(),
+                "{template} with {pattern:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn invalid_templates() {
+        for template in ["{file_name}", "{path}-{stem}", "name", "{stem"] {
+            assert!(
+                CaseNames::default().set_template(template).is_err(),
+                "{template}"
+            );
+        }
+    }
+}
@@ -0,0 +1,276 @@
+// Copyright (c) The datatest-stable Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Reporting fixture files that no test group matched.
+
+use crate::{
+    data_source::{TestEntry, WalkFilter},
+    directives::SIDECAR_SUFFIX,
+    pattern::Pattern,
+    snapshot::EXPECTED_SUFFIX,
+    TestGroup, Unit,
+};
+use camino::{Utf8Path, Utf8PathBuf};
+use std::collections::HashSet;
+
+/// What to do with fixture files that no test group matched.
+///
+/// This is passed to [`OrphanCheck::new`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum OrphanAction {
+    /// The harness reports the unmatched files as an error while discovering
+    /// tests, and exits with a failure without running any tests.
+    Fail,
+
+    /// The unmatched files are listed in a warning on standard error, and
+    /// tests run as usual.
+    Warn,
+}
+
+/// An opt-in check for fixture files under a root that no test group matched.
+///
+/// A typo in a `pattern` or a fixture with the wrong extension can otherwise
+/// cause tests to silently not run. With this check, after all test groups
+/// have been expanded, each distinct root is walked, and any files that
+/// aren't used by a group are reported.
+///
+/// A file is used by a group if it's a test case, or if it's the expected
+/// file, snapshot or directives sidecar for a test case. Files in a
+/// subdirectory that's a test case (with [`Unit::Dir`]) are used as well.
+///
+/// This is set with `orphans = { ... }` in [`harness!`](crate::harness), or
+/// with [`Harness::orphans`](crate::Harness::orphans).
+///
+/// The check only runs when all tests are discovered, not when a single test
+/// is run with `--exact`, as nextest does.
+#[derive(Clone, Debug)]
+pub struct OrphanCheck {
+    pub(crate) action: OrphanAction,
+    pub(crate) allow: Vec<String>,
+}
+
+impl OrphanCheck {
+    /// Creates a new check that takes `action` for unmatched files.
+    pub fn new(action: OrphanAction) -> Self {
+        Self {
+            action,
+            allow: Vec::new(),
+        }
+    }
+
+    /// Adds a regular expression for files that are allowed to not be matched
+    /// by any group, such as READMEs or helper files.
+    ///
+    /// As with `pattern`, this is matched against the path relative to the
+    /// root. It can be called several times to allow files matching any of
+    /// several patterns.
+    pub fn allow(mut self, pattern: impl Into<String>) -> Self {
+        self.allow.push(pattern.into());
+        self
+    }
+
+    /// Walks the roots of `groups`, and reports files not in any of the
+    /// corresponding `claims`, which have an element for each root.
+    ///
+    /// Returns an error listing the files with [`OrphanAction::Fail`], or if a
+    /// root can't be read.
+    pub(crate) fn check(&self, groups: &[TestGroup], claims: &[Vec<Claims>]) -> Result<(), String> {
+        let allow: Vec<_> = self
+            .allow
+            .iter()
+            .map(|allow| {
+                let allow = Pattern::Regex(allow.clone());
+                let re = allow.compile();
+                (allow, re)
+            })
+            .collect();
+
+        // Every root of every group, along with the group and its claims.
+        let roots: Vec<_> = groups
+            .iter()
+            .zip(claims)
+            .flat_map(|(group, claims)| {
+                group
+                    .roots
+                    .iter()
+                    .zip(claims)
+                    .map(move |(root, claims)| (group, root, claims))
+            })
+            .collect();
+
+        let mut message = String::new();
+        let mut orphan_count = 0;
+        for (index, (group, root, _)) in roots.iter().enumerate() {
+            // Only walk the outermost roots, since nested roots are walked as
+            // part of them. If several groups share a root, walk it once.
+            let is_nested = roots
+                .iter()
+                .enumerate()
+                .any(
+                    |(other_index, (_, other, _))| match root.source.path_within(&other.source) {
+                        Some(path) if path.as_str().is_empty() => other_index < index,
+                        Some(_) => true,
+                        None => false,
+                    },
+                );
+            // Missing roots of optional groups have no files to check.
+            if is_nested || !root.source.exists() {
+                continue;
+            }
+            // The groups with roots under this one, along with the paths of
+            // their roots relative to it.
+            let within: Vec<_> = roots
+                .iter()
+                .filter_map(|(other_group, other, claims)| {
+                    let path = other.source.path_within(&root.source)?;
+                    Some((other_group, path, claims))
+                })
+                .collect();
+            let filter = WalkFilter {
+                hidden: false,
+                ignore_files: within.iter().any(|(other, _, _)| other.gitignore),
+            };
+
+            let mut orphans = Vec::new();
+            for entry_res in root.source.walk(Unit::File, filter) {
+                let entry = entry_res.map_err(|error| {
+                    format!(
+                        "error reading {} for the orphan check: {error}",
+                        group.display_root(root)
+                    )
+                })?;
+                let path = entry.match_path();
+                let is_claimed = within.iter().any(|(_, root_path, claims)| {
+                    path.strip_prefix(root_path)
+                        .is_ok_and(|path| claims.is_claimed(path))
+                });
+                if is_claimed
+                    || allow
+                        .iter()
+                        .any(|(allow, re)| crate::runner::is_match(re, allow, path.as_str()))
+                {
+                    continue;
+                }
+                orphans.push(path.to_owned());
+            }
+            if orphans.is_empty() {
+                continue;
+            }
+
+            orphans.sort_unstable();
+            orphan_count += orphans.len();
+            message.push_str(&format!("\n  in {}:", group.display_root(root)));
+            for orphan in orphans {
+                message.push_str(&format!("\n    {orphan}"));
+            }
+        }
+
+        if orphan_count == 0 {
+            return Ok(());
+        }
+        let message = format!(
+            "{orphan_count} fixture file{} not matched by any test group:{message}\n\
+             (fix the patterns of test groups, or allow these files in the orphan check)",
+            if orphan_count == 1 { " was" } else { "s were" },
+        );
+        match self.action {
+            OrphanAction::Fail => Err(message),
+            OrphanAction::Warn => {
+                eprintln!("warning: {message}");
+                Ok(())
+            }
+        }
+    }
+}
+
+/// The files under a root that a test group uses.
+#[derive(Debug, Default)]
+pub(crate) struct Claims {
+    /// Test case files and their expected files.
+    files: HashSet<Utf8PathBuf>,
+    /// Test case directories, with `unit = dir`.
+    dirs: HashSet<Utf8PathBuf>,
+}
+
+impl Claims {
+    /// Records that `entry` is a test case.
+    pub(crate) fn claim_entry(&mut self, entry: &TestEntry, unit: Unit) {
+        let path = entry.match_path().to_owned();
+        match unit {
+            Unit::File => self.files.insert(path),
+            Unit::Dir => self.dirs.insert(path),
+        };
+    }
+
+    /// Records that the file at `path` is used by a test case, for example as
+    /// its expected file.
+    pub(crate) fn claim_file(&mut self, path: impl Into<Utf8PathBuf>) {
+        self.files.insert(path.into());
+    }
+
+    fn is_claimed(&self, path: &Utf8Path) -> bool {
+        if self.files.contains(path)
+            || path
+                .ancestors()
+                .skip(1)
+                .any(|ancestor| self.dirs.contains(ancestor))
+        {
+            return true;
+        }
+
+        let path = path.as_str();
+        if let Some(case) = path.strip_suffix(SIDECAR_SUFFIX) {
+            let case = Utf8Path::new(case);
+            return self.files.contains(case) || self.dirs.contains(case);
+        }
+        if let Some(mut case) = path.strip_suffix(EXPECTED_SUFFIX) {
+            // Snapshots are at `<case>.expected`, or `<case>.<section>.expected`
+            // for sections. Section names can contain `.`, so try each
+            // possible split.
+            loop {
+                if self.files.contains(Utf8Path::new(case)) {
+                    return true;
+                }
+                match case.rfind('.') {
+                    Some(index) if !case[index..].contains('/') => case = &case[..index],
+                    _ => return false,
+                }
+            }
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn claimed_paths() {
+        let mut claims = Claims::default();
+        claims.files.insert("a.txt".into());
+        claims.files.insert("nested/b.in".into());
+        claims.dirs.insert("case".into());
+
+        for (path, expected) in [
+            ("a.txt", true),
+            ("b.txt", false),
+            ("nested/b.in", true),
+            ("nested/b.out", false),
+            ("case/input.txt", true),
+            ("case/deeper/input.txt", true),
+            ("case.txt", false),
+            ("a.txt.datatest.toml", true),
+            ("case.datatest.toml", true),
+            ("b.txt.datatest.toml", false),
+            ("a.txt.expected", true),
+            ("a.txt.section.name.expected", true),
+            ("nested/b.in.expected", true),
+            ("nested/b.expected", false),
+            ("a.expected", false),
+        ] {
+            assert_eq!(claims.is_claimed(Utf8Path::new(path)), expected, "{path}");
+        }
+    }
+}
@@ -0,0 +1,301 @@
+// Copyright (c) The datatest-stable Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Pairing each input fixture with an expected-output file, and the path
+//! templates used for it.
+
+use camino::Utf8Path;
+
+/// What to do when an input file doesn't have an expected file.
+///
+/// This is set with the `on_missing` key in [`harness!`](crate::harness), or
+/// with [`TestGroup::on_missing`](crate::TestGroup::on_missing).
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum OnMissing {
+    /// The test fails. This is the default.
+    #[default]
+    Fail,
+
+    /// The input file isn't a test case.
+    Skip,
+
+    /// The test function is passed `None` as the expected contents. The test
+    /// function must accept an `Option`.
+    PassNone,
+}
+
+/// A template for the path to the expected file, such as `{stem}.out`.
+///
+/// Templates are also used for the names of test cases.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct Template {
+    segments: Vec<Segment>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum Placeholder {
+    /// `{path}`: the path to the input file, e.g. `cases/foo.rs`.
+    Path,
+    /// `{stem}`: the path without its extension, e.g. `cases/foo`.
+    Stem,
+    /// `{file_name}`: the file name, e.g. `foo.rs`.
+    FileName,
+    /// `{file_stem}`: the file name without its extension, e.g. `foo`.
+    FileStem,
+    /// `{extension}`: the extension, e.g. `rs`.
+    Extension,
+    /// `{name}` for any other identifier: the text matched by the capture
+    /// group `name` in the pattern.
+    Capture(String),
+}
+
+/// The part of a path that a template was expanded from, as returned by
+/// [`Template::invert`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) enum PathPart {
+    /// The path relative to the root.
+    Path(String),
+    /// The path relative to the root, without its extension.
+    Stem(String),
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum Segment {
+    Literal(String),
+    Placeholder(Placeholder),
+}
+
+impl Template {
+    /// Parses a template. `{{` and `}}` stand for literal braces.
+    pub(crate) fn parse(template: &str) -> Result<Self, String> {
+        let mut segments = Vec::new();
+        let mut literal = String::new();
+        let mut chars = template.chars();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.as_str().starts_with('{') => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '}' if chars.as_str().starts_with('}') => {
+                    chars.next();
+                    literal.push('}');
+                }
+                '{' => {
+                    let rest = chars.as_str();
+                    let end = rest.find('}').ok_or("unclosed `{`")?;
+                    let placeholder = match &rest[..end] {
+                        "path" => Placeholder::Path,
+                        "stem" => Placeholder::Stem,
+                        "file_name" => Placeholder::FileName,
+                        "file_stem" => Placeholder::FileStem,
+                        "extension" => Placeholder::Extension,
+                        name if is_identifier(name) => Placeholder::Capture(name.to_owned()),
+                        other => {
+                            return Err(format!(
+                                "unknown placeholder `{{{other}}}` (expected `{{path}}`, \
+                                 `{{stem}}`, `{{file_name}}`, `{{file_stem}}`, `{{extension}}`, \
+                                 or the name of a capture group)"
+                            ))
+                        }
+                    };
+                    chars = rest[end + 1..].chars();
+                    if !literal.is_empty() {
+                        segments.push(Segment::Literal(std::mem::take(&mut literal)));
+                    }
+                    segments.push(Segment::Placeholder(placeholder));
+                }
+                '}' => return Err("unmatched `}`".to_owned()),
+                c => literal.push(c),
+            }
+        }
+        if !literal.is_empty() {
+            segments.push(Segment::Literal(literal));
+        }
+
+        Ok(Self { segments })
+    }
+
+    /// Returns the path to the expected file for the input at `rel_path`,
+    /// relative to the root.
+    ///
+    /// `captures` are the named groups captured by the pattern. Groups that
+    /// didn't participate in the match expand to nothing.
+    pub(crate) fn expand(&self, rel_path: &Utf8Path, captures: &[(String, String)]) -> String {
+        let extension = rel_path.extension().unwrap_or_default();
+        let without_extension = |path: &str| {
+            if extension.is_empty() {
+                path.to_owned()
+            } else {
+                path[..path.len() - extension.len() - 1].to_owned()
+            }
+        };
+
+        let mut ret = String::new();
+        for segment in &self.segments {
+            match segment {
+                Segment::Literal(literal) => ret.push_str(literal),
+                Segment::Placeholder(placeholder) => ret.push_str(&match placeholder {
+                    Placeholder::Path => rel_path.to_string(),
+                    Placeholder::Stem => without_extension(rel_path.as_str()),
+                    Placeholder::FileName => rel_path.file_name().unwrap_or_default().to_owned(),
+                    Placeholder::FileStem => {
+                        without_extension(rel_path.file_name().unwrap_or_default())
+                    }
+                    Placeholder::Extension => extension.to_owned(),
+                    Placeholder::Capture(name) => captures
+                        .iter()
+                        .find(|(capture_name, _)| capture_name == name)
+                        .map(|(_, value)| value.clone())
+                        .unwrap_or_default(),
+                }),
+            }
+        }
+        ret
+    }
+
+    /// Returns the names of the capture groups used by the template, in
+    /// order. Names may be repeated.
+    pub(crate) fn capture_names(&self) -> impl Iterator<Item = &str> {
+        self.placeholders()
+            .filter_map(|placeholder| match placeholder {
+                Placeholder::Capture(name) => Some(name.as_str()),
+                _ => None,
+            })
+    }
+
+    /// Returns true if every placeholder in the template is a capture group.
+    pub(crate) fn only_captures(&self) -> bool {
+        self.placeholders()
+            .all(|placeholder| matches!(placeholder, Placeholder::Capture(_)))
+    }
+
+    /// Returns a regex matching the expansions of the template, with one
+    /// unnamed group for each name returned by
+    /// [`capture_names`](Self::capture_names).
+    ///
+    /// `group_regex` returns the regex for the capture group with each name.
+    pub(crate) fn to_regex(&self, group_regex: impl Fn(&str) -> String) -> String {
+        let mut regex = String::from("^");
+        for segment in &self.segments {
+            match segment {
+                Segment::Literal(literal) => regex.push_str(&fancy_regex::escape(literal)),
+                Segment::Placeholder(Placeholder::Capture(name)) => {
+                    regex.push_str(&format!("((?:{}))", group_regex(name)));
+                }
+                Segment::Placeholder(_) => regex.push_str("(?:.*)"),
+            }
+        }
+        regex.push('$');
+        regex
+    }
+
+    fn placeholders(&self) -> impl Iterator<Item = &Placeholder> {
+        self.segments.iter().filter_map(|segment| match segment {
+            Segment::Literal(_) => None,
+            Segment::Placeholder(placeholder) => Some(placeholder),
+        })
+    }
+
+    /// Returns true if the template has exactly one placeholder, which is
+    /// `{path}` or `{stem}`, so it can be inverted.
+    pub(crate) fn is_invertible(&self) -> bool {
+        let mut placeholders = self.placeholders();
+        matches!(
+            (placeholders.next(), placeholders.next()),
+            (Some(Placeholder::Path | Placeholder::Stem), None)
+        )
+    }
+
+    /// For templates where [`is_invertible`](Self::is_invertible) is true,
+    /// returns the part of the path that `expanded` was expanded from.
+    ///
+    /// Returns `None` if `expanded` doesn't match the literal parts of the
+    /// template.
+    pub(crate) fn invert(&self, expanded: &str) -> Option<PathPart> {
+        let index = self
+            .segments
+            .iter()
+            .position(|segment| matches!(segment, Segment::Placeholder(_)))?;
+        let literal = |segments: &[Segment]| -> String {
+            segments
+                .iter()
+                .map(|segment| match segment {
+                    Segment::Literal(literal) => literal.as_str(),
+                    Segment::Placeholder(_) => "",
+                })
+                .collect()
+        };
+        let part = expanded
+            .strip_prefix(literal(&self.segments[..index]).as_str())?
+            .strip_suffix(literal(&self.segments[index + 1..]).as_str())?;
+        if part.is_empty() {
+            return None;
+        }
+
+        match self.segments[index] {
+            Segment::Placeholder(Placeholder::Path) => Some(PathPart::Path(part.to_owned())),
+            Segment::Placeholder(Placeholder::Stem) => Some(PathPart::Stem(part.to_owned())),
+            _ => None,
+        }
+    }
+}
+
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expand_templates() {
+        for (template, rel_path, expected) in [
+            ("{stem}.out", "foo.in", "foo.out"),
+            ("{stem}.out", "nested/foo.in", "nested/foo.out"),
+            ("{path}.out", "nested/foo.in", "nested/foo.in.out"),
+            (
+                "expected/{file_stem}.stderr",
+                "cases/foo.rs",
+                "expected/foo.stderr",
+            ),
+            ("{file_name}/{extension}", "a/b.c.d", "b.c.d/d"),
+            ("{file_stem}.out", "no-extension", "no-extension.out"),
+            ("{{{stem}}}", "a.b", "{a}"),
+            ("{dialect}/{case}.out", "pg/a.sql", "pg/a.out"),
+            ("{missing}{stem}", "a.b", "a"),
+        ] {
+            let captures = [
+                ("dialect".to_owned(), "pg".to_owned()),
+                ("case".to_owned(), "a".to_owned()),
+            ];
+            assert_eq!(
+                Template::parse(template)
+                    .unwrap()
+                    .expand(Utf8Path::new(rel_path), &captures),
+                expected,
+                "template '{template}' for '{rel_path}'"
+            );
+        }
+    }
+
+    #[test]
+    fn invalid_templates() {
+        for (template, error) in [
+            ("{stem", "unclosed `{`"),
+            ("stem}", "unmatched `}`"),
+            (
+                "{file-name}.out",
+                "unknown placeholder `{file-name}` (expected `{path}`, `{stem}`, \
+                 `{file_name}`, `{file_stem}`, `{extension}`, or the name of a capture group)",
+            ),
+        ] {
+            assert_eq!(Template::parse(template).unwrap_err(), error);
+        }
+    }
+}
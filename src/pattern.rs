@@ -0,0 +1,383 @@
+// Copyright (c) The datatest-stable Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use std::fmt;
+
+/// How a test group selects the files it runs against.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) enum Pattern {
+    /// A regular expression, matched anywhere in the path unless anchored.
+    Regex(String),
+    /// A glob, matched against the whole path.
+    Glob(String),
+}
+
+impl Pattern {
+    /// Compiles the pattern into a regex.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the pattern is invalid.
+    pub(crate) fn compile(&self) -> fancy_regex::Regex {
+        match self {
+            Pattern::Regex(pattern) => compile_regex(pattern),
+            Pattern::Glob(glob) => {
+                let regex = glob_to_regex(glob, true)
+                    .unwrap_or_else(|error| panic!("invalid glob: '{}': {}", glob, error));
+                fancy_regex::Regex::new(&regex)
+                    .unwrap_or_else(|error| panic!("invalid glob: '{}': {}", glob, error))
+            }
+        }
+    }
+}
+
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Pattern::Regex(pattern) => write!(f, "pattern '{pattern}'"),
+            Pattern::Glob(glob) => write!(f, "glob '{glob}'"),
+        }
+    }
+}
+
+fn compile_regex(pattern: &str) -> fancy_regex::Regex {
+    fancy_regex::Regex::new(pattern)
+        .unwrap_or_else(|_| panic!("invalid regular expression: '{}'", pattern))
+}
+
+/// Returns the index after the character class starting at `ix`, or `None` if
+/// it isn't closed.
+fn skip_class(bytes: &[u8], ix: usize) -> Option<usize> {
+    let mut ix = ix + 1;
+    let mut nest = 1;
+    if ix < bytes.len() && bytes[ix] == b'^' {
+        ix += 1;
+    }
+    // `]` is a literal right after `[` or `[^`.
+    if ix < bytes.len() && bytes[ix] == b']' {
+        ix += 1;
+    }
+    while ix < bytes.len() {
+        match bytes[ix] {
+            b'\\' => ix += 1,
+            b'[' => nest += 1,
+            b']' => {
+                nest -= 1;
+                if nest == 0 {
+                    return Some(ix + 1);
+                }
+            }
+            _ => {}
+        }
+        ix += 1;
+    }
+    None
+}
+
+/// A part of a regex split by [`split_named_groups`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) enum PatternPart {
+    /// Text that the regex matches literally.
+    Literal(String),
+    /// A named group, along with the regex inside it.
+    Group { name: String, regex: String },
+}
+
+/// Splits an anchored regex like `^(?P<dialect>\w+)/(?P<case>.+)\.sql$` into
+/// literal text and named groups, so that a path matched by it can be rebuilt
+/// from the text captured by each group.
+///
+/// Returns `None` if the regex isn't anchored at both ends, or if it has
+/// anything other than literal characters outside its named groups, such as a
+/// repeated group.
+pub(crate) fn split_named_groups(pattern: &str) -> Option<Vec<PatternPart>> {
+    let body = pattern.strip_prefix('^')?.strip_suffix('$')?;
+    // A `$` preceded by an odd number of backslashes is a literal.
+    if (body.len() - body.trim_end_matches('\\').len()) % 2 == 1 {
+        return None;
+    }
+
+    let bytes = body.as_bytes();
+    let mut parts = Vec::new();
+    let mut literal = String::new();
+    let mut ix = 0;
+    while ix < body.len() {
+        let c = body[ix..].chars().next()?;
+        match c {
+            '\\' => {
+                let escaped = body[ix + 1..].chars().next()?;
+                if !escaped.is_ascii_punctuation() {
+                    return None;
+                }
+                literal.push(escaped);
+                ix += 1 + escaped.len_utf8();
+            }
+            '(' => {
+                let rest = &body[ix + 1..];
+                let group = rest
+                    .strip_prefix("?P<")
+                    .or_else(|| rest.strip_prefix("?<"))?;
+                let name_end = group.find('>')?;
+                let name = &group[..name_end];
+                // This also rules out lookbehinds like `(?<=` and `(?<!`.
+                if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+                    return None;
+                }
+                let start = body.len() - group.len() + name_end + 1;
+                let end = skip_group(bytes, start)?;
+                if matches!(bytes.get(end + 1), Some(b'*' | b'+' | b'?' | b'{')) {
+                    return None;
+                }
+                if !literal.is_empty() {
+                    parts.push(PatternPart::Literal(std::mem::take(&mut literal)));
+                }
+                parts.push(PatternPart::Group {
+                    name: name.to_owned(),
+                    regex: body[start..end].to_owned(),
+                });
+                ix = end + 1;
+            }
+            '.' | '*' | '+' | '?' | '{' | '[' | ')' | '|' | '^' | '$' => return None,
+            c => {
+                literal.push(c);
+                ix += c.len_utf8();
+            }
+        }
+    }
+    if !literal.is_empty() {
+        parts.push(PatternPart::Literal(literal));
+    }
+    Some(parts)
+}
+
+/// Returns the index of the `)` closing the group whose contents start at
+/// `ix`, or `None` if it isn't closed.
+fn skip_group(bytes: &[u8], mut ix: usize) -> Option<usize> {
+    let mut depth = 0;
+    while ix < bytes.len() {
+        match bytes[ix] {
+            b'\\' => ix += 1,
+            b'[' => {
+                ix = skip_class(bytes, ix)?;
+                continue;
+            }
+            b'(' => depth += 1,
+            b')' if depth == 0 => return Some(ix),
+            b')' => depth -= 1,
+            _ => {}
+        }
+        ix += 1;
+    }
+    None
+}
+
+/// Translates a glob into an anchored regex.
+///
+/// The following syntax is supported:
+///
+/// * `*` matches any sequence of characters other than `/`.
+/// * `?` matches any single character other than `/`.
+/// * `**` matches any sequence of characters including `/`. At the start of a
+///   path component, `**/` also matches zero components.
+/// * `[abc]`, `[a-z]` and `[!abc]` match a single character in (or not in) a set.
+/// * `{a,b}` matches either of the alternatives, which may themselves contain
+///   glob syntax. If `alternation` is false, braces and commas are literal
+///   characters, as in `.gitignore` files.
+/// * `\` escapes the following character.
+///
+/// `datatest-stable-macros` has a copy of this in `src/glob.rs`, to check
+/// literal globs at compile time. Keep the two in sync.
+pub(crate) fn glob_to_regex(glob: &str, alternation: bool) -> Result<String, String> {
+    let mut regex = String::from("^");
+    let mut chars = glob.chars().peekable();
+    // The number of currently open `{` alternations.
+    let mut depth = 0;
+    // Whether the previous character ended a path component, or started the
+    // glob.
+    let mut at_component_start = true;
+
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                if at_component_start && chars.next_if_eq(&'/').is_some() {
+                    // A `**/` component matches zero or more components.
+                    regex.push_str("(?:[^/]*/)*");
+                    continue;
+                }
+                regex.push_str(".*");
+            }
+            '*' => regex.push_str("[^/]*"),
+            '?' => regex.push_str("[^/]"),
+            '[' => {
+                regex.push('[');
+                if chars.next_if_eq(&'!').is_some() {
+                    regex.push('^');
+                }
+                // A `]` right after the opening bracket is part of the set.
+                if chars.next_if_eq(&']').is_some() {
+                    regex.push_str("\\]");
+                }
+                loop {
+                    match chars.next() {
+                        Some(']') => break,
+                        Some('\\') => match chars.next() {
+                            Some(c) => push_escaped(&mut regex, c),
+                            None => return Err("trailing `\\`".to_owned()),
+                        },
+                        Some(c @ ('[' | '&' | '~' | '^')) => push_escaped(&mut regex, c),
+                        Some(c) => regex.push(c),
+                        None => return Err("unclosed `[`".to_owned()),
+                    }
+                }
+                regex.push(']');
+            }
+            '{' if alternation => {
+                depth += 1;
+                regex.push_str("(?:");
+            }
+            ',' if depth > 0 => regex.push('|'),
+            '}' if depth > 0 => {
+                depth -= 1;
+                regex.push(')');
+            }
+            '}' if alternation => return Err("unmatched `}`".to_owned()),
+            '\\' => match chars.next() {
+                Some(c) => push_escaped(&mut regex, c),
+                None => return Err("trailing `\\`".to_owned()),
+            },
+            c => push_escaped(&mut regex, c),
+        }
+        at_component_start = c == '/' || (alternation && matches!(c, '{' | ','));
+    }
+
+    if depth > 0 {
+        return Err("unclosed `{`".to_owned());
+    }
+    regex.push('$');
+    Ok(regex)
+}
+
+fn push_escaped(regex: &mut String, c: char) {
+    if "\\.+*?()|[]{}^$#&-~".contains(c) {
+        regex.push('\\');
+    }
+    regex.push(c);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(glob: &str, path: &str) -> bool {
+        Pattern::Glob(glob.to_owned())
+            .compile()
+            .is_match(path)
+            .unwrap()
+    }
+
+    #[test]
+    fn glob_matching() {
+        for (glob, path, expected) in [
+            ("*.txt", "foo.txt", true),
+            ("*.txt", "foo.txt.bak", false),
+            ("*.txt", "dir/foo.txt", false),
+            ("**/*.txt", "foo.txt", true),
+            ("**/*.txt", "dir/sub/foo.txt", true),
+            ("dir/**", "dir/sub/foo.txt", true),
+            ("dir/**/foo.txt", "dir/foo.txt", true),
+            ("dir/**/foo.txt", "dir/a/b/foo.txt", true),
+            ("dir/**/foo.txt", "other/foo.txt", false),
+            ("a**b", "a/x/b", true),
+            ("?.txt", "a.txt", true),
+            ("?.txt", "ab.txt", false),
+            ("cases/{ok,err}/*.rs", "cases/ok/a.rs", true),
+            ("cases/{ok,err}/*.rs", "cases/err/b.rs", true),
+            ("cases/{ok,err}/*.rs", "cases/skip/c.rs", false),
+            ("{**/,}*.json", "a/b.json", true),
+            ("[abc].txt", "b.txt", true),
+            ("[!abc].txt", "b.txt", false),
+            ("[a-c].txt", "c.txt", true),
+            ("[]].txt", "].txt", true),
+            ("\\*.txt", "*.txt", true),
+            ("\\*.txt", "a.txt", false),
+            ("a+(b).txt", "a+(b).txt", true),
+            ("::colon::dir/*.txt", "::colon::dir/a.txt", true),
+        ] {
+            assert_eq!(
+                matches(glob, path),
+                expected,
+                "glob '{glob}' matching '{path}'"
+            );
+        }
+    }
+
+    #[test]
+    fn invalid_globs() {
+        for (glob, error) in [
+            ("[abc", "unclosed `[`"),
+            ("{a,b", "unclosed `{`"),
+            ("a}", "unmatched `}`"),
+            ("a\\", "trailing `\\`"),
+        ] {
+            assert_eq!(
+                glob_to_regex(glob, true).unwrap_err(),
+                error,
+                "glob '{glob}'"
+            );
+        }
+    }
+
+    #[test]
+    fn split_patterns() {
+        use PatternPart::*;
+
+        let group = |name: &str, regex: &str| Group {
+            name: name.to_owned(),
+            regex: regex.to_owned(),
+        };
+        for (pattern, parts) in [
+            (
+                r"^(?P<dialect>mysql|pg)/(?P<case>.+)\.sql$",
+                vec![
+                    group("dialect", "mysql|pg"),
+                    Literal("/".to_owned()),
+                    group("case", ".+"),
+                    Literal(".sql".to_owned()),
+                ],
+            ),
+            (
+                r"^cases/(?<name>[^/)]+(?:\.\w+)?)$",
+                vec![
+                    Literal("cases/".to_owned()),
+                    group("name", r"[^/)]+(?:\.\w+)?"),
+                ],
+            ),
+            (r"^a\$$", vec![Literal("a$".to_owned())]),
+        ] {
+            assert_eq!(split_named_groups(pattern), Some(parts), "{pattern}");
+        }
+
+        for pattern in [
+            r"(?P<case>.+)\.sql$",
+            r"^(?P<case>.+)\.sql",
+            r"^(?P<case>.+)\.sql\$",
+            r"^(?P<case>.+).sql$",
+            r"^(?P<case>\w)+$",
+            r"^(\w+)/(?P<case>\w+)$",
+            r"^(?<=a)(?P<case>\w+)$",
+            r"^\w(?P<case>\w+)$",
+            r"^a|(?P<case>\w+)$",
+        ] {
+            assert_eq!(split_named_groups(pattern), None, "{pattern}");
+        }
+    }
+
+    #[test]
+    fn without_alternation() {
+        let regex = glob_to_regex("{a,b}}.txt", false).unwrap();
+        let regex = fancy_regex::Regex::new(&regex).unwrap();
+        assert!(regex.is_match("{a,b}}.txt").unwrap());
+        assert!(!regex.is_match("a.txt").unwrap());
+    }
+}
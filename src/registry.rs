@@ -0,0 +1,58 @@
+// Copyright (c) The datatest-stable Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Test groups declared with `#[files]`, which are collected at startup.
+
+use crate::TestGroup;
+
+/// A test group declared with [`files`](crate::files).
+#[doc(hidden)]
+pub struct Registration {
+    group: fn() -> TestGroup,
+}
+
+impl Registration {
+    pub const fn new(group: fn() -> TestGroup) -> Self {
+        Self { group }
+    }
+}
+
+inventory::collect!(Registration);
+
+/// Returns all groups declared with `#[files]`, sorted by name.
+pub(crate) fn registered_groups() -> Vec<TestGroup> {
+    let mut groups: Vec<_> = inventory::iter::<Registration>
+        .into_iter()
+        .map(|registration| (registration.group)())
+        .collect();
+    // The order in which registrations are collected is unspecified.
+    groups.sort_by(|a, b| a.test_name.cmp(&b.test_name));
+    groups
+}
+
+/// Returns the default name of a group declared with `#[files]` on a
+/// function.
+///
+/// This is the path to the function from the crate root, as it would be
+/// written for `test` in `harness!`.
+#[doc(hidden)]
+pub fn default_group_name(module_path: &str, function: &str) -> String {
+    match module_path.split_once("::") {
+        Some((_, module)) => format!("{module}::{function}"),
+        None => function.to_owned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_group_names() {
+        assert_eq!(default_group_name("my_crate", "my_test"), "my_test");
+        assert_eq!(
+            default_group_name("my_crate::parser::cases", "my_test"),
+            "parser::cases::my_test"
+        );
+    }
+}
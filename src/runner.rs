@@ -1,20 +1,45 @@
 // Copyright (c) The datatest-stable Contributors
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
-use crate::{data_source::TestEntry, DataSource, Result};
+#[cfg(any(feature = "json", feature = "toml", feature = "yaml"))]
+use crate::Format;
+use crate::{
+    context::TestContext,
+    data_source::TestEntry,
+    directives::{Directives, SIDECAR_SUFFIX},
+    harness::Root,
+    orphans::{Claims, OrphanCheck},
+    pairing::OnMissing,
+    pattern::Pattern,
+    Result, Snapshot, TestGroup,
+};
 use camino::{Utf8Path, Utf8PathBuf};
-use libtest_mimic::{Arguments, Trial};
-use std::{path::Path, process::ExitCode};
-
-#[doc(hidden)]
-pub fn runner(requirements: &[Requirements]) -> ExitCode {
+use libtest_mimic::{Arguments, Completion, Trial};
+use std::{
+    panic::{self, AssertUnwindSafe},
+    path::Path,
+    process::ExitCode,
+    sync::Arc,
+};
+
+pub(crate) fn run(groups: &[TestGroup], orphans: Option<&OrphanCheck>) -> ExitCode {
     if let Some(cwd) = custom_cwd() {
         std::env::set_current_dir(cwd).expect("set custom working directory");
     }
 
     let args = Arguments::from_args();
 
-    let tests = find_tests(&args, requirements);
+    let tests = match find_tests(&args, groups, orphans) {
+        Ok(tests) => tests,
+        Err(errors) => {
+            // These are mistakes in the harness rather than in the code under
+            // test, so report them without running any tests.
+            for error in errors {
+                eprintln!("error: {error}");
+            }
+            return ExitCode::FAILURE;
+        }
+    };
 
     let conclusion = libtest_mimic::run(&args, tests);
 
@@ -33,12 +58,24 @@ fn custom_cwd() -> Option<Utf8PathBuf> {
     std::env::var("__DATATEST_CWD").ok().map(Utf8PathBuf::from)
 }
 
-fn find_tests(args: &Arguments, requirements: &[Requirements]) -> Vec<Trial> {
+/// Returns the tests to run, or the errors found while expanding test groups.
+fn find_tests(
+    args: &Arguments,
+    groups: &[TestGroup],
+    orphans: Option<&OrphanCheck>,
+) -> std::result::Result<Vec<Trial>, Vec<String>> {
     let tests: Vec<_> = if let Some(exact_filter) = exact_filter(args) {
-        let exact_tests: Vec<_> = requirements
-            .iter()
-            .filter_map(|req| req.exact(exact_filter))
-            .collect();
+        let mut exact_tests = Vec::new();
+        let mut errors = Vec::new();
+        for group in groups {
+            match group.exact(exact_filter) {
+                Ok(trial) => exact_tests.extend(trial),
+                Err(error) => errors.push(error),
+            }
+        }
+        if !errors.is_empty() {
+            return Err(errors);
+        }
 
         match NextestKind::determine() {
             NextestKind::InUse { process_per_test } => {
@@ -59,11 +96,50 @@ fn find_tests(args: &Arguments, requirements: &[Requirements]) -> Vec<Trial> {
     } else if is_full_scan_forbidden(args) {
         panic!("Exact filter was expected to be used");
     } else {
-        let mut tests: Vec<_> = requirements.iter().flat_map(|req| req.expand()).collect();
+        let mut claims: Vec<Vec<_>> = groups
+            .iter()
+            .map(|group| group.roots.iter().map(|_| Claims::default()).collect())
+            .collect();
+        let mut tests = Vec::new();
+        let mut errors = Vec::new();
+        for (group, claims) in groups.iter().zip(&mut claims) {
+            match group.expand(claims) {
+                Ok(group_tests) => tests.extend(group_tests),
+                Err(group_errors) => errors.extend(group_errors),
+            }
+        }
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+        if let Some(orphans) = orphans {
+            orphans
+                .check(groups, &claims)
+                .map_err(|error| vec![error])?;
+        }
         tests.sort_unstable_by(|a, b| a.name().cmp(b.name()));
+        check_duplicate_names(&tests).map_err(|error| vec![error])?;
         tests
     };
-    tests
+    Ok(tests)
+}
+
+/// Returns an error if several tests have the same name. `tests` must be
+/// sorted by name.
+fn check_duplicate_names(tests: &[Trial]) -> std::result::Result<(), String> {
+    let mut duplicates: Vec<_> = tests
+        .windows(2)
+        .filter(|pair| pair[0].name() == pair[1].name())
+        .map(|pair| pair[0].name())
+        .collect();
+    duplicates.dedup();
+    if duplicates.is_empty() {
+        return Ok(());
+    }
+    Err(format!(
+        "found several tests with each of these names: {} -- test names must be unique, \
+         so check that `case_name` and `strip_prefix` don't map several files to the same name",
+        duplicates.join(", ")
+    ))
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -97,129 +173,467 @@ fn exact_filter(args: &Arguments) -> Option<&str> {
     }
 }
 
-#[doc(hidden)]
-pub struct Requirements {
-    test: TestFn,
-    test_name: String,
-    root: DataSource,
-    pattern: String,
-}
-
-impl Requirements {
-    #[doc(hidden)]
-    pub fn new(test: TestFn, test_name: String, root: DataSource, pattern: String) -> Self {
-        // include_dir data sources aren't compatible with test functions that
-        // don't accept the contents as an argument.
-        if !test.loads_data() && root.is_in_memory() {
-            panic!(
-                "test data for '{}' is stored in memory, so it \
-                must accept file contents as an argument",
-                test_name
-            );
+/// The last part of the name of the placeholder test for a missing root of an
+/// optional group.
+const MISSING_ROOT: &str = "(missing root)";
+
+impl TestGroup {
+    /// Creates a trial for `entry` under `root`, or returns `None` if the
+    /// entry should be skipped because its expected file is missing.
+    ///
+    /// Returns an error if the entry's directives couldn't be read.
+    fn trial(
+        &self,
+        root: &Root,
+        mut entry: TestEntry,
+    ) -> std::result::Result<Option<Trial>, String> {
+        // The path to the expected file, if it's missing and the test should
+        // fail.
+        let mut missing_expected = None;
+        if let Some(template) = &self.expected {
+            let expected_path = template.expand(entry.match_path(), entry.captures());
+            match root.source.existing_file(&expected_path) {
+                Some(expected) => entry = entry.with_expected(expected),
+                None => match self.on_missing {
+                    OnMissing::Fail => missing_expected = Some(expected_path),
+                    OnMissing::Skip => return Ok(None),
+                    OnMissing::PassNone => {}
+                },
+            }
         }
 
-        Self {
-            test,
-            test_name,
-            root,
-            pattern,
-        }
+        let directives = if self.directives {
+            entry.read_directives().map_err(|error| error.to_string())?
+        } else {
+            Directives::default()
+        };
+
+        let testfn = self.test.clone();
+        let name = entry.derive_test_name(&root.test_name(&self.test_name), &self.names);
+        let context = TestContext::new(
+            entry,
+            self.test_name.clone(),
+            root.source.dir_path().map(Utf8Path::to_path_buf),
+        );
+        let root = self.display_root(root);
+        let trial = Trial::test(name, move || {
+            if let Some(expected_path) = &missing_expected {
+                return Err(format!(
+                    "expected file '{expected_path}' for '{}' not found in {root}",
+                    context.rel_path()
+                )
+                .into());
+            }
+            if directives.should_fail {
+                // The test function may either return an error or panic.
+                match panic::catch_unwind(AssertUnwindSafe(|| testfn.call(context))) {
+                    Ok(Ok(())) => Err("test was marked `should-fail`, but it succeeded".into()),
+                    Ok(Err(_)) | Err(_) => Ok(()),
+                }
+            } else {
+                testfn
+                    .call(context)
+                    .map_err(|err| failure_message(&*err).into())
+            }
+        })
+        .with_ignored_flag(directives.ignore);
+        Ok(Some(trial))
     }
 
-    fn trial(&self, entry: TestEntry) -> Trial {
-        let testfn = self.test;
-        let name = entry.derive_test_name(&self.test_name);
-        Trial::test(name, move || {
-            testfn
-                .call(entry)
-                .map_err(|err| format!("{:?}", err).into())
-        })
+    /// Creates an ignored placeholder trial for `root`, which doesn't exist.
+    fn missing_root_trial(&self, root: &Root) -> Trial {
+        let name = format!("{}::{MISSING_ROOT}", root.test_name(&self.test_name));
+        let reason = format!(
+            "{} not found, and '{}' is optional",
+            self.display_root(root),
+            self.test_name
+        );
+        Trial::ignorable_test(name, move || Ok(Completion::ignored_with(reason)))
     }
 
-    fn exact(&self, filter: &str) -> Option<Trial> {
-        let entry = self.root.derive_exact(filter, &self.test_name)?;
-        entry.exists().then(|| self.trial(entry))
+    /// Returns true if `root` should be skipped because it doesn't exist.
+    fn is_missing(&self, root: &Root) -> bool {
+        self.optional && !root.source.exists()
+    }
+
+    /// Returns the trial named `filter` in this group, if there is one.
+    fn exact(&self, filter: &str) -> std::result::Result<Option<Trial>, String> {
+        if let Some(root) = self.roots.iter().find(|root| {
+            filter
+                .strip_suffix(MISSING_ROOT)
+                .and_then(|rest| rest.strip_suffix("::"))
+                == Some(root.test_name(&self.test_name).as_str())
+                && self.is_missing(root)
+        }) {
+            return Ok(Some(self.missing_root_trial(root)));
+        }
+
+        let selector = self.selector();
+        // The label in the name of a test picks out its root.
+        let found = self.roots.iter().find_map(|root| {
+            let entry = root.source.derive_exact(
+                filter,
+                &root.test_name(&self.test_name),
+                &self.names,
+                self.unit,
+                self.sections,
+                &|path| match selector.select(path.as_str()) {
+                    Selection::Selected => Some(selector.captures(path.as_str())),
+                    _ => None,
+                },
+            )?;
+            Some((root, entry))
+        });
+        match found {
+            Some((root, entry)) if entry.exists() => self.trial(root, entry),
+            _ => Ok(None),
+        }
     }
 
     /// Scans all files in a given directory, finds matching ones and generates a test descriptor
     /// for each of them.
-    fn expand(&self) -> Vec<Trial> {
-        let re = fancy_regex::Regex::new(&self.pattern)
-            .unwrap_or_else(|_| panic!("invalid regular expression: '{}'", self.pattern));
-
-        let tests: Vec<_> = self
-            .root
-            .walk_files()
-            .filter_map(|entry_res| {
-                let entry = entry_res.expect("error reading directory");
-                let path_str = entry.match_path().as_str();
-                if re.is_match(path_str).unwrap_or_else(|error| {
-                    panic!(
-                        "error matching pattern '{}' against path '{}' : {}",
-                        self.pattern, path_str, error
-                    )
-                }) {
-                    Some(self.trial(entry))
-                } else {
-                    None
+    ///
+    /// The files used by the tests are recorded in `claims`, which has an
+    /// element for each root.
+    ///
+    /// Returns errors for entries that couldn't be read, or if no test cases
+    /// were found, unless the group allows that.
+    fn expand(&self, claims: &mut [Claims]) -> std::result::Result<Vec<Trial>, Vec<String>> {
+        let selector = self.selector();
+
+        // Track why files were dropped, to report them if nothing matches.
+        let mut not_included = 0;
+        let mut excluded = 0;
+        let mut entries = Vec::new();
+        let mut missing_roots = Vec::new();
+        let mut errors = Vec::new();
+        for (root, claims) in self.roots.iter().zip(claims) {
+            if self.is_missing(root) {
+                missing_roots.push(self.missing_root_trial(root));
+                continue;
+            }
+            for entry_res in root.source.walk(self.unit, self.walk_filter(&root.source)) {
+                let entry = match entry_res {
+                    Ok(entry) => entry,
+                    Err(error) => {
+                        errors.push(format!(
+                            "error reading {} for test '{}': {error}",
+                            self.display_root(root),
+                            self.test_name
+                        ));
+                        continue;
+                    }
+                };
+                match selector.select(entry.match_path().as_str()) {
+                    Selection::Selected => {
+                        let captures = selector.captures(entry.match_path().as_str());
+                        let entry = entry.with_captures(captures);
+                        claims.claim_entry(&entry, self.unit);
+                        if let Some(template) = &self.expected {
+                            claims
+                                .claim_file(template.expand(entry.match_path(), entry.captures()));
+                        }
+                        entries.push((root, entry));
+                    }
+                    Selection::Sidecar => {}
+                    Selection::NotIncluded => not_included += 1,
+                    Selection::Excluded => excluded += 1,
                 }
-            })
-            .collect();
+            }
+        }
+
+        let mut tests = missing_roots;
+        for (root, entry) in entries {
+            let entries = if self.sections {
+                match entry.split_sections() {
+                    Ok(entries) => entries,
+                    Err(error) => {
+                        errors.push(error.to_string());
+                        continue;
+                    }
+                }
+            } else {
+                vec![entry]
+            };
+            for entry in entries {
+                match self.trial(root, entry) {
+                    Ok(trial) => tests.extend(trial),
+                    Err(error) => errors.push(error),
+                }
+            }
+        }
+        if !errors.is_empty() {
+            return Err(errors);
+        }
 
         // We want to avoid silent fails due to typos in regexp!
-        if tests.is_empty() {
-            panic!(
-                "no test cases found for test '{}' -- scanned {} with pattern '{}'",
+        if tests.is_empty() && !self.allow_empty {
+            let mut message = format!(
+                "no test cases found for test '{}' -- scanned {} with {}",
                 self.test_name,
-                self.root.display(),
-                self.pattern,
+                self.roots
+                    .iter()
+                    .map(|root| self.display_root(root))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                selector.pattern,
             );
+            if !self.exclude.is_empty() {
+                let exclude = self
+                    .exclude
+                    .iter()
+                    .map(|pattern| format!("'{pattern}'"))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                message.push_str(&format!(
+                    " and exclude {exclude} ({not_included} did not match the pattern, \
+                     {excluded} matched an exclude pattern)"
+                ));
+            }
+            message.push_str(" -- if the group can be empty, set `allow_empty = true`");
+            return Err(vec![message]);
         }
 
-        tests
+        Ok(tests)
+    }
+
+    fn selector(&self) -> Selector {
+        let pattern = self
+            .pattern
+            .clone()
+            .unwrap_or_else(|| Pattern::Regex(".*".to_owned()));
+        let re = pattern.compile();
+        let excludes = self
+            .exclude
+            .iter()
+            .map(|exclude| {
+                let exclude = Pattern::Regex(exclude.clone());
+                let re = exclude.compile();
+                (exclude, re)
+            })
+            .collect();
+        Selector {
+            pattern,
+            re,
+            excludes,
+            directives: self.directives,
+        }
     }
 }
 
+/// The compiled pattern and exclude patterns of a test group.
+struct Selector {
+    pattern: Pattern,
+    re: fancy_regex::Regex,
+    excludes: Vec<(Pattern, fancy_regex::Regex)>,
+    directives: bool,
+}
+
+/// Whether a path is a test case in a group, and if not, why.
+enum Selection {
+    Selected,
+    /// A sidecar file for directives, which is never a test case itself.
+    Sidecar,
+    NotIncluded,
+    Excluded,
+}
+
+impl Selector {
+    fn select(&self, path_str: &str) -> Selection {
+        if self.directives && path_str.ends_with(SIDECAR_SUFFIX) {
+            Selection::Sidecar
+        } else if !is_match(&self.re, &self.pattern, path_str) {
+            Selection::NotIncluded
+        } else if self
+            .excludes
+            .iter()
+            .any(|(pattern, re)| is_match(re, pattern, path_str))
+        {
+            Selection::Excluded
+        } else {
+            Selection::Selected
+        }
+    }
+
+    /// Returns the named groups captured by the pattern in a selected path.
+    fn captures(&self, path_str: &str) -> Vec<(String, String)> {
+        if self.re.capture_names().all(|name| name.is_none()) {
+            return Vec::new();
+        }
+        let captures = self
+            .re
+            .captures(path_str)
+            .unwrap_or_else(|error| {
+                panic!(
+                    "error matching {} against path '{}' : {}",
+                    self.pattern, path_str, error
+                )
+            })
+            .expect("selected path matches the pattern");
+        self.re
+            .capture_names()
+            .flatten()
+            .filter_map(|name| {
+                let value = captures.name(name)?;
+                Some((name.to_owned(), value.as_str().to_owned()))
+            })
+            .collect()
+    }
+}
+
+pub(crate) fn is_match(re: &fancy_regex::Regex, pattern: &Pattern, path_str: &str) -> bool {
+    re.is_match(path_str).unwrap_or_else(|error| {
+        panic!(
+            "error matching {} against path '{}' : {}",
+            pattern, path_str, error
+        )
+    })
+}
+
 // -- Polymorphic dispatch --
 
-#[derive(Clone, Copy)]
-#[doc(hidden)]
+/// A test function that can be run against fixtures.
+///
+/// This is created from any of the function shapes accepted by
+/// [`harness!`](crate::harness) using the [`test_fn!`](crate::test_fn) macro.
+#[derive(Clone)]
 pub enum TestFn {
     // Functions that work on paths.
+    #[doc(hidden)]
     Base(TestFnBase),
     /// Test functions that load a file as a string (UTF-8 text).
+    #[doc(hidden)]
     LoadString(TestFnLoadString),
     /// Test functions that load a file as binary data.
+    #[doc(hidden)]
     LoadBinary(TestFnLoadBinary),
+    /// Test functions that deserialize a file into a typed value.
+    #[doc(hidden)]
+    #[cfg(any(feature = "json", feature = "toml", feature = "yaml"))]
+    Deserialize(TestFnDeserialize),
+    /// Test functions that accept a subdirectory of an included directory.
+    #[doc(hidden)]
+    #[cfg(feature = "include-dir")]
+    IncludedDir(TestFnIncludedDir),
+    /// Test functions that accept a `TestContext`.
+    #[doc(hidden)]
+    Context(TestFnContext),
 }
 
 impl TestFn {
-    fn loads_data(&self) -> bool {
+    /// Returns true if the test function accepts the contents of a file.
+    pub(crate) fn loads_data(&self) -> bool {
         match self {
             TestFn::Base(_) => false,
             TestFn::LoadString(_) | TestFn::LoadBinary(_) => true,
+            #[cfg(any(feature = "json", feature = "toml", feature = "yaml"))]
+            TestFn::Deserialize(_) => true,
+            #[cfg(feature = "include-dir")]
+            TestFn::IncludedDir(_) => false,
+            TestFn::Context(f) => f.loads_data(),
         }
     }
 
-    fn call(&self, entry: TestEntry) -> Result<()> {
+    /// Returns whether the test function accepts the contents of an expected
+    /// file, and if so, whether it accepts a missing one.
+    pub(crate) fn expected_arg(&self) -> Option<ExpectedArg> {
+        match self {
+            TestFn::LoadString(f) => f.expected_arg(),
+            TestFn::LoadBinary(f) => f.expected_arg(),
+            _ => None,
+        }
+    }
+
+    /// Returns true if the test function accepts an included directory.
+    pub(crate) fn loads_included_dir(&self) -> bool {
+        match self {
+            #[cfg(feature = "include-dir")]
+            TestFn::IncludedDir(_) => true,
+            _ => false,
+        }
+    }
+
+    fn call(&self, context: TestContext) -> Result<()> {
         match self {
             TestFn::Base(f) => {
-                let path = entry
+                let path = context
                     .disk_path()
                     .expect("test entry being on disk was checked in the constructor");
                 f.call(path)
             }
-            TestFn::LoadString(f) => f.call(entry),
-            TestFn::LoadBinary(f) => f.call(entry),
+            TestFn::LoadString(f) => f.call(context.into_entry()),
+            TestFn::LoadBinary(f) => f.call(context.into_entry()),
+            #[cfg(any(feature = "json", feature = "toml", feature = "yaml"))]
+            TestFn::Deserialize(f) => f.call(context.into_entry()),
+            #[cfg(feature = "include-dir")]
+            TestFn::IncludedDir(f) => f.call(context.into_entry()),
+            TestFn::Context(f) => f.call(&context),
         }
     }
 }
 
-#[derive(Clone, Copy)]
+/// How a test function accepts the contents of an expected file.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum ExpectedArg {
+    /// As a `String` or `Vec<u8>`.
+    Required,
+    /// As an `Option<String>` or `Option<Vec<u8>>`.
+    Optional,
+}
+
+/// Formats a test failure as the error's `Display` output, followed by the
+/// chain of errors that caused it.
+fn failure_message(error: &dyn std::error::Error) -> String {
+    let mut message = error.to_string();
+    let mut source = error.source();
+    if source.is_some() {
+        message.push_str("\n\nCaused by:");
+    }
+    while let Some(error) = source {
+        message.push_str("\n    ");
+        message.push_str(&error.to_string());
+        source = error.source();
+    }
+    message
+}
+
+fn missing_expected(entry: &TestEntry) -> Box<dyn std::error::Error> {
+    format!("no expected file was found for '{}'", entry.test_path()).into()
+}
+
+// Test functions are stored as shared trait objects, so that they can be
+// closures that capture state.
+type PathFn = dyn Fn(&Path) -> Result<()> + Send + Sync;
+type Utf8PathFn = dyn Fn(&Utf8Path) -> Result<()> + Send + Sync;
+type PathStringFn = dyn Fn(&Path, String) -> Result<()> + Send + Sync;
+type Utf8PathStringFn = dyn Fn(&Utf8Path, String) -> Result<()> + Send + Sync;
+type PathStringSnapshotFn = dyn Fn(&Path, String, &Snapshot) -> Result<()> + Send + Sync;
+type Utf8PathStringSnapshotFn = dyn Fn(&Utf8Path, String, &Snapshot) -> Result<()> + Send + Sync;
+type PathBytesFn = dyn Fn(&Path, Vec<u8>) -> Result<()> + Send + Sync;
+type Utf8PathBytesFn = dyn Fn(&Utf8Path, Vec<u8>) -> Result<()> + Send + Sync;
+type PathBytesSnapshotFn = dyn Fn(&Path, Vec<u8>, &Snapshot) -> Result<()> + Send + Sync;
+type Utf8PathBytesSnapshotFn = dyn Fn(&Utf8Path, Vec<u8>, &Snapshot) -> Result<()> + Send + Sync;
+type PathStringExpectedFn = dyn Fn(&Path, String, String) -> Result<()> + Send + Sync;
+type Utf8PathStringExpectedFn = dyn Fn(&Utf8Path, String, String) -> Result<()> + Send + Sync;
+type PathStringMaybeExpectedFn = dyn Fn(&Path, String, Option<String>) -> Result<()> + Send + Sync;
+type Utf8PathStringMaybeExpectedFn =
+    dyn Fn(&Utf8Path, String, Option<String>) -> Result<()> + Send + Sync;
+type PathBytesExpectedFn = dyn Fn(&Path, Vec<u8>, Vec<u8>) -> Result<()> + Send + Sync;
+type Utf8PathBytesExpectedFn = dyn Fn(&Utf8Path, Vec<u8>, Vec<u8>) -> Result<()> + Send + Sync;
+type PathBytesMaybeExpectedFn = dyn Fn(&Path, Vec<u8>, Option<Vec<u8>>) -> Result<()> + Send + Sync;
+type Utf8PathBytesMaybeExpectedFn =
+    dyn Fn(&Utf8Path, Vec<u8>, Option<Vec<u8>>) -> Result<()> + Send + Sync;
+#[cfg(feature = "include-dir")]
+type PathIncludedDirFn = dyn Fn(&Path, &include_dir::Dir<'static>) -> Result<()> + Send + Sync;
+#[cfg(feature = "include-dir")]
+type Utf8PathIncludedDirFn =
+    dyn Fn(&Utf8Path, &include_dir::Dir<'static>) -> Result<()> + Send + Sync;
+
+#[derive(Clone)]
 #[doc(hidden)]
 pub enum TestFnBase {
-    Path(fn(&Path) -> Result<()>),
-    Utf8Path(fn(&Utf8Path) -> Result<()>),
+    Path(Arc<PathFn>),
+    Utf8Path(Arc<Utf8PathFn>),
 }
 
 impl TestFnBase {
@@ -231,38 +645,219 @@ impl TestFnBase {
     }
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone)]
 #[doc(hidden)]
 pub enum TestFnLoadString {
-    Path(fn(&Path, String) -> Result<()>),
-    Utf8Path(fn(&Utf8Path, String) -> Result<()>),
+    Path(Arc<PathStringFn>),
+    Utf8Path(Arc<Utf8PathStringFn>),
+    PathSnapshot(Arc<PathStringSnapshotFn>),
+    Utf8PathSnapshot(Arc<Utf8PathStringSnapshotFn>),
+    PathExpected(Arc<PathStringExpectedFn>),
+    Utf8PathExpected(Arc<Utf8PathStringExpectedFn>),
+    PathMaybeExpected(Arc<PathStringMaybeExpectedFn>),
+    Utf8PathMaybeExpected(Arc<Utf8PathStringMaybeExpectedFn>),
 }
 
 impl TestFnLoadString {
+    fn expected_arg(&self) -> Option<ExpectedArg> {
+        match self {
+            TestFnLoadString::PathExpected(_) | TestFnLoadString::Utf8PathExpected(_) => {
+                Some(ExpectedArg::Required)
+            }
+            TestFnLoadString::PathMaybeExpected(_) | TestFnLoadString::Utf8PathMaybeExpected(_) => {
+                Some(ExpectedArg::Optional)
+            }
+            _ => None,
+        }
+    }
+
     fn call(&self, entry: TestEntry) -> Result<()> {
         let contents = entry.read_as_string()?;
         match self {
             TestFnLoadString::Path(f) => f(entry.test_path().as_ref(), contents),
             TestFnLoadString::Utf8Path(f) => f(entry.test_path(), contents),
+            TestFnLoadString::PathSnapshot(f) => {
+                f(entry.test_path().as_ref(), contents, &Snapshot::new(&entry))
+            }
+            TestFnLoadString::Utf8PathSnapshot(f) => {
+                f(entry.test_path(), contents, &Snapshot::new(&entry))
+            }
+            TestFnLoadString::PathExpected(f) => {
+                let expected = entry.read_expected_as_string()?;
+                f(
+                    entry.test_path().as_ref(),
+                    contents,
+                    expected.ok_or_else(|| missing_expected(&entry))?,
+                )
+            }
+            TestFnLoadString::Utf8PathExpected(f) => {
+                let expected = entry.read_expected_as_string()?;
+                f(
+                    entry.test_path(),
+                    contents,
+                    expected.ok_or_else(|| missing_expected(&entry))?,
+                )
+            }
+            TestFnLoadString::PathMaybeExpected(f) => f(
+                entry.test_path().as_ref(),
+                contents,
+                entry.read_expected_as_string()?,
+            ),
+            TestFnLoadString::Utf8PathMaybeExpected(f) => f(
+                entry.test_path(),
+                contents,
+                entry.read_expected_as_string()?,
+            ),
         }
     }
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone)]
 #[doc(hidden)]
 pub enum TestFnLoadBinary {
-    Path(fn(&Path, Vec<u8>) -> Result<()>),
-    Utf8Path(fn(&Utf8Path, Vec<u8>) -> Result<()>),
+    Path(Arc<PathBytesFn>),
+    Utf8Path(Arc<Utf8PathBytesFn>),
+    PathSnapshot(Arc<PathBytesSnapshotFn>),
+    Utf8PathSnapshot(Arc<Utf8PathBytesSnapshotFn>),
+    PathExpected(Arc<PathBytesExpectedFn>),
+    Utf8PathExpected(Arc<Utf8PathBytesExpectedFn>),
+    PathMaybeExpected(Arc<PathBytesMaybeExpectedFn>),
+    Utf8PathMaybeExpected(Arc<Utf8PathBytesMaybeExpectedFn>),
 }
 
 impl TestFnLoadBinary {
+    fn expected_arg(&self) -> Option<ExpectedArg> {
+        match self {
+            TestFnLoadBinary::PathExpected(_) | TestFnLoadBinary::Utf8PathExpected(_) => {
+                Some(ExpectedArg::Required)
+            }
+            TestFnLoadBinary::PathMaybeExpected(_) | TestFnLoadBinary::Utf8PathMaybeExpected(_) => {
+                Some(ExpectedArg::Optional)
+            }
+            _ => None,
+        }
+    }
+
     fn call(&self, entry: TestEntry) -> Result<()> {
         let contents = entry.read()?;
         match self {
             TestFnLoadBinary::Path(f) => f(entry.test_path().as_ref(), contents),
             TestFnLoadBinary::Utf8Path(f) => f(entry.test_path(), contents),
+            TestFnLoadBinary::PathSnapshot(f) => {
+                f(entry.test_path().as_ref(), contents, &Snapshot::new(&entry))
+            }
+            TestFnLoadBinary::Utf8PathSnapshot(f) => {
+                f(entry.test_path(), contents, &Snapshot::new(&entry))
+            }
+            TestFnLoadBinary::PathExpected(f) => {
+                let expected = entry.read_expected()?;
+                f(
+                    entry.test_path().as_ref(),
+                    contents,
+                    expected.ok_or_else(|| missing_expected(&entry))?,
+                )
+            }
+            TestFnLoadBinary::Utf8PathExpected(f) => {
+                let expected = entry.read_expected()?;
+                f(
+                    entry.test_path(),
+                    contents,
+                    expected.ok_or_else(|| missing_expected(&entry))?,
+                )
+            }
+            TestFnLoadBinary::PathMaybeExpected(f) => {
+                f(entry.test_path().as_ref(), contents, entry.read_expected()?)
+            }
+            TestFnLoadBinary::Utf8PathMaybeExpected(f) => {
+                f(entry.test_path(), contents, entry.read_expected()?)
+            }
+        }
+    }
+}
+
+#[derive(Clone)]
+#[doc(hidden)]
+#[cfg(feature = "include-dir")]
+pub enum TestFnIncludedDir {
+    Path(Arc<PathIncludedDirFn>),
+    Utf8Path(Arc<Utf8PathIncludedDirFn>),
+}
+
+#[cfg(feature = "include-dir")]
+impl TestFnIncludedDir {
+    fn call(&self, entry: TestEntry) -> Result<()> {
+        let dir = entry
+            .included_dir()
+            .expect("test entry being an included directory was checked in the constructor");
+        match self {
+            TestFnIncludedDir::Path(f) => f(entry.test_path().as_ref(), dir),
+            TestFnIncludedDir::Utf8Path(f) => f(entry.test_path(), dir),
+        }
+    }
+}
+
+type ContextFn = dyn Fn(&TestContext) -> Result<()> + Send + Sync;
+type ContextStringFn = dyn Fn(&TestContext, String) -> Result<()> + Send + Sync;
+type ContextBytesFn = dyn Fn(&TestContext, Vec<u8>) -> Result<()> + Send + Sync;
+
+#[derive(Clone)]
+#[doc(hidden)]
+pub enum TestFnContext {
+    Base(Arc<ContextFn>),
+    String(Arc<ContextStringFn>),
+    Bytes(Arc<ContextBytesFn>),
+}
+
+impl TestFnContext {
+    fn loads_data(&self) -> bool {
+        !matches!(self, TestFnContext::Base(_))
+    }
+
+    fn call(&self, context: &TestContext) -> Result<()> {
+        match self {
+            TestFnContext::Base(f) => f(context),
+            TestFnContext::String(f) => f(context, context.entry().read_as_string()?),
+            TestFnContext::Bytes(f) => f(context, context.entry().read()?),
+        }
+    }
+}
+
+#[cfg(any(feature = "json", feature = "toml", feature = "yaml"))]
+type DeserializeFn = dyn Fn(&Utf8Path, &[u8], Format) -> Result<()> + Send + Sync;
+
+#[cfg(any(feature = "json", feature = "toml", feature = "yaml"))]
+#[derive(Clone)]
+#[doc(hidden)]
+pub struct TestFnDeserialize {
+    // The test function is generic over the deserialized type, so it's stored
+    // as a closure that deserializes the contents and then calls it.
+    f: Arc<DeserializeFn>,
+    pub(crate) format: Option<Format>,
+}
+
+#[cfg(any(feature = "json", feature = "toml", feature = "yaml"))]
+impl TestFnDeserialize {
+    fn new(f: impl Fn(&Utf8Path, &[u8], Format) -> Result<()> + Send + Sync + 'static) -> Self {
+        Self {
+            f: Arc::new(f),
+            format: None,
         }
     }
+
+    fn call(&self, entry: TestEntry) -> Result<()> {
+        let path = entry.test_path();
+        let format = self
+            .format
+            .or_else(|| path.extension().and_then(Format::from_extension))
+            .ok_or_else(|| {
+                format!(
+                    "unable to determine the format of '{path}' from its extension \
+                     (specify `format` to set it explicitly)"
+                )
+            })?;
+        let contents = entry.read()?;
+        (self.f)(path, &contents, format)
+    }
 }
 
 /// Implementations to allow `TestFn` to be created with functions of one of several types.
@@ -296,178 +891,969 @@ impl TestFnLoadBinary {
 ///
 /// # Notes
 ///
-/// We can't say `impl PathKind for fn(&Path) -> Result<()>` because Rust won't automatically coerce
-/// the concrete function type to the function pointer. (If we could, then we wouldn't need to rely
-/// on the macro-ness of `harness!` at all, and could just have a single trait implemented for all
-/// the different function pointer types.) To address this, we use a two-step process.
+/// Rust can't tell which of the `Kind` traits applies to a given function type without the macro
+/// naming it at each call site. To address this, we use a two-step process.
+///
+/// * Step 1: Implement `PathKind<R>` for all `F: Fn(&Path) -> R`, where `R` is `()` or
+///   `Result<(), E>`. This allows a `.kind()` method to exist which returns a new `PathTag<R>` type.
+/// * Step 2: Implement `PathTag::resolve`, which takes any `F: Fn(&Path) -> R + Send + Sync +
+///   'static`, along with a function that converts `R` into a `Result<()>`, and stores it as a
+///   trait object in the `TestFn` enum.
 ///
-/// * Step 1: Implement `PathKind` for all `F: Fn(&Path) -> Result<()>`. This allows a `.kind()`
-///   method to exist which returns a new `PathTag` type.
-/// * Step 2: Implement `PathTag::new` which only takes `fn(&Path) -> Result<()>`. *This* type can
-///   coerce to the function pointer, which can be stored in the `TestFn` enum.
+/// Since test functions are stored as trait objects rather than function pointers, they can be
+/// closures that capture state, such as a compiled grammar shared by all the tests in a group.
 ///
 /// This two-step process is similar to the one documented in [autoref-specialization].
 ///
+/// ## Return types
+///
+/// How `R` is converted depends on the error type: errors that convert into a `Box<dyn Error>` are
+/// kept as they are, and other errors are formatted using `Debug`. These overlap, so the
+/// conversion is picked in the same way as the kind: `harness!` calls `(&tag.output()).conclude()`,
+/// and method resolution finds the kinds implemented for `Output<R>` before the `Debug` kind
+/// implemented for `&Output<R>`.
+///
+/// ## Deserialized values
+///
+/// Test functions of the form `fn(&Path, T)` where `T: DeserializeOwned` are generic over `T`, so
+/// their kinds (e.g. `PathDeserializeKind<T>`) carry `T` as a type parameter. `String` and
+/// `Vec<u8>` also implement `DeserializeOwned`, so these kinds are implemented for `&F` rather than
+/// `F`, and `harness!` calls `(&test).kind()`. Method resolution finds the `String` and `Vec<u8>`
+/// kinds (implemented for `F`) before it gets to the deserialize kinds, which is exactly
+/// [autoref-specialization].
+///
 /// [autoref-specialization]: https://github.com/dtolnay/case-studies/blob/master/autoref-specialization/README.md
 #[doc(hidden)]
 pub mod test_kinds {
 
     use super::*;
+    #[cfg(any(feature = "json", feature = "toml", feature = "yaml"))]
+    use serde::de::DeserializeOwned;
+    use std::{error::Error, fmt, future::Future, marker::PhantomData};
 
     mod private {
         // We need to define a separate Sealed for each of the tags below, because Rust doesn't allow
-        // multiple kinds of F: Fn(T) -> Result<()> to implement the same trait.
-        pub trait PathSealed {}
-        pub trait Utf8PathSealed {}
-        pub trait PathStringSealed {}
-        pub trait Utf8PathStringSealed {}
-        pub trait PathBytesSealed {}
-        pub trait Utf8PathBytesSealed {}
+        // multiple kinds of F: Fn(T) -> R to implement the same trait.
+        pub trait TestOutputSealed {}
+        pub trait PathSealed<R> {}
+        pub trait Utf8PathSealed<R> {}
+        pub trait PathStringSealed<R> {}
+        pub trait Utf8PathStringSealed<R> {}
+        pub trait PathBytesSealed<R> {}
+        pub trait Utf8PathBytesSealed<R> {}
+        pub trait PathStringSnapshotSealed<R> {}
+        pub trait Utf8PathStringSnapshotSealed<R> {}
+        pub trait PathBytesSnapshotSealed<R> {}
+        pub trait Utf8PathBytesSnapshotSealed<R> {}
+        pub trait PathStringExpectedSealed<R> {}
+        pub trait Utf8PathStringExpectedSealed<R> {}
+        pub trait PathStringMaybeExpectedSealed<R> {}
+        pub trait Utf8PathStringMaybeExpectedSealed<R> {}
+        pub trait PathBytesExpectedSealed<R> {}
+        pub trait Utf8PathBytesExpectedSealed<R> {}
+        pub trait PathBytesMaybeExpectedSealed<R> {}
+        pub trait Utf8PathBytesMaybeExpectedSealed<R> {}
+        pub trait ContextSealed<R> {}
+        pub trait ContextStringSealed<R> {}
+        pub trait ContextBytesSealed<R> {}
+        pub trait AsyncPathSealed<R> {}
+        pub trait AsyncUtf8PathSealed<R> {}
+        pub trait AsyncPathStringSealed<R> {}
+        pub trait AsyncUtf8PathStringSealed<R> {}
+        pub trait AsyncPathBytesSealed<R> {}
+        pub trait AsyncUtf8PathBytesSealed<R> {}
+        #[cfg(any(feature = "json", feature = "toml", feature = "yaml"))]
+        pub trait PathDeserializeSealed<T, R> {}
+        #[cfg(any(feature = "json", feature = "toml", feature = "yaml"))]
+        pub trait Utf8PathDeserializeSealed<T, R> {}
+        #[cfg(feature = "include-dir")]
+        pub trait PathIncludedDirSealed<R> {}
+        #[cfg(feature = "include-dir")]
+        pub trait Utf8PathIncludedDirSealed<R> {}
     }
 
-    // -- Paths --
+    // -- Return types --
 
+    /// The return type of a test function: either `()` or `Result<(), E>` for any `E: Debug`.
     #[doc(hidden)]
-    pub struct PathTag;
+    pub trait TestOutput: private::TestOutputSealed {}
 
-    impl PathTag {
-        #[inline]
-        pub fn resolve(self, f: fn(&Path) -> Result<()>) -> TestFn {
-            TestFn::Base(TestFnBase::Path(f))
-        }
-    }
+    impl private::TestOutputSealed for () {}
+    impl TestOutput for () {}
+
+    impl<E: fmt::Debug> private::TestOutputSealed for std::result::Result<(), E> {}
+    impl<E: fmt::Debug> TestOutput for std::result::Result<(), E> {}
+
+    /// Converts the value returned by a test function into a [`Result`].
+    #[doc(hidden)]
+    pub type Conclude<R> = fn(R) -> Result<()>;
+
+    /// Returned by `output` on each tag, to pick a [`Conclude`] for its return type. See [*Return
+    /// types*](self#return-types) above.
+    #[doc(hidden)]
+    pub struct Output<R>(PhantomData<fn() -> R>);
 
     #[doc(hidden)]
-    pub trait PathKind: private::PathSealed {
+    pub trait UnitOutputKind {
         #[inline]
-        fn kind(&self) -> PathTag {
-            PathTag
+        fn conclude(&self) -> Conclude<()> {
+            |()| Ok(())
         }
     }
 
-    impl<F: Fn(&Path) -> Result<()>> private::PathSealed for F {}
-    impl<F: Fn(&Path) -> Result<()>> PathKind for F {}
-
-    // -- UTF-8 paths --
+    impl UnitOutputKind for Output<()> {}
 
     #[doc(hidden)]
-    pub struct Utf8PathTag;
-
-    impl Utf8PathTag {
+    pub trait ErrorOutputKind<E: Into<Box<dyn Error>>> {
         #[inline]
-        pub fn resolve(&self, f: fn(&Utf8Path) -> Result<()>) -> TestFn {
-            TestFn::Base(TestFnBase::Utf8Path(f))
+        fn conclude(&self) -> Conclude<std::result::Result<(), E>> {
+            |result| result.map_err(Into::into)
         }
     }
 
+    impl<E: Into<Box<dyn Error>>> ErrorOutputKind<E> for Output<std::result::Result<(), E>> {}
+
     #[doc(hidden)]
-    pub trait Utf8PathKind: private::Utf8PathSealed {
+    pub trait DebugOutputKind<E: fmt::Debug> {
         #[inline]
-        fn kind(&self) -> Utf8PathTag {
-            Utf8PathTag
+        fn conclude(&self) -> Conclude<std::result::Result<(), E>> {
+            |result| result.map_err(|error| format!("{error:?}").into())
         }
     }
 
-    impl<F: Fn(&Utf8Path) -> Result<()>> private::Utf8PathSealed for F {}
-    impl<F: Fn(&Utf8Path) -> Result<()>> Utf8PathKind for F {}
+    impl<E: fmt::Debug> DebugOutputKind<E> for &Output<std::result::Result<(), E>> {}
+
+    // -- Test function shapes --
+
+    /// Defines the tag and `Kind` trait for one shape of test function, as
+    /// described above.
+    ///
+    /// `F` is bounded by the signature in brackets. The `Kind` trait is
+    /// implemented for the type after `for`: `F`, or `&F` for the deserialize
+    /// kinds. The closure builds the `TestFn` from the function and its
+    /// `Conclude`.
+    macro_rules! test_kind {
+        (
+            $(#[$attr:meta])*
+            $tag:ident $(<$t:ident: $t_bound:path>)?, $kind:ident, $sealed:ident;
+            for $target:ty where F: [$($bound:tt)+];
+            |$f:ident, $conclude:ident| $resolve:expr
+        ) => {
+            $(#[$attr])*
+            #[doc(hidden)]
+            pub struct $tag<$($t,)? R>(PhantomData<fn() -> ($($t,)? R,)>);
+
+            $(#[$attr])*
+            impl<$($t: $t_bound + 'static,)? R: TestOutput + 'static> $tag<$($t,)? R> {
+                #[inline]
+                pub fn output(&self) -> Output<R> {
+                    Output(PhantomData)
+                }
+
+                #[inline]
+                pub fn resolve<F>(self, $f: F, $conclude: Conclude<R>) -> TestFn
+                where
+                    F: $($bound)+ + Send + Sync + 'static,
+                {
+                    $resolve
+                }
+            }
+
+            $(#[$attr])*
+            #[doc(hidden)]
+            pub trait $kind<$($t,)? R>: private::$sealed<$($t,)? R> {
+                #[inline]
+                fn kind(&self) -> $tag<$($t,)? R> {
+                    $tag(PhantomData)
+                }
+            }
+
+            $(#[$attr])*
+            impl<F: $($bound)+, $($t: $t_bound,)? R: TestOutput> private::$sealed<$($t,)? R>
+                for $target
+            {
+            }
+            $(#[$attr])*
+            impl<F: $($bound)+, $($t: $t_bound,)? R: TestOutput> $kind<$($t,)? R> for $target {}
+        };
+    }
+
+    // -- Paths --
+
+    test_kind! {
+        PathTag, PathKind, PathSealed;
+        for F where F: [Fn(&Path) -> R];
+        |f, conclude| TestFn::Base(TestFnBase::Path(Arc::new(move |path: &Path| {
+            conclude(f(path))
+        })))
+    }
+
+    // -- UTF-8 paths --
+
+    test_kind! {
+        Utf8PathTag, Utf8PathKind, Utf8PathSealed;
+        for F where F: [Fn(&Utf8Path) -> R];
+        |f, conclude| TestFn::Base(TestFnBase::Utf8Path(Arc::new(move |path: &Utf8Path| {
+            conclude(f(path))
+        })))
+    }
 
     // -- Path, load file as string --
 
+    test_kind! {
+        PathStringTag, PathStringKind, PathStringSealed;
+        for F where F: [Fn(&Path, String) -> R];
+        |f, conclude| TestFn::LoadString(TestFnLoadString::Path(Arc::new(
+            move |path: &Path, contents: String| conclude(f(path, contents)),
+        )))
+    }
+
+    // -- Utf8Path, load file as string --
+
+    test_kind! {
+        Utf8PathStringTag, Utf8PathStringKind, Utf8PathStringSealed;
+        for F where F: [Fn(&Utf8Path, String) -> R];
+        |f, conclude| TestFn::LoadString(TestFnLoadString::Utf8Path(Arc::new(
+            move |path: &Utf8Path, contents: String| conclude(f(path, contents)),
+        )))
+    }
+
+    // -- Path, load file as binary --
+
+    test_kind! {
+        PathBytesTag, PathBytesKind, PathBytesSealed;
+        for F where F: [Fn(&Path, Vec<u8>) -> R];
+        |f, conclude| TestFn::LoadBinary(TestFnLoadBinary::Path(Arc::new(
+            move |path: &Path, contents: Vec<u8>| conclude(f(path, contents)),
+        )))
+    }
+
+    // -- Utf8Path, load file as binary --
+
+    test_kind! {
+        Utf8PathBytesTag, Utf8PathBytesKind, Utf8PathBytesSealed;
+        for F where F: [Fn(&Utf8Path, Vec<u8>) -> R];
+        |f, conclude| TestFn::LoadBinary(TestFnLoadBinary::Utf8Path(Arc::new(
+            move |path: &Utf8Path, contents: Vec<u8>| conclude(f(path, contents)),
+        )))
+    }
+
+    // -- Path, load file as string, compare against snapshot --
+
+    test_kind! {
+        PathStringSnapshotTag, PathStringSnapshotKind, PathStringSnapshotSealed;
+        for F where F: [Fn(&Path, String, &Snapshot) -> R];
+        |f, conclude| TestFn::LoadString(TestFnLoadString::PathSnapshot(Arc::new(
+            move |path: &Path, contents: String, snapshot: &Snapshot| {
+                conclude(f(path, contents, snapshot))
+            },
+        )))
+    }
+
+    // -- Utf8Path, load file as string, compare against snapshot --
+
+    test_kind! {
+        Utf8PathStringSnapshotTag, Utf8PathStringSnapshotKind, Utf8PathStringSnapshotSealed;
+        for F where F: [Fn(&Utf8Path, String, &Snapshot) -> R];
+        |f, conclude| TestFn::LoadString(TestFnLoadString::Utf8PathSnapshot(Arc::new(
+            move |path: &Utf8Path, contents: String, snapshot: &Snapshot| {
+                conclude(f(path, contents, snapshot))
+            },
+        )))
+    }
+
+    // -- Path, load file as binary, compare against snapshot --
+
+    test_kind! {
+        PathBytesSnapshotTag, PathBytesSnapshotKind, PathBytesSnapshotSealed;
+        for F where F: [Fn(&Path, Vec<u8>, &Snapshot) -> R];
+        |f, conclude| TestFn::LoadBinary(TestFnLoadBinary::PathSnapshot(Arc::new(
+            move |path: &Path, contents: Vec<u8>, snapshot: &Snapshot| {
+                conclude(f(path, contents, snapshot))
+            },
+        )))
+    }
+
+    // -- Utf8Path, load file as binary, compare against snapshot --
+
+    test_kind! {
+        Utf8PathBytesSnapshotTag, Utf8PathBytesSnapshotKind, Utf8PathBytesSnapshotSealed;
+        for F where F: [Fn(&Utf8Path, Vec<u8>, &Snapshot) -> R];
+        |f, conclude| TestFn::LoadBinary(TestFnLoadBinary::Utf8PathSnapshot(Arc::new(
+            move |path: &Utf8Path, contents: Vec<u8>, snapshot: &Snapshot| {
+                conclude(f(path, contents, snapshot))
+            },
+        )))
+    }
+
+    // -- Path, load file as string, with expected file --
+
+    test_kind! {
+        PathStringExpectedTag, PathStringExpectedKind, PathStringExpectedSealed;
+        for F where F: [Fn(&Path, String, String) -> R];
+        |f, conclude| TestFn::LoadString(TestFnLoadString::PathExpected(Arc::new(
+            move |path: &Path, contents: String, expected: String| {
+                conclude(f(path, contents, expected))
+            },
+        )))
+    }
+
+    // -- Utf8Path, load file as string, with expected file --
+
+    test_kind! {
+        Utf8PathStringExpectedTag, Utf8PathStringExpectedKind, Utf8PathStringExpectedSealed;
+        for F where F: [Fn(&Utf8Path, String, String) -> R];
+        |f, conclude| TestFn::LoadString(TestFnLoadString::Utf8PathExpected(Arc::new(
+            move |path: &Utf8Path, contents: String, expected: String| {
+                conclude(f(path, contents, expected))
+            },
+        )))
+    }
+
+    // -- Path, load file as string, with optional expected file --
+
+    test_kind! {
+        PathStringMaybeExpectedTag, PathStringMaybeExpectedKind, PathStringMaybeExpectedSealed;
+        for F where F: [Fn(&Path, String, Option<String>) -> R];
+        |f, conclude| TestFn::LoadString(TestFnLoadString::PathMaybeExpected(Arc::new(
+            move |path: &Path, contents: String, expected: Option<String>| {
+                conclude(f(path, contents, expected))
+            },
+        )))
+    }
+
+    // -- Utf8Path, load file as string, with optional expected file --
+
+    test_kind! {
+        Utf8PathStringMaybeExpectedTag, Utf8PathStringMaybeExpectedKind,
+        Utf8PathStringMaybeExpectedSealed;
+        for F where F: [Fn(&Utf8Path, String, Option<String>) -> R];
+        |f, conclude| TestFn::LoadString(TestFnLoadString::Utf8PathMaybeExpected(Arc::new(
+            move |path: &Utf8Path, contents: String, expected: Option<String>| {
+                conclude(f(path, contents, expected))
+            },
+        )))
+    }
+
+    // -- Path, load file as binary, with expected file --
+
+    test_kind! {
+        PathBytesExpectedTag, PathBytesExpectedKind, PathBytesExpectedSealed;
+        for F where F: [Fn(&Path, Vec<u8>, Vec<u8>) -> R];
+        |f, conclude| TestFn::LoadBinary(TestFnLoadBinary::PathExpected(Arc::new(
+            move |path: &Path, contents: Vec<u8>, expected: Vec<u8>| {
+                conclude(f(path, contents, expected))
+            },
+        )))
+    }
+
+    // -- Utf8Path, load file as binary, with expected file --
+
+    test_kind! {
+        Utf8PathBytesExpectedTag, Utf8PathBytesExpectedKind, Utf8PathBytesExpectedSealed;
+        for F where F: [Fn(&Utf8Path, Vec<u8>, Vec<u8>) -> R];
+        |f, conclude| TestFn::LoadBinary(TestFnLoadBinary::Utf8PathExpected(Arc::new(
+            move |path: &Utf8Path, contents: Vec<u8>, expected: Vec<u8>| {
+                conclude(f(path, contents, expected))
+            },
+        )))
+    }
+
+    // -- Path, load file as binary, with optional expected file --
+
+    test_kind! {
+        PathBytesMaybeExpectedTag, PathBytesMaybeExpectedKind, PathBytesMaybeExpectedSealed;
+        for F where F: [Fn(&Path, Vec<u8>, Option<Vec<u8>>) -> R];
+        |f, conclude| TestFn::LoadBinary(TestFnLoadBinary::PathMaybeExpected(Arc::new(
+            move |path: &Path, contents: Vec<u8>, expected: Option<Vec<u8>>| {
+                conclude(f(path, contents, expected))
+            },
+        )))
+    }
+
+    // -- Utf8Path, load file as binary, with optional expected file --
+
+    test_kind! {
+        Utf8PathBytesMaybeExpectedTag, Utf8PathBytesMaybeExpectedKind,
+        Utf8PathBytesMaybeExpectedSealed;
+        for F where F: [Fn(&Utf8Path, Vec<u8>, Option<Vec<u8>>) -> R];
+        |f, conclude| TestFn::LoadBinary(TestFnLoadBinary::Utf8PathMaybeExpected(Arc::new(
+            move |path: &Utf8Path, contents: Vec<u8>, expected: Option<Vec<u8>>| {
+                conclude(f(path, contents, expected))
+            },
+        )))
+    }
+
+    // -- Context --
+
+    test_kind! {
+        ContextTag, ContextKind, ContextSealed;
+        for F where F: [Fn(&TestContext) -> R];
+        |f, conclude| TestFn::Context(TestFnContext::Base(Arc::new(
+            move |context: &TestContext| conclude(f(context)),
+        )))
+    }
+
+    // -- Context, load file as string --
+
+    test_kind! {
+        ContextStringTag, ContextStringKind, ContextStringSealed;
+        for F where F: [Fn(&TestContext, String) -> R];
+        |f, conclude| TestFn::Context(TestFnContext::String(Arc::new(
+            move |context: &TestContext, contents: String| conclude(f(context, contents)),
+        )))
+    }
+
+    // -- Context, load file as binary --
+
+    test_kind! {
+        ContextBytesTag, ContextBytesKind, ContextBytesSealed;
+        for F where F: [Fn(&TestContext, Vec<u8>) -> R];
+        |f, conclude| TestFn::Context(TestFnContext::Bytes(Arc::new(
+            move |context: &TestContext, contents: Vec<u8>| conclude(f(context, contents)),
+        )))
+    }
+
+    // -- Async functions --
+    //
+    // An async function's future borrows its arguments, so its return type
+    // depends on the lifetime of the path. That can't be named in an `Fn`
+    // bound directly, so these traits name it as an associated type instead.
+
     #[doc(hidden)]
-    pub struct PathStringTag;
+    pub trait AsyncPathFn<'a, P: ?Sized + 'a> {
+        type Output: TestOutput;
+        type Future: Future<Output = Self::Output> + 'a;
+
+        fn call_async(&self, path: &'a P) -> Self::Future;
+    }
+
+    impl<'a, P: ?Sized + 'a, F, Fut> AsyncPathFn<'a, P> for F
+    where
+        F: Fn(&'a P) -> Fut,
+        Fut: Future + 'a,
+        Fut::Output: TestOutput,
+    {
+        type Output = Fut::Output;
+        type Future = Fut;
 
-    impl PathStringTag {
         #[inline]
-        pub fn resolve(self, f: fn(&Path, String) -> Result<()>) -> TestFn {
-            TestFn::LoadString(TestFnLoadString::Path(f))
+        fn call_async(&self, path: &'a P) -> Fut {
+            self(path)
         }
     }
 
     #[doc(hidden)]
-    pub trait PathStringKind: private::PathStringSealed {
+    pub trait AsyncPathContentsFn<'a, P: ?Sized + 'a, C> {
+        type Output: TestOutput;
+        type Future: Future<Output = Self::Output> + 'a;
+
+        fn call_async(&self, path: &'a P, contents: C) -> Self::Future;
+    }
+
+    impl<'a, P: ?Sized + 'a, C, F, Fut> AsyncPathContentsFn<'a, P, C> for F
+    where
+        F: Fn(&'a P, C) -> Fut,
+        Fut: Future + 'a,
+        Fut::Output: TestOutput,
+    {
+        type Output = Fut::Output;
+        type Future = Fut;
+
         #[inline]
-        fn kind(&self) -> PathStringTag {
-            PathStringTag
+        fn call_async(&self, path: &'a P, contents: C) -> Fut {
+            self(path, contents)
         }
     }
 
-    impl<F: Fn(&Path, String) -> Result<()>> private::PathStringSealed for F {}
-    impl<F: Fn(&Path, String) -> Result<()>> PathStringKind for F {}
+    // -- Async, Path --
 
-    // -- Utf8Path, load file as string --
+    test_kind! {
+        AsyncPathTag, AsyncPathKind, AsyncPathSealed;
+        for F where F: [for<'a> AsyncPathFn<'a, Path, Output = R>];
+        |f, conclude| TestFn::Base(TestFnBase::Path(Arc::new(move |path: &Path| {
+            let future = f.call_async(path);
+            crate::executor::block_on(async move { conclude(future.await) })
+        })))
+    }
 
-    #[doc(hidden)]
-    pub struct Utf8PathStringTag;
+    // -- Async, Utf8Path --
 
-    impl Utf8PathStringTag {
-        #[inline]
-        pub fn resolve(self, f: fn(&Utf8Path, String) -> Result<()>) -> TestFn {
-            TestFn::LoadString(TestFnLoadString::Utf8Path(f))
-        }
+    test_kind! {
+        AsyncUtf8PathTag, AsyncUtf8PathKind, AsyncUtf8PathSealed;
+        for F where F: [for<'a> AsyncPathFn<'a, Utf8Path, Output = R>];
+        |f, conclude| TestFn::Base(TestFnBase::Utf8Path(Arc::new(move |path: &Utf8Path| {
+            let future = f.call_async(path);
+            crate::executor::block_on(async move { conclude(future.await) })
+        })))
     }
 
-    #[doc(hidden)]
-    pub trait Utf8PathStringKind: private::Utf8PathStringSealed {
-        #[inline]
-        fn kind(&self) -> Utf8PathStringTag {
-            Utf8PathStringTag
+    // -- Async, Path, load file as string --
+
+    test_kind! {
+        AsyncPathStringTag, AsyncPathStringKind, AsyncPathStringSealed;
+        for F where F: [for<'a> AsyncPathContentsFn<'a, Path, String, Output = R>];
+        |f, conclude| TestFn::LoadString(TestFnLoadString::Path(Arc::new(
+            move |path: &Path, contents: String| {
+                let future = f.call_async(path, contents);
+                crate::executor::block_on(async move { conclude(future.await) })
+            },
+        )))
+    }
+
+    // -- Async, Utf8Path, load file as string --
+
+    test_kind! {
+        AsyncUtf8PathStringTag, AsyncUtf8PathStringKind, AsyncUtf8PathStringSealed;
+        for F where F: [for<'a> AsyncPathContentsFn<'a, Utf8Path, String, Output = R>];
+        |f, conclude| TestFn::LoadString(TestFnLoadString::Utf8Path(Arc::new(
+            move |path: &Utf8Path, contents: String| {
+                let future = f.call_async(path, contents);
+                crate::executor::block_on(async move { conclude(future.await) })
+            },
+        )))
+    }
+
+    // -- Async, Path, load file as binary --
+
+    test_kind! {
+        AsyncPathBytesTag, AsyncPathBytesKind, AsyncPathBytesSealed;
+        for F where F: [for<'a> AsyncPathContentsFn<'a, Path, Vec<u8>, Output = R>];
+        |f, conclude| TestFn::LoadBinary(TestFnLoadBinary::Path(Arc::new(
+            move |path: &Path, contents: Vec<u8>| {
+                let future = f.call_async(path, contents);
+                crate::executor::block_on(async move { conclude(future.await) })
+            },
+        )))
+    }
+
+    // -- Async, Utf8Path, load file as binary --
+
+    test_kind! {
+        AsyncUtf8PathBytesTag, AsyncUtf8PathBytesKind, AsyncUtf8PathBytesSealed;
+        for F where F: [for<'a> AsyncPathContentsFn<'a, Utf8Path, Vec<u8>, Output = R>];
+        |f, conclude| TestFn::LoadBinary(TestFnLoadBinary::Utf8Path(Arc::new(
+            move |path: &Utf8Path, contents: Vec<u8>| {
+                let future = f.call_async(path, contents);
+                crate::executor::block_on(async move { conclude(future.await) })
+            },
+        )))
+    }
+
+    // -- Path, deserialize file --
+
+    test_kind! {
+        #[cfg(any(feature = "json", feature = "toml", feature = "yaml"))]
+        PathDeserializeTag<T: DeserializeOwned>, PathDeserializeKind, PathDeserializeSealed;
+        for &F where F: [Fn(&Path, T) -> R];
+        |f, conclude| TestFn::Deserialize(TestFnDeserialize::new(
+            move |path: &Utf8Path, contents: &[u8], format: Format| {
+                conclude(f(path.as_std_path(), format.deserialize(path, contents)?))
+            },
+        ))
+    }
+
+    // -- Utf8Path, deserialize file --
+
+    test_kind! {
+        #[cfg(any(feature = "json", feature = "toml", feature = "yaml"))]
+        Utf8PathDeserializeTag<T: DeserializeOwned>, Utf8PathDeserializeKind,
+        Utf8PathDeserializeSealed;
+        for &F where F: [Fn(&Utf8Path, T) -> R];
+        |f, conclude| TestFn::Deserialize(TestFnDeserialize::new(
+            move |path: &Utf8Path, contents: &[u8], format: Format| {
+                conclude(f(path, format.deserialize(path, contents)?))
+            },
+        ))
+    }
+
+    // -- Path, included directory --
+
+    test_kind! {
+        #[cfg(feature = "include-dir")]
+        PathIncludedDirTag, PathIncludedDirKind, PathIncludedDirSealed;
+        for F where F: [Fn(&Path, &include_dir::Dir<'static>) -> R];
+        |f, conclude| TestFn::IncludedDir(TestFnIncludedDir::Path(Arc::new(
+            move |path: &Path, dir: &include_dir::Dir<'static>| conclude(f(path, dir)),
+        )))
+    }
+
+    // -- Utf8Path, included directory --
+
+    test_kind! {
+        #[cfg(feature = "include-dir")]
+        Utf8PathIncludedDirTag, Utf8PathIncludedDirKind, Utf8PathIncludedDirSealed;
+        for F where F: [Fn(&Utf8Path, &include_dir::Dir<'static>) -> R];
+        |f, conclude| TestFn::IncludedDir(TestFnIncludedDir::Utf8Path(Arc::new(
+            move |path: &Utf8Path, dir: &include_dir::Dir<'static>| conclude(f(path, dir)),
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DataSource;
+
+    #[test]
+    fn exclude_everything() {
+        fn my_test(_: &Path) -> Result<()> {
+            Ok(())
         }
+
+        let group = TestGroup::new(
+            "my_test",
+            TestFn::Base(TestFnBase::Path(Arc::new(my_test))),
+            "tests/files",
+        )
+        .pattern(r"\.txt$")
+        .exclude(r"\.skip\.txt$")
+        .exclude("^(dir|b)");
+        let errors = group
+            .expand(&mut [Claims::default()])
+            .expect_err("no test cases found");
+        assert_eq!(
+            errors,
+            [
+                "no test cases found for test 'my_test' -- scanned directory: `tests/files` with \
+             pattern '\\.txt$' and exclude '\\.skip\\.txt$', '^(dir|b)' (1 did not match the \
+             pattern, 3 matched an exclude pattern) -- if the group can be empty, set \
+             `allow_empty = true`"
+            ]
+        );
+
+        let tests = group
+            .allow_empty(true)
+            .expand(&mut [Claims::default()])
+            .expect("empty groups are allowed");
+        assert!(tests.is_empty());
     }
 
-    impl<F: Fn(&Utf8Path, String) -> Result<()>> private::Utf8PathStringSealed for F {}
-    impl<F: Fn(&Utf8Path, String) -> Result<()>> Utf8PathStringKind for F {}
+    #[test]
+    fn root_env_no_tests() {
+        fn my_test(_: &Path) -> Result<()> {
+            Ok(())
+        }
 
-    // -- Path, load file as binary --
+        let errors = TestGroup::new(
+            "my_test",
+            TestFn::Base(TestFnBase::Path(Arc::new(my_test))),
+            "tests/files",
+        )
+        .root_env_with("MY_ROOT".to_owned(), Some("tests/paired".to_owned()))
+        .pattern(r"\.txt$")
+        .expand(&mut [Claims::default()])
+        .expect_err("no test cases found");
+        assert!(
+            matches!(&errors[..], [error] if error.starts_with(
+                "no test cases found for test 'my_test' -- scanned directory: `tests/paired` \
+                 (from `$MY_ROOT`) with pattern '\\.txt$'"
+            )),
+            "{errors:?}"
+        );
 
-    #[doc(hidden)]
-    pub struct PathBytesTag;
+        // An empty or missing value keeps the roots, and says how to replace
+        // them.
+        let errors = TestGroup::new(
+            "my_test",
+            TestFn::Base(TestFnBase::Path(Arc::new(my_test))),
+            "tests/paired",
+        )
+        .root_env_with("MY_ROOT".to_owned(), Some(String::new()))
+        .pattern(r"\.txt$")
+        .expand(&mut [Claims::default()])
+        .expect_err("no test cases found");
+        assert!(
+            matches!(&errors[..], [error] if error.starts_with(
+                "no test cases found for test 'my_test' -- scanned directory: `tests/paired` \
+                 (set `$MY_ROOT` to use another root) with pattern '\\.txt$'"
+            )),
+            "{errors:?}"
+        );
+    }
 
-    impl PathBytesTag {
-        #[inline]
-        pub fn resolve(self, f: fn(&Path, Vec<u8>) -> Result<()>) -> TestFn {
-            TestFn::LoadBinary(TestFnLoadBinary::Path(f))
+    #[cfg(feature = "archive-zip")]
+    #[test]
+    fn root_env_archive() {
+        fn my_test(_: &Path, _: String) -> Result<()> {
+            Ok(())
         }
+
+        // A value with an archive extension replaces the roots with that
+        // archive.
+        let errors = TestGroup::new(
+            "my_test",
+            TestFn::LoadString(TestFnLoadString::Path(Arc::new(my_test))),
+            "tests/files",
+        )
+        .root_env_with(
+            "MY_ROOT".to_owned(),
+            Some("tests/archives/files.zip".to_owned()),
+        )
+        .pattern(r"\.md$")
+        .expand(&mut [Claims::default()])
+        .expect_err("no test cases found");
+        assert!(
+            matches!(&errors[..], [error] if error.starts_with(
+                "no test cases found for test 'my_test' -- scanned archive: \
+                 `tests/archives/files.zip` (from `$MY_ROOT`) with pattern '\\.md$'"
+            )),
+            "{errors:?}"
+        );
     }
 
-    #[doc(hidden)]
-    pub trait PathBytesKind: private::PathBytesSealed {
-        #[inline]
-        fn kind(&self) -> PathBytesTag {
-            PathBytesTag
+    #[test]
+    fn unreadable_entries() {
+        fn my_test(_: &Path, _: String) -> Result<()> {
+            Ok(())
         }
+
+        let dir = camino_tempfile::Utf8TempDir::new().unwrap();
+        std::fs::write(dir.path().join("a.txt"), "// datatest: bogus\n").unwrap();
+        std::fs::write(dir.path().join("b.txt"), "# datatest: ignore, later\n").unwrap();
+        std::fs::write(dir.path().join("c.txt"), "// datatest: ignore\n").unwrap();
+
+        // Every entry that can't be read is reported, instead of the first
+        // one causing a panic.
+        let mut errors = TestGroup::new(
+            "my_test",
+            TestFn::LoadString(TestFnLoadString::Path(Arc::new(my_test))),
+            dir.path(),
+        )
+        .directives(true)
+        .expand(&mut [Claims::default()])
+        .expect_err("directives are invalid");
+        errors.sort();
+        assert_eq!(errors.len(), 2, "{errors:?}");
+        assert!(
+            errors[0].starts_with("error reading directives for '")
+                && errors[0].ends_with(
+                    "a.txt': unknown directive 'bogus' (expected `ignore` or `should-fail`)"
+                ),
+            "{errors:?}"
+        );
+        assert!(
+            errors[1].ends_with(
+                "b.txt': unknown directive 'later' (expected `ignore` or `should-fail`)"
+            ),
+            "{errors:?}"
+        );
+
+        let errors = TestGroup::new(
+            "my_test",
+            TestFn::LoadString(TestFnLoadString::Path(Arc::new(my_test))),
+            dir.path(),
+        )
+        .sections(true)
+        .pattern(r"^c\.txt$")
+        .expand(&mut [Claims::default()])
+        .expect_err("c.txt has no sections");
+        assert!(
+            matches!(&errors[..], [error] if error.ends_with(
+                "c.txt' into sections: line 1: expected a `=== name ===` section header"
+            )),
+            "{errors:?}"
+        );
     }
 
-    impl<F: Fn(&Path, Vec<u8>) -> Result<()>> private::PathBytesSealed for F {}
-    impl<F: Fn(&Path, Vec<u8>) -> Result<()>> PathBytesKind for F {}
+    #[test]
+    fn optional_missing_root() {
+        fn my_test(_: &Path) -> Result<()> {
+            Ok(())
+        }
 
-    // -- Utf8Path, load file as binary --
+        let group = TestGroup::with_roots(
+            "my_test",
+            TestFn::Base(TestFnBase::Path(Arc::new(my_test))),
+            [
+                ("files", DataSource::from("tests/files")),
+                ("missing", DataSource::from("tests/missing-corpus")),
+            ],
+        )
+        .pattern(r"^b\.txt$")
+        .optional(true);
+        let mut names: Vec<_> = group
+            .expand(&mut [Claims::default(), Claims::default()])
+            .unwrap()
+            .iter()
+            .map(|trial| trial.name().to_owned())
+            .collect();
+        names.sort();
+        assert_eq!(
+            names,
+            ["my_test::files::b.txt", "my_test::missing::(missing root)"]
+        );
 
-    #[doc(hidden)]
-    pub struct Utf8PathBytesTag;
+        let trial = group
+            .exact("my_test::missing::(missing root)")
+            .expect("no errors reading the group")
+            .expect("placeholder is found by name");
+        assert_eq!(trial.name(), "my_test::missing::(missing root)");
+        assert!(group
+            .exact("my_test::files::(missing root)")
+            .expect("no errors reading the group")
+            .is_none());
+    }
 
-    impl Utf8PathBytesTag {
-        #[inline]
-        pub fn resolve(self, f: fn(&Utf8Path, Vec<u8>) -> Result<()>) -> TestFn {
-            TestFn::LoadBinary(TestFnLoadBinary::Utf8Path(f))
+    #[test]
+    #[should_panic = "more than one test group is named 'my_test'"]
+    fn duplicate_group_names() {
+        fn my_test(_: &Path) -> Result<()> {
+            Ok(())
         }
+
+        let group = |root| {
+            TestGroup::new(
+                "my_test",
+                TestFn::Base(TestFnBase::Path(Arc::new(my_test))),
+                root,
+            )
+        };
+        crate::Harness::new()
+            .group(group("tests/files"))
+            .group(group("tests/paired"));
     }
 
-    #[doc(hidden)]
-    pub trait Utf8PathBytesKind: private::Utf8PathBytesSealed {
-        #[inline]
-        fn kind(&self) -> Utf8PathBytesTag {
-            Utf8PathBytesTag
+    #[test]
+    #[should_panic = "more than one root of 'my_test' is labelled 'files'"]
+    fn duplicate_root_labels() {
+        fn my_test(_: &Path) -> Result<()> {
+            Ok(())
+        }
+
+        TestGroup::with_roots(
+            "my_test",
+            TestFn::Base(TestFnBase::Path(Arc::new(my_test))),
+            [
+                ("files", DataSource::from("tests/files")),
+                ("files", DataSource::from("tests/paired")),
+            ],
+        );
+    }
+
+    #[test]
+    fn duplicate_test_names() {
+        fn my_test(_: &Path) -> Result<()> {
+            Ok(())
         }
+
+        let mut tests = TestGroup::new(
+            "my_test",
+            TestFn::Base(TestFnBase::Path(Arc::new(my_test))),
+            "tests/paired",
+        )
+        .case_name("{stem}")
+        .expand(&mut [Claims::default()])
+        .unwrap();
+        tests.sort_unstable_by(|a, b| a.name().cmp(b.name()));
+        let error = check_duplicate_names(&tests).expect_err("names are duplicated");
+        assert!(
+            error.starts_with(
+                "found several tests with each of these names: my_test::add, \
+                 my_test::nested/sub -- test names must be unique"
+            ),
+            "{error}"
+        );
     }
 
-    impl<F: Fn(&Utf8Path, Vec<u8>) -> Result<()>> private::Utf8PathBytesSealed for F {}
-    impl<F: Fn(&Utf8Path, Vec<u8>) -> Result<()>> Utf8PathBytesKind for F {}
+    #[test]
+    fn on_missing_expected() {
+        fn my_test(_: &Utf8Path, _: String, _: Option<String>) -> Result<()> {
+            Ok(())
+        }
+
+        for (on_missing, expected_names) in [
+            (
+                OnMissing::Fail,
+                &[
+                    "my_test::add.in",
+                    "my_test::missing.in",
+                    "my_test::nested/sub.in",
+                ][..],
+            ),
+            (
+                OnMissing::Skip,
+                &["my_test::add.in", "my_test::nested/sub.in"],
+            ),
+            (
+                OnMissing::PassNone,
+                &[
+                    "my_test::add.in",
+                    "my_test::missing.in",
+                    "my_test::nested/sub.in",
+                ],
+            ),
+        ] {
+            let group = TestGroup::new(
+                "my_test",
+                TestFn::LoadString(TestFnLoadString::Utf8PathMaybeExpected(Arc::new(my_test))),
+                "tests/paired",
+            )
+            .glob("**/*.in")
+            .expected("{stem}.out")
+            .on_missing(on_missing);
+            let mut names: Vec<_> = group
+                .expand(&mut [Claims::default()])
+                .unwrap()
+                .iter()
+                .map(|trial| trial.name().to_owned())
+                .collect();
+            names.sort();
+            assert_eq!(names, expected_names, "for {on_missing:?}");
+        }
+    }
+
+    #[test]
+    fn orphans_fail() {
+        fn my_test(_: &Path) -> Result<()> {
+            Ok(())
+        }
+
+        let groups = [
+            TestGroup::new(
+                "my_test",
+                TestFn::Base(TestFnBase::Path(Arc::new(my_test))),
+                "tests/orphans",
+            )
+            .glob("{snapshots,paired}/*"),
+            TestGroup::new(
+                "my_dir_test",
+                TestFn::Base(TestFnBase::Path(Arc::new(my_test))),
+                "tests/orphans/dirs",
+            )
+            .unit(crate::Unit::Dir),
+        ];
+        let claims: Vec<_> = groups
+            .iter()
+            .map(|group| {
+                let mut claims = vec![Claims::default()];
+                group.expand(&mut claims).unwrap();
+                claims
+            })
+            .collect();
+        let error = OrphanCheck::new(crate::OrphanAction::Fail)
+            .allow(r"^README\.md$")
+            .check(&groups, &claims)
+            .expect_err("stray.json isn't matched");
+        assert!(
+            error.starts_with(
+                "1 fixture file was not matched by any test group:\n  \
+                 in directory: `tests/orphans`:\n    stray.json\n"
+            ),
+            "{error}"
+        );
+
+        // With `warn`, the same files are only reported as a warning.
+        OrphanCheck::new(crate::OrphanAction::Warn)
+            .allow(r"^README\.md$")
+            .check(&groups, &claims)
+            .expect("warnings aren't errors");
+    }
 }
 
 #[cfg(all(test, feature = "include-dir"))]
 mod include_dir_tests {
     use super::*;
-    use std::borrow::Cow;
 
     #[test]
     #[should_panic = "test data for 'my_test' is stored in memory, \
@@ -477,11 +1863,43 @@ mod include_dir_tests {
             Ok(())
         }
 
-        Requirements::new(
-            TestFn::Base(TestFnBase::Path(my_test)),
-            "my_test".to_owned(),
-            DataSource::IncludeDir(Cow::Owned(include_dir::include_dir!("tests/files"))),
-            "xxx".to_owned(),
-        );
+        crate::Harness::new().group(TestGroup::new(
+            "my_test",
+            TestFn::Base(TestFnBase::Path(Arc::new(my_test))),
+            include_dir::include_dir!("tests/files"),
+        ));
+    }
+
+    #[test]
+    fn include_dir_hidden_files() {
+        fn my_test(_: &Utf8Path, _: String) -> Result<()> {
+            Ok(())
+        }
+
+        let names = |hidden: Option<bool>| {
+            let mut group = TestGroup::new(
+                "my_test",
+                TestFn::LoadString(TestFnLoadString::Utf8Path(Arc::new(my_test))),
+                include_dir::include_dir!("tests/ignore-files"),
+            )
+            .pattern(r"\.txt$");
+            if let Some(hidden) = hidden {
+                group = group.hidden(hidden);
+            }
+            let mut names: Vec<_> = group
+                .expand(&mut [Claims::default()])
+                .unwrap()
+                .iter()
+                .map(|trial| trial.name().to_owned())
+                .collect();
+            names.sort();
+            names
+        };
+
+        // Unlike directories on disk, included directories have hidden files
+        // by default.
+        assert!(names(None).contains(&"my_test::.hidden.txt".to_owned()));
+        assert_eq!(names(Some(true)), names(None));
+        assert!(!names(Some(false)).contains(&"my_test::.hidden.txt".to_owned()));
     }
 }
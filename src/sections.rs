@@ -0,0 +1,156 @@
+// Copyright (c) The datatest-stable Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Splitting fixture files into named sections.
+
+/// The delimiter on either side of a section name in a header line.
+const DELIMITER: &[u8] = b"===";
+
+/// A named test case within a fixture file.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct Section {
+    pub(crate) name: String,
+    pub(crate) contents: Vec<u8>,
+}
+
+/// Splits `contents` into sections, each starting with a `=== name ===`
+/// header line.
+///
+/// The contents of each section are the lines between its header and the
+/// next one, with trailing blank lines removed. Blank lines before the first
+/// header are ignored, but anything else is an error, as are empty and
+/// duplicate section names.
+pub(crate) fn split_sections(contents: &[u8]) -> Result<Vec<Section>, String> {
+    let mut sections: Vec<Section> = Vec::new();
+
+    for (index, line) in contents.split_inclusive(|&b| b == b'\n').enumerate() {
+        if let Some(name) = parse_header(line) {
+            let name = std::str::from_utf8(name)
+                .map_err(|_| format!("line {}: section name is not valid UTF-8", index + 1))?
+                .trim();
+            if name.is_empty() {
+                return Err(format!("line {}: section name is empty", index + 1));
+            }
+            if sections.iter().any(|section| section.name == name) {
+                return Err(format!(
+                    "line {}: duplicate section name '{name}'",
+                    index + 1
+                ));
+            }
+            sections.push(Section {
+                name: name.to_owned(),
+                contents: Vec::new(),
+            });
+        } else if let Some(section) = sections.last_mut() {
+            section.contents.extend_from_slice(line);
+        } else if !is_blank(line) {
+            return Err(format!(
+                "line {}: expected a `=== name ===` section header",
+                index + 1
+            ));
+        }
+    }
+
+    if sections.is_empty() {
+        return Err("no `=== name ===` section headers found".to_owned());
+    }
+
+    for section in &mut sections {
+        trim_trailing_blank_lines(&mut section.contents);
+    }
+    Ok(sections)
+}
+
+/// Returns the name in a `=== name ===` header line, if this is one.
+fn parse_header(line: &[u8]) -> Option<&[u8]> {
+    trim_whitespace(line)
+        .strip_prefix(DELIMITER)?
+        .strip_suffix(DELIMITER)
+}
+
+fn trim_whitespace(mut bytes: &[u8]) -> &[u8] {
+    while let [first, rest @ ..] = bytes {
+        if !first.is_ascii_whitespace() {
+            break;
+        }
+        bytes = rest;
+    }
+    while let [rest @ .., last] = bytes {
+        if !last.is_ascii_whitespace() {
+            break;
+        }
+        bytes = rest;
+    }
+    bytes
+}
+
+fn is_blank(line: &[u8]) -> bool {
+    line.iter().all(u8::is_ascii_whitespace)
+}
+
+fn trim_trailing_blank_lines(contents: &mut Vec<u8>) {
+    loop {
+        // Find the start of the last line, ignoring the final newline.
+        let without_newline = contents.strip_suffix(b"\n").unwrap_or(contents);
+        let start = without_newline
+            .iter()
+            .rposition(|&b| b == b'\n')
+            .map_or(0, |i| i + 1);
+        if start == contents.len() || !is_blank(&contents[start..]) {
+            return;
+        }
+        contents.truncate(start);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn section(name: &str, contents: &str) -> Section {
+        Section {
+            name: name.to_owned(),
+            contents: contents.as_bytes().to_vec(),
+        }
+    }
+
+    #[test]
+    fn splits_sections() {
+        let contents =
+            b"\n=== first ===\nfoo\nbar\n\n  ===second===  \r\nbaz\n===  third case ===\n";
+        assert_eq!(
+            split_sections(contents).unwrap(),
+            [
+                section("first", "foo\nbar\n"),
+                section("second", "baz\n"),
+                section("third case", ""),
+            ]
+        );
+    }
+
+    #[test]
+    fn keeps_inner_blank_lines() {
+        assert_eq!(
+            split_sections(b"=== a ===\n\nfoo\n\nbar").unwrap(),
+            [section("a", "\nfoo\n\nbar")]
+        );
+    }
+
+    #[test]
+    fn errors() {
+        for (contents, message) in [
+            (&b""[..], "no `=== name ===` section headers found"),
+            (
+                b"foo\n=== a ===\n",
+                "line 1: expected a `=== name ===` section header",
+            ),
+            (b"=== a ===\n=== ===\n", "line 2: section name is empty"),
+            (
+                b"=== a ===\n=== a ===\n",
+                "line 2: duplicate section name 'a'",
+            ),
+        ] {
+            assert_eq!(split_sections(contents).unwrap_err(), message);
+        }
+    }
+}
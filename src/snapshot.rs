@@ -0,0 +1,280 @@
+// Copyright (c) The datatest-stable Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::{data_source::TestEntry, Result};
+use camino::{Utf8Path, Utf8PathBuf};
+use std::fmt;
+
+/// The environment variable that, when set, causes expected files to be
+/// overwritten with the actual output.
+const BLESS_ENV: &str = "DATATEST_BLESS";
+
+/// The suffix appended to a fixture's path to obtain its expected file.
+pub(crate) const EXPECTED_SUFFIX: &str = ".expected";
+
+/// Compares the output of a test against an expected ("golden") file stored
+/// next to the fixture.
+///
+/// For a fixture at `path/to/input.foo`, the expected file is
+/// `path/to/input.foo.expected`. For a fixture split into sections, the
+/// expected file for the section `name` is `path/to/input.foo.name.expected`.
+///
+/// If the `DATATEST_BLESS` environment variable is set to a non-empty value
+/// other than `0`, the expected file is created or overwritten with the actual
+/// output instead of being compared against it. Blessing is only supported for
+/// fixtures on disk: for directories embedded via `include_dir` and for
+/// archives, comparisons work as usual but blessing results in an error.
+///
+/// A `Snapshot` is passed into test functions that accept it as their last
+/// argument. See the [crate documentation](crate#golden-file-snapshots) for
+/// more.
+#[derive(Debug)]
+pub struct Snapshot {
+    expected: ExpectedSource,
+    bless: bool,
+}
+
+impl Snapshot {
+    pub(crate) fn new(entry: &TestEntry) -> Self {
+        let bless = std::env::var(BLESS_ENV).is_ok_and(|v| !v.is_empty() && v != "0");
+        Self::with_bless(entry, bless)
+    }
+
+    fn with_bless(entry: &TestEntry, bless: bool) -> Self {
+        let expected = match entry.disk_path() {
+            Some(path) => ExpectedSource::Path(append_suffix(path, entry.section_name())),
+            None => ExpectedSource::InMemory {
+                rel_path: append_suffix(entry.match_path(), entry.section_name()),
+                entry: entry.clone(),
+            },
+        };
+        Self { expected, bless }
+    }
+
+    /// Returns the path to the expected file.
+    ///
+    /// For directories on disk, this is the fixture path with `.expected`
+    /// appended. For `include_dir` sources and archives, this is relative to
+    /// the root of the included directory or archive.
+    pub fn expected_path(&self) -> &Utf8Path {
+        match &self.expected {
+            ExpectedSource::Path(path) => path,
+            ExpectedSource::InMemory { rel_path, .. } => rel_path,
+        }
+    }
+
+    /// Returns true if expected files are being overwritten rather than
+    /// compared against.
+    pub fn is_blessing(&self) -> bool {
+        self.bless
+    }
+
+    /// Compares `actual` against the contents of the expected file, returning
+    /// an error with a unified diff if they differ.
+    pub fn check(&self, actual: &str) -> Result<()> {
+        self.check_bytes(actual.as_bytes())
+    }
+
+    /// Compares `actual` against the contents of the expected file as raw
+    /// bytes.
+    ///
+    /// If both the expected and actual contents are valid UTF-8, mismatches
+    /// are reported as a unified diff.
+    pub fn check_bytes(&self, actual: &[u8]) -> Result<()> {
+        if self.bless {
+            return self.bless(actual);
+        }
+
+        let expected = self.read_expected()?;
+        if expected == actual {
+            return Ok(());
+        }
+
+        let diff = match (std::str::from_utf8(&expected), std::str::from_utf8(actual)) {
+            (Ok(expected), Ok(actual)) => similar::TextDiff::from_lines(expected, actual)
+                .unified_diff()
+                .header("expected", "actual")
+                .to_string(),
+            _ => format!(
+                "binary contents differ ({} bytes expected, {} bytes actual)\n",
+                expected.len(),
+                actual.len()
+            ),
+        };
+        Err(SnapshotError(format!(
+            "output does not match expected file '{}' \
+             (set {BLESS_ENV}=1 to update it):\n\n{diff}",
+            self.expected_path(),
+        ))
+        .into())
+    }
+
+    fn read_expected(&self) -> Result<Vec<u8>> {
+        match &self.expected {
+            ExpectedSource::Path(path) => std::fs::read(path).map_err(|err| {
+                if err.kind() == std::io::ErrorKind::NotFound {
+                    SnapshotError(format!(
+                        "expected file '{path}' not found (set {BLESS_ENV}=1 to create it)"
+                    ))
+                    .into()
+                } else {
+                    format!("error reading expected file '{path}': {err}").into()
+                }
+            }),
+            ExpectedSource::InMemory { rel_path, entry } => match entry.in_memory_file(rel_path)? {
+                Some(contents) => Ok(contents),
+                None => Err(format!(
+                    "expected file '{rel_path}' not found in {}",
+                    entry.in_memory_source()
+                )
+                .into()),
+            },
+        }
+    }
+
+    fn bless(&self, actual: &[u8]) -> Result<()> {
+        match &self.expected {
+            ExpectedSource::Path(path) => {
+                // Avoid touching files that are already up-to-date.
+                if std::fs::read(path).is_ok_and(|expected| expected == actual) {
+                    return Ok(());
+                }
+                if let Some(parent) = path.parent() {
+                    std::fs::create_dir_all(parent)
+                        .map_err(|err| format!("error creating directory '{parent}': {err}"))?;
+                }
+                std::fs::write(path, actual)
+                    .map_err(|err| format!("error writing expected file '{path}': {err}").into())
+            }
+            ExpectedSource::InMemory { rel_path, entry } => Err(format!(
+                "cannot bless '{rel_path}': it is part of the {}, which is read-only \
+                 (unset {BLESS_ENV} to compare against it instead)",
+                entry.in_memory_source()
+            )
+            .into()),
+        }
+    }
+}
+
+#[derive(Debug)]
+enum ExpectedSource {
+    Path(Utf8PathBuf),
+    /// An expected file in an included directory or an archive, which is read
+    /// through the fixture's entry.
+    InMemory {
+        rel_path: Utf8PathBuf,
+        entry: TestEntry,
+    },
+}
+
+fn append_suffix(path: &Utf8Path, section_name: Option<&str>) -> Utf8PathBuf {
+    match section_name {
+        Some(section_name) => format!("{path}.{section_name}{EXPECTED_SUFFIX}").into(),
+        None => format!("{path}{EXPECTED_SUFFIX}").into(),
+    }
+}
+
+/// An error whose message contains a multi-line diff.
+///
+/// Test failures are rendered using `Display`, but a test that unwraps the
+/// result of [`Snapshot::check`] prints it using `Debug`, which would escape
+/// the newlines in a plain `String` error. This type forwards `Debug` to
+/// `Display` so diffs stay readable either way.
+struct SnapshotError(String);
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl fmt::Debug for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for SnapshotError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use camino_tempfile::Utf8TempDir;
+
+    fn entry_in(dir: &Utf8TempDir, name: &str) -> TestEntry {
+        TestEntry::from_full_path(dir.path(), dir.path().join(name))
+    }
+
+    #[test]
+    fn matching_contents() {
+        let dir = Utf8TempDir::new().unwrap();
+        std::fs::write(dir.path().join("a.txt.expected"), "hello\n").unwrap();
+
+        let snapshot = Snapshot::with_bless(&entry_in(&dir, "a.txt"), false);
+        assert_eq!(snapshot.expected_path(), dir.path().join("a.txt.expected"));
+        snapshot.check("hello\n").expect("contents match");
+    }
+
+    #[test]
+    fn mismatch_shows_diff() {
+        let dir = Utf8TempDir::new().unwrap();
+        std::fs::write(dir.path().join("a.txt.expected"), "one\ntwo\n").unwrap();
+
+        let snapshot = Snapshot::with_bless(&entry_in(&dir, "a.txt"), false);
+        let message = format!("{:?}", snapshot.check("one\nthree\n").unwrap_err());
+        assert!(
+            message.contains("-two\n+three\n"),
+            "diff in message: {message}"
+        );
+    }
+
+    #[test]
+    fn missing_expected_file() {
+        let dir = Utf8TempDir::new().unwrap();
+
+        let snapshot = Snapshot::with_bless(&entry_in(&dir, "a.txt"), false);
+        let message = format!("{:?}", snapshot.check("hello\n").unwrap_err());
+        assert!(message.contains("not found"), "message: {message}");
+    }
+
+    #[test]
+    fn bless_creates_and_overwrites() {
+        let dir = Utf8TempDir::new().unwrap();
+        let expected = dir.path().join("sub/a.txt.expected");
+
+        let snapshot = Snapshot::with_bless(&entry_in(&dir, "sub/a.txt"), true);
+        snapshot.check("first\n").expect("blessing succeeded");
+        assert_eq!(std::fs::read_to_string(&expected).unwrap(), "first\n");
+
+        snapshot.check("second\n").expect("blessing succeeded");
+        assert_eq!(std::fs::read_to_string(&expected).unwrap(), "second\n");
+    }
+
+    #[cfg(feature = "include-dir")]
+    #[test]
+    fn include_dir_refuses_bless() {
+        use crate::{DataSource, Unit};
+        use std::borrow::Cow;
+
+        let source = DataSource::IncludeDir(Cow::Owned(include_dir::include_dir!(
+            "$CARGO_MANIFEST_DIR/tests/files"
+        )));
+        let entry = source
+            .derive_exact(
+                "t::b.txt",
+                "t",
+                &crate::naming::CaseNames::default(),
+                Unit::File,
+                false,
+                &|_| Some(Vec::new()),
+            )
+            .expect("b.txt exists");
+
+        let snapshot = Snapshot::with_bless(&entry, true);
+        let message = format!("{:?}", snapshot.check("b").unwrap_err());
+        assert!(
+            message.contains("cannot bless 'b.txt.expected'"),
+            "message: {message}"
+        );
+    }
+}
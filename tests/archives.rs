@@ -0,0 +1,44 @@
+// Copyright (c) The datatest-stable Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use datatest_stable::{Archive, Result, Utf8Path};
+
+fn test_member(path: &Utf8Path, contents: String) -> Result<()> {
+    // Paths are relative to the root of the archive.
+    assert!(path.is_relative(), "{path} is relative");
+    assert_eq!(
+        contents.trim(),
+        path.file_stem().unwrap(),
+        "contents of {path}"
+    );
+    Ok(())
+}
+
+fn test_paired(path: &Utf8Path, input: String, expected: String) -> Result<()> {
+    assert_eq!(path, "nested/c.in");
+    assert_eq!(input.to_uppercase(), expected);
+    Ok(())
+}
+
+datatest_stable::harness! {
+    {
+        test = test_member,
+        root = Archive::new("tests/archives/files.tar.gz"),
+        pattern = r"\.txt$",
+        gitignore = true,
+        name = "tar",
+    },
+    {
+        test = test_member,
+        root = Archive::new("tests/archives/files.zip"),
+        pattern = r"\.txt$",
+        gitignore = true,
+        name = "zip",
+    },
+    {
+        test = test_paired,
+        root = Archive::new("tests/archives/files.zip"),
+        pattern = r"\.in$",
+        expected = "{stem}.out",
+    },
+}
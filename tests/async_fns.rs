@@ -0,0 +1,56 @@
+// Copyright (c) The datatest-stable Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use datatest_stable::{Result, Utf8Path};
+use std::{
+    future::Future,
+    path::Path,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+/// A future that's pending the first time it's polled, so that the executor
+/// has to wait for a wakeup.
+struct YieldNow(bool);
+
+impl Future for YieldNow {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if self.0 {
+            Poll::Ready(())
+        } else {
+            self.0 = true;
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        }
+    }
+}
+
+async fn test_path(path: &Path) -> Result<()> {
+    YieldNow(false).await;
+    assert!(path.exists(), "{} exists", path.display());
+    Ok(())
+}
+
+async fn test_string(path: &Utf8Path, contents: String) -> Result<()> {
+    YieldNow(false).await;
+    // The future can borrow the path across await points.
+    let expected = std::fs::read_to_string(path)?;
+    YieldNow(false).await;
+    assert_eq!(contents, expected, "contents of {path}");
+    Ok(())
+}
+
+async fn test_bytes(path: &Utf8Path, contents: Vec<u8>) -> Result<()> {
+    YieldNow(false).await;
+    assert!(!contents.is_empty(), "{path} isn't empty");
+    Ok(())
+}
+
+datatest_stable::harness! {
+    executor = datatest_stable::BlockingExecutor,
+    { test = test_path, root = "tests/files", pattern = r"\.txt$" },
+    { test = test_string, root = "tests/files", pattern = r"\.txt$" },
+    { test = test_bytes, root = "tests/files", pattern = r"\.txt$" },
+}
@@ -0,0 +1,52 @@
+// Copyright (c) The datatest-stable Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use datatest_stable::{Result, Utf8Path};
+use std::{net::SocketAddr, sync::OnceLock};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+};
+
+/// Returns the address of an echo server, starting it on the first call.
+///
+/// The server runs on the executor's runtime, so it only keeps working across
+/// tests if the runtime is shared by all of them.
+fn echo_server() -> SocketAddr {
+    static ADDR: OnceLock<SocketAddr> = OnceLock::new();
+    *ADDR.get_or_init(|| {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bound echo server");
+        listener
+            .set_nonblocking(true)
+            .expect("set echo server to non-blocking");
+        let addr = listener.local_addr().expect("echo server has an address");
+        let listener = TcpListener::from_std(listener).expect("registered echo server");
+        tokio::spawn(async move {
+            loop {
+                let (mut socket, _) = listener.accept().await.expect("accepted connection");
+                tokio::spawn(async move {
+                    let (mut reader, mut writer) = socket.split();
+                    tokio::io::copy(&mut reader, &mut writer)
+                        .await
+                        .expect("echoed data");
+                });
+            }
+        });
+        addr
+    })
+}
+
+async fn test_echo(path: &Utf8Path, contents: String) -> Result<()> {
+    let mut stream = TcpStream::connect(echo_server()).await?;
+    stream.write_all(contents.as_bytes()).await?;
+    stream.shutdown().await?;
+
+    let mut echoed = String::new();
+    stream.read_to_string(&mut echoed).await?;
+    assert_eq!(echoed, contents, "echoed contents of {path}");
+    Ok(())
+}
+
+datatest_stable::harness! {
+    { test = test_echo, root = "tests/files", pattern = r"\.txt$" },
+}
@@ -0,0 +1,63 @@
+// Copyright (c) The datatest-stable Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use datatest_stable::{Result, Utf8Path};
+
+#[datatest_stable::files(root = "tests/files", pattern = r"\.txt$", exclude = r"\.skip\.txt$")]
+fn test_txt(path: &Utf8Path, contents: String) -> Result<()> {
+    assert!(path.as_str().ends_with(".txt"), "{path} is a text file");
+    assert!(!contents.is_empty(), "{path} isn't empty");
+    Ok(())
+}
+
+mod nested {
+    use datatest_stable::{Result, Utf8Path};
+
+    // Arguments can be given in any order.
+    #[datatest_stable::files(glob = "*.json", root = "tests/files")]
+    fn test_json(path: &Utf8Path) -> Result<()> {
+        assert_eq!(path.file_name(), Some("other.json"));
+        Ok(())
+    }
+
+    #[datatest_stable::files(
+        root = [
+            local = "tests/multiple-roots/local",
+            spec = "tests/multiple-roots/spec-tests",
+        ],
+        pattern = r"^a\.txt$",
+    )]
+    fn test_roots(path: &Utf8Path) -> Result<()> {
+        assert!(path.ends_with("a.txt"), "{path} is a.txt");
+        Ok(())
+    }
+
+    // `crate` points the generated code at `datatest_stable` when it's only
+    // available under another path, such as a re-export.
+    #[datatest_stable::files(crate = crate::reexport::dts, root = "tests/files", pattern = r"^b\.txt$")]
+    fn test_crate_path(path: &Utf8Path) -> Result<()> {
+        assert_eq!(path.file_name(), Some("b.txt"));
+        Ok(())
+    }
+
+    #[datatest_stable::files(root = "tests/dir-cases", unit = dir, name = "dirs")]
+    fn test_dir(path: &Utf8Path) -> Result<()> {
+        assert!(path.join("input.txt").exists(), "{path} has an input");
+        Ok(())
+    }
+}
+
+mod reexport {
+    pub(crate) use datatest_stable as dts;
+}
+
+fn test_harness(path: &Utf8Path) -> Result<()> {
+    assert!(path.exists());
+    Ok(())
+}
+
+// Groups passed to `harness!` are run along with those declared with the
+// attribute.
+datatest_stable::harness! {
+    { test = test_harness, root = "tests/files", pattern = r"^b\.txt$" },
+}
@@ -0,0 +1,60 @@
+// Copyright (c) The datatest-stable Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use datatest_stable::{test_fn, Harness, Result, TestGroup, Utf8Path};
+use std::process::ExitCode;
+
+const CORPUS: &str = "tests/builder";
+
+fn main() -> ExitCode {
+    let mut harness = Harness::new();
+
+    // Create one group per subdirectory of the corpus, where every file in the
+    // subdirectory contains the name of the subdirectory.
+    let mut dirs: Vec<_> = Utf8Path::new(CORPUS)
+        .read_dir_utf8()
+        .expect("read corpus directory")
+        .map(|entry| entry.expect("read corpus entry").file_name().to_owned())
+        .collect();
+    dirs.sort();
+
+    for dir in dirs {
+        let expected = dir.clone();
+        let test = move |path: &Utf8Path, contents: String| -> Result<()> {
+            assert_eq!(contents.trim(), expected, "contents of {path}");
+            Ok(())
+        };
+        harness = harness.group(
+            TestGroup::new(
+                format!("corpus::{dir}"),
+                test_fn!(test),
+                format!("{CORPUS}/{dir}"),
+            )
+            .pattern(r"\.txt$"),
+        );
+    }
+
+    // Used by the integration tests to check that failures are reflected in
+    // the exit code.
+    if std::env::var_os("__DATATEST_BUILDER_FAIL").is_some() {
+        harness = harness.group(TestGroup::new(
+            "always_fails",
+            test_fn!(always_fails),
+            CORPUS,
+        ));
+    }
+
+    // Used by the integration tests to check that a group without test cases
+    // is reported as an error.
+    if std::env::var_os("__DATATEST_BUILDER_EMPTY").is_some() {
+        harness = harness.group(
+            TestGroup::new("empty", test_fn!(always_fails), CORPUS).pattern(r"\.regression$"),
+        );
+    }
+
+    harness.run()
+}
+
+fn always_fails(_path: &Utf8Path) -> Result<()> {
+    Err("this test always fails".into())
+}
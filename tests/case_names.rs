@@ -0,0 +1,112 @@
+// Copyright (c) The datatest-stable Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use datatest_stable::{Result, Utf8Path};
+
+fn test_stem(path: &Utf8Path, input: String, expected: String) -> Result<()> {
+    assert_eq!(path.extension(), Some("in"));
+    assert!(!input.is_empty() && !expected.is_empty());
+    Ok(())
+}
+
+fn test_stripped(path: &Utf8Path, contents: String) -> Result<()> {
+    assert!(!contents.is_empty(), "{path} is not empty");
+    Ok(())
+}
+
+fn test_custom(path: &Utf8Path) -> Result<()> {
+    assert!(path.is_dir(), "{path} is a directory");
+    Ok(())
+}
+
+fn test_captures(path: &Utf8Path, input: String, expected: String) -> Result<()> {
+    assert!(path.ends_with("nested/sub.in"), "{path}");
+    assert!(!input.is_empty() && !expected.is_empty());
+    Ok(())
+}
+
+#[cfg(feature = "include-dir")]
+fn test_included_captures(context: &datatest_stable::TestContext, input: Vec<u8>) -> Result<()> {
+    assert_eq!(context.root(), None);
+    assert_eq!(context.capture("dir"), Some("dir"));
+    assert_eq!(context.capture("name"), Some("a"));
+    assert!(!input.is_empty());
+    Ok(())
+}
+
+#[cfg(feature = "include-dir")]
+fn test_included_stem(path: &Utf8Path, input: Vec<u8>) -> Result<()> {
+    assert_eq!(path.extension(), Some("in"));
+    assert!(!input.is_empty());
+    Ok(())
+}
+
+#[cfg(not(feature = "include-dir"))]
+datatest_stable::harness! {
+    {
+        test = test_stem,
+        root = "tests/paired",
+        glob = "**/*.in",
+        expected = "{stem}.out",
+        on_missing = skip,
+        case_name = "{stem}",
+    },
+    { test = test_stripped, root = "tests/files", glob = "**/*.txt", exclude = r"\.skip\.", strip_prefix = "dir" },
+    {
+        test = test_custom,
+        root = "tests/dir-cases",
+        unit = dir,
+        case_name = (
+            |path: &Utf8Path| path.as_str().to_uppercase(),
+            |name: &str| Some(name.to_lowercase()),
+        ),
+    },
+    {
+        test = test_captures,
+        root = "tests/paired",
+        pattern = r"^(?P<dir>\w+)/(?P<case>\w+)\.in$",
+        expected = "{dir}/{case}.out",
+        case_name = "{case}-in-{dir}",
+    },
+}
+
+#[cfg(feature = "include-dir")]
+datatest_stable::harness! {
+    {
+        test = test_stem,
+        root = "tests/paired",
+        glob = "**/*.in",
+        expected = "{stem}.out",
+        on_missing = skip,
+        case_name = "{stem}",
+    },
+    { test = test_stripped, root = "tests/files", glob = "**/*.txt", exclude = r"\.skip\.", strip_prefix = "dir" },
+    {
+        test = test_custom,
+        root = "tests/dir-cases",
+        unit = dir,
+        case_name = (
+            |path: &Utf8Path| path.as_str().to_uppercase(),
+            |name: &str| Some(name.to_lowercase()),
+        ),
+    },
+    {
+        test = test_included_stem,
+        root = datatest_stable::include_dir!("$CARGO_MANIFEST_DIR/tests/paired"),
+        glob = "**/*.in",
+        case_name = "case-{stem}",
+    },
+    {
+        test = test_captures,
+        root = "tests/paired",
+        pattern = r"^(?P<dir>\w+)/(?P<case>\w+)\.in$",
+        expected = "{dir}/{case}.out",
+        case_name = "{case}-in-{dir}",
+    },
+    {
+        test = test_included_captures,
+        root = datatest_stable::include_dir!("$CARGO_MANIFEST_DIR/tests/files"),
+        pattern = r"^(?P<dir>\w+)/(?P<name>\w+)\.txt$",
+        case_name = "{dir}::{name}",
+    },
+}
@@ -0,0 +1,8 @@
+fn my_test(_path: &std::path::Path) -> datatest_stable::Result<()> {
+    Ok(())
+}
+
+datatest_stable::harness! {
+    { test = my_test, root = "tests/files", glob = "**/*.{txt,md" },
+    { test = my_test, root = "tests/files", glob = r"data/\" },
+}
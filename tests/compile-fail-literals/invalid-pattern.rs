@@ -0,0 +1,7 @@
+fn my_test(_path: &std::path::Path) -> datatest_stable::Result<()> {
+    Ok(())
+}
+
+datatest_stable::harness! {
+    { test = my_test, root = "tests/files", pattern = "*.txt" },
+}
@@ -0,0 +1,11 @@
+fn my_test(_path: &std::path::Path) -> datatest_stable::Result<()> {
+    Ok(())
+}
+
+datatest_stable::harness! {
+    { test = my_test, root = "tests/files", pattern = "a{3,2}" },
+    { test = my_test, root = "tests/files", pattern = "(?=a)*" },
+    { test = my_test, root = "tests/files", pattern = "(?i)*" },
+    { test = my_test, root = "tests/files", pattern = r"\b+" },
+    { test = my_test, root = "tests/files", exclude = r"\Qa" },
+}
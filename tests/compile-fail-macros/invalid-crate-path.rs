@@ -0,0 +1,8 @@
+use datatest_stable::{Result, Utf8Path};
+
+#[datatest_stable::files(crate = "datatest_stable", root = "tests/files")]
+fn my_test(_path: &Utf8Path) -> Result<()> {
+    Ok(())
+}
+
+datatest_stable::harness! {}
@@ -0,0 +1,3 @@
+datatest_stable::harness! {
+    { test = my_test, root = "abc", pattern = r"\.txt$", exclude = "skip", pattern = r"\.md$" }
+}
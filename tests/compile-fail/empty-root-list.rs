@@ -0,0 +1,9 @@
+use datatest_stable::{Result, Utf8Path};
+
+fn my_test(_path: &Utf8Path) -> Result<()> {
+    Ok(())
+}
+
+datatest_stable::harness! {
+    { test = my_test, root = [], pattern = r"\.txt$" }
+}
@@ -0,0 +1,3 @@
+datatest_stable::harness! {
+    { test = my_test, root = "abc", glob = "*.txt", pattern = r"\.txt$" }
+}
@@ -0,0 +1,9 @@
+use datatest_stable::Utf8Path;
+
+fn make_test() -> impl Fn(&Utf8Path) -> datatest_stable::Result<()> {
+    |_path| Ok(())
+}
+
+datatest_stable::harness! {
+    { test = make_test(), root = "tests/files", pattern = r"\.txt$" },
+}
@@ -0,0 +1,57 @@
+// Copyright (c) The datatest-stable Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use datatest_stable::{Result, TestContext, Utf8Path};
+
+fn test_context(context: &TestContext) -> Result<()> {
+    assert_eq!(context.group_name(), "test_context");
+    let root = context.root().expect("root is a directory on disk");
+    assert_eq!(root, "tests/files");
+    assert_eq!(context.test_path(), root.join(context.rel_path()));
+    assert_eq!(context.disk_path(), Some(context.test_path()));
+    assert_eq!(context.section_name(), None);
+
+    // Named groups in the pattern are captured.
+    let rel_path = context.rel_path();
+    assert_eq!(context.capture("stem"), rel_path.file_stem());
+    let dir = rel_path.parent().filter(|dir| !dir.as_str().is_empty());
+    assert_eq!(context.capture("dir"), dir.map(Utf8Path::as_str));
+    assert_eq!(context.capture("missing"), None);
+    let names: Vec<_> = context.captures().map(|(name, _)| name).collect();
+    match dir {
+        Some(_) => assert_eq!(names, ["dir", "stem"]),
+        None => assert_eq!(names, ["stem"]),
+    }
+    Ok(())
+}
+
+fn test_context_string(context: &TestContext, contents: String) -> Result<()> {
+    assert_eq!(contents, std::fs::read_to_string(context.test_path())?);
+
+    // The scratch directory starts out empty, and is the same for the whole
+    // test case.
+    let temp_dir = context.temp_dir();
+    assert_eq!(temp_dir.read_dir_utf8()?.count(), 0);
+    let copy = temp_dir.join(context.rel_path().file_name().unwrap());
+    std::fs::write(&copy, &contents)?;
+    assert_eq!(context.temp_dir(), temp_dir);
+    assert_eq!(std::fs::read_to_string(copy)?, contents);
+    Ok(())
+}
+
+fn test_context_bytes(context: &TestContext, contents: Vec<u8>) -> Result<()> {
+    assert_eq!(context.group_name(), "bytes");
+    assert_eq!(context.captures().count(), 0, "no named groups");
+    assert_eq!(contents, std::fs::read(context.test_path())?);
+    Ok(())
+}
+
+datatest_stable::harness! {
+    {
+        test = test_context,
+        root = "tests/files",
+        pattern = r"^(?:(?P<dir>\w+)/)?(?P<stem>\w+)\.txt$",
+    },
+    { test = test_context_string, root = "tests/files", pattern = r"\.txt$" },
+    { test = test_context_bytes, root = "tests/files", pattern = r"\.json$", name = "bytes" },
+}
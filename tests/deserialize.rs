@@ -0,0 +1,58 @@
+// Copyright (c) The datatest-stable Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use datatest_stable::{Result, Utf8Path};
+use serde::Deserialize;
+use std::path::Path;
+
+#[derive(Debug, Deserialize)]
+struct Point {
+    name: String,
+    x: i64,
+    y: i64,
+}
+
+fn test_point(path: &Utf8Path, point: Point) -> Result<()> {
+    assert!(!point.name.is_empty(), "name must not be empty: {path}");
+    assert!(
+        point.x >= 0 && point.y >= 0,
+        "coordinates must not be negative: {point:?}"
+    );
+    Ok(())
+}
+
+fn test_point_path(path: &Path, point: Point) -> Result<()> {
+    assert_eq!(
+        path.file_name().and_then(|name| name.to_str()),
+        Some("point-json.txt")
+    );
+    assert_eq!(point.name, "plain-text");
+    Ok(())
+}
+
+// Functions that accept strings continue to be passed the raw contents, even
+// though `String` implements `Deserialize`.
+fn test_raw(_path: &Utf8Path, contents: String) -> Result<()> {
+    assert!(contents.contains("name"), "contents are unparsed");
+    Ok(())
+}
+
+datatest_stable::harness! {
+    {
+        test = test_point,
+        root = "tests/deserialize",
+        // The format is determined by the file extension.
+        pattern = r"\.(json|toml|ya?ml)$",
+    },
+    {
+        test = test_point_path,
+        root = "tests/deserialize",
+        pattern = r"\.txt$",
+        format = json,
+    },
+    {
+        test = test_raw,
+        root = "tests/deserialize",
+        pattern = r"\.toml$",
+    },
+}
@@ -0,0 +1,54 @@
+// Copyright (c) The datatest-stable Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use datatest_stable::{Result, Utf8Path};
+
+/// Applies the transformation named in `args` to `input`.
+fn transform(args: &str, input: &str) -> Result<String> {
+    match args.trim() {
+        "upper" => Ok(input.to_uppercase()),
+        "lower" => Ok(input.to_lowercase()),
+        other => Err(format!("unknown transformation: {other}").into()),
+    }
+}
+
+fn test_dir(path: &Utf8Path) -> Result<()> {
+    assert!(path.is_dir(), "{path} is a directory");
+    let read = |name: &str| std::fs::read_to_string(path.join(name));
+
+    let actual = transform(&read("args")?, &read("input.txt")?)?;
+    assert_eq!(actual, read("expected.txt")?, "output for {path}");
+    Ok(())
+}
+
+#[cfg(feature = "include-dir")]
+fn test_included_dir(path: &Utf8Path, dir: &include_dir::Dir<'_>) -> Result<()> {
+    assert_eq!(dir.path(), path.as_std_path());
+    // Paths within an included directory are relative to the root of the
+    // included directory, not to `dir`.
+    let read = |name: &str| {
+        dir.get_file(path.join(name))
+            .and_then(|file| file.contents_utf8())
+            .ok_or_else(|| format!("{path}/{name} not found"))
+    };
+
+    let actual = transform(read("args")?, read("input.txt")?)?;
+    assert_eq!(actual, read("expected.txt")?, "output for {path}");
+    Ok(())
+}
+
+#[cfg(not(feature = "include-dir"))]
+datatest_stable::harness! {
+    { test = test_dir, root = "tests/dir-cases", unit = dir },
+}
+
+#[cfg(feature = "include-dir")]
+datatest_stable::harness! {
+    { test = test_dir, root = "tests/dir-cases", unit = dir },
+    {
+        test = test_included_dir,
+        root = datatest_stable::include_dir!("$CARGO_MANIFEST_DIR/tests/dir-cases"),
+        pattern = r"^shout$",
+        unit = dir,
+    },
+}
@@ -0,0 +1,15 @@
+// Copyright (c) The datatest-stable Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use datatest_stable::{Result, Utf8Path};
+
+fn test_fixture(path: &Utf8Path, contents: String) -> Result<()> {
+    if contents.contains("broken") {
+        return Err(format!("{path} is broken").into());
+    }
+    Ok(())
+}
+
+datatest_stable::harness! {
+    { test = test_fixture, root = "tests/directives", directives = true },
+}
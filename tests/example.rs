@@ -2,7 +2,7 @@
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
 use camino::Utf8Path;
-use datatest_stable::Result;
+use datatest_stable::{Result, Snapshot};
 use std::{fs::File, io::Read, path::Path};
 
 fn test_artifact(path: &Path) -> Result<()> {
@@ -34,6 +34,21 @@ fn test_artifact_utf8_abs(path: &Utf8Path) -> Result<()> {
     test_artifact(path.as_ref())
 }
 
+/// Returns a test function that captures state, which is set up once per
+/// process before any tests are run.
+fn test_closure() -> impl Fn(&Utf8Path, String) -> Result<()> + Send + Sync {
+    // A stand-in for expensive shared setup, such as compiling a grammar.
+    let prefix = Utf8Path::new("tests/files").to_owned();
+    move |path, contents| {
+        assert!(path.starts_with(&prefix), "{path} starts with {prefix}");
+        compare(path.as_std_path(), contents.as_bytes())
+    }
+}
+
+fn test_snapshot(_path: &Utf8Path, contents: String, snapshot: &Snapshot) -> Result<()> {
+    snapshot.check(&contents.to_uppercase())
+}
+
 #[cfg(feature = "include-dir")]
 #[macro_use]
 mod with_contents {
@@ -120,7 +135,7 @@ mod with_contents {
             "path must start with 'tests/files': {:?}",
             path
         );
-        compare(&path, expected)
+        compare(path, expected)
     }
 }
 
@@ -164,6 +179,24 @@ datatest_stable::harness! {
         // This regex pattern skips .skip.txt files.
         pattern = r"^.*(?<!\.skip)\.txt$",
     },
+    {
+        test = test_artifact,
+        root = "tests/files",
+        // Groups that run the same function need distinct names.
+        name = "test_artifact_exclude",
+        // Options after `root`, including `pattern`, can be in any order.
+        pattern = r"\.txt$",
+        // Exclude patterns are an easier-to-read alternative to lookaround.
+        exclude = [r"\.skip\.txt$", "dir/"],
+    },
+    {
+        test = test_artifact,
+        root = "tests/files",
+        // Globs are matched against the whole path. This matches a.txt and
+        // b.txt in any directory.
+        glob = "**/[ab].txt",
+        name = "test_artifact_glob",
+    },
     {
         test = test_artifact_utf8,
         // Ensure that tests\files is normalized to tests/files on Windows.
@@ -189,6 +222,14 @@ datatest_stable::harness! {
         // crate root.
         pattern = r"^(dir/a|b|c\.skip)\.txt$",
     },
+    {
+        test = with_contents::test_artifact_string,
+        root = maybe_include_dir!(),
+        pattern = r"^b\.txt$",
+        // Replaces `with_contents::test_artifact_string` as the prefix of
+        // test names, which also keeps them distinct from the group above.
+        name = "artifact_string_b",
+    },
     {
         test = with_contents::test_artifact_utf8_string,
         // Test out some combinations with &'static include_dir::Dir.
@@ -206,4 +247,18 @@ datatest_stable::harness! {
         root = maybe_include_dir!(),
         pattern = r"^.*\.txt$",
     },
+    {
+        // Any expression that evaluates to a test function can be used, as
+        // long as the group is given a name.
+        test = test_closure(),
+        root = "tests/files",
+        pattern = r"^.*\.txt$",
+        name = "test_closure",
+    },
+    {
+        test = test_snapshot,
+        root = "tests/snapshots",
+        // Don't match the .expected files themselves.
+        pattern = r"^.*\.txt$",
+    },
 }
@@ -0,0 +1,20 @@
+// Copyright (c) The datatest-stable Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use datatest_stable::{Result, Utf8Path};
+
+fn test_not_ignored(path: &Utf8Path, contents: String) -> Result<()> {
+    if contents.contains("ignored") {
+        return Err(format!("{path} should have been ignored").into());
+    }
+    Ok(())
+}
+
+fn test_with_hidden(path: &Utf8Path, contents: String) -> Result<()> {
+    test_not_ignored(path, contents)
+}
+
+datatest_stable::harness! {
+    { test = test_not_ignored, root = "tests/ignore-files", gitignore = true },
+    { test = test_with_hidden, root = "tests/ignore-files", gitignore = true, hidden = true },
+}
@@ -10,12 +10,16 @@ static EXPECTED_LINES: &[&str] = &[
     "datatest-stable::example test_artifact_utf8_abs::c.skip.txt",
     "datatest-stable::example test_artifact::dir/a.txt",
     "datatest-stable::example test_artifact::b.txt",
+    "datatest-stable::example test_artifact_exclude::b.txt",
+    "datatest-stable::example test_artifact_glob::dir/a.txt",
+    "datatest-stable::example test_artifact_glob::b.txt",
     "datatest-stable::example with_contents::test_artifact_bytes::dir/a.txt",
     "datatest-stable::example with_contents::test_artifact_bytes::b.txt",
     "datatest-stable::example with_contents::test_artifact_bytes::c.skip.txt",
     "datatest-stable::example with_contents::test_artifact_string::dir/a.txt",
     "datatest-stable::example with_contents::test_artifact_string::b.txt",
     "datatest-stable::example with_contents::test_artifact_string::c.skip.txt",
+    "datatest-stable::example artifact_string_b::b.txt",
     "datatest-stable::example with_contents::test_artifact_utf8_bytes::dir/a.txt",
     "datatest-stable::example with_contents::test_artifact_utf8_bytes::b.txt",
     "datatest-stable::example with_contents::test_artifact_utf8_bytes::c.skip.txt",
@@ -23,6 +27,11 @@ static EXPECTED_LINES: &[&str] = &[
     "datatest-stable::example with_contents::test_artifact_utf8_string::dir/a.txt",
     "datatest-stable::example with_contents::test_artifact_utf8_string::b.txt",
     "datatest-stable::example with_contents::test_artifact_utf8_string::c.skip.txt",
+    "datatest-stable::example test_closure::dir/a.txt",
+    "datatest-stable::example test_closure::b.txt",
+    "datatest-stable::example test_closure::c.skip.txt",
+    "datatest-stable::example test_snapshot::greeting.txt",
+    "datatest-stable::example test_snapshot::nested/farewell.txt",
 ];
 
 #[test]
@@ -47,7 +56,7 @@ fn run_example() {
     for line in EXPECTED_LINES
         .iter()
         .copied()
-        .chain(std::iter::once("21 tests run: 21 passed, 0 skipped"))
+        .chain(std::iter::once("30 tests run: 30 passed, 0 skipped"))
     {
         assert!(
             stderr.contains(line),
@@ -56,12 +65,484 @@ fn run_example() {
     }
 }
 
+#[test]
+fn run_deserialize() {
+    let stderr = run_nextest(&["--test=deserialize", "--features=json,toml,yaml"]);
+
+    for line in [
+        "datatest-stable::deserialize test_point::nested/point.yaml",
+        "datatest-stable::deserialize test_point::nested/point.yml",
+        "datatest-stable::deserialize test_point::point.json",
+        "datatest-stable::deserialize test_point::point.toml",
+        "datatest-stable::deserialize test_point_path::point-json.txt",
+        "datatest-stable::deserialize test_raw::point.toml",
+        "6 tests run: 6 passed, 0 skipped",
+    ] {
+        assert!(
+            stderr.contains(line),
+            "Expected to find substring\n  {line}\nin stderr\n  {stderr}",
+        );
+    }
+}
+
+#[test]
+fn run_builder() {
+    let stderr = run_nextest(&["--test=builder"]);
+
+    for line in [
+        "datatest-stable::builder corpus::alpha::one.txt",
+        "datatest-stable::builder corpus::alpha::two.txt",
+        "datatest-stable::builder corpus::beta::nested/three.txt",
+        "datatest-stable::builder corpus::beta::one.txt",
+        "4 tests run: 4 passed, 0 skipped",
+    ] {
+        assert!(
+            stderr.contains(line),
+            "Expected to find substring\n  {line}\nin stderr\n  {stderr}",
+        );
+    }
+}
+
+#[test]
+fn run_attribute() {
+    let stderr = run_nextest(&["--test=attribute", "--features=macros"]);
+
+    for line in [
+        "datatest-stable::attribute dirs::shout",
+        "datatest-stable::attribute dirs::whisper",
+        "datatest-stable::attribute nested::test_crate_path::b.txt",
+        "datatest-stable::attribute nested::test_json::other.json",
+        "datatest-stable::attribute nested::test_roots::local::a.txt",
+        "datatest-stable::attribute nested::test_roots::spec::a.txt",
+        "datatest-stable::attribute test_harness::b.txt",
+        "datatest-stable::attribute test_txt::b.txt",
+        "datatest-stable::attribute test_txt::dir/a.txt",
+        "9 tests run: 9 passed, 0 skipped",
+    ] {
+        assert!(
+            stderr.contains(line),
+            "Expected to find substring\n  {line}\nin stderr\n  {stderr}",
+        );
+    }
+}
+
+#[test]
+fn run_async_fns() {
+    let stderr = run_nextest(&["--test=async_fns"]);
+
+    for line in [
+        "datatest-stable::async_fns test_bytes::b.txt",
+        "datatest-stable::async_fns test_path::dir/a.txt",
+        "datatest-stable::async_fns test_string::c.skip.txt",
+        "9 tests run: 9 passed, 0 skipped",
+    ] {
+        assert!(
+            stderr.contains(line),
+            "Expected to find substring\n  {line}\nin stderr\n  {stderr}",
+        );
+    }
+}
+
+#[test]
+fn run_async_tokio() {
+    let stderr = run_nextest(&["--test=async_tokio", "--features=tokio"]);
+
+    for line in [
+        "datatest-stable::async_tokio test_echo::b.txt",
+        "datatest-stable::async_tokio test_echo::dir/a.txt",
+        "3 tests run: 3 passed, 0 skipped",
+    ] {
+        assert!(
+            stderr.contains(line),
+            "Expected to find substring\n  {line}\nin stderr\n  {stderr}",
+        );
+    }
+}
+
+#[test]
+fn run_dir_cases() {
+    let stderr = run_nextest(&["--test=dir_cases", "--features=include-dir"]);
+
+    for line in [
+        "datatest-stable::dir_cases test_dir::shout",
+        "datatest-stable::dir_cases test_dir::whisper",
+        "datatest-stable::dir_cases test_included_dir::shout",
+        "3 tests run: 3 passed, 0 skipped",
+    ] {
+        assert!(
+            stderr.contains(line),
+            "Expected to find substring\n  {line}\nin stderr\n  {stderr}",
+        );
+    }
+}
+
+#[test]
+fn run_archives() {
+    let stderr = run_nextest(&["--test=archives", "--features=archive-tar,archive-zip"]);
+
+    for line in [
+        "datatest-stable::archives tar::a.txt",
+        "datatest-stable::archives tar::nested/b.txt",
+        "datatest-stable::archives zip::a.txt",
+        "datatest-stable::archives zip::nested/b.txt",
+        "datatest-stable::archives test_paired::nested/c.in",
+        "5 tests run: 5 passed, 0 skipped",
+    ] {
+        assert!(
+            stderr.contains(line),
+            "Expected to find substring\n  {line}\nin stderr\n  {stderr}",
+        );
+    }
+}
+
+#[test]
+fn run_multiple_roots() {
+    let stderr = run_nextest(&["--test=multiple_roots"]);
+
+    for line in [
+        "datatest-stable::multiple_roots test_roots::local::a.txt",
+        "datatest-stable::multiple_roots test_roots::local::b.txt",
+        "datatest-stable::multiple_roots test_roots::spec-tests::a.txt",
+        "datatest-stable::multiple_roots test_roots::spec-tests::nested/c.txt",
+        "datatest-stable::multiple_roots test_labelled::local::a.txt",
+        "datatest-stable::multiple_roots test_labelled::local::b.txt",
+        "datatest-stable::multiple_roots test_labelled::spec::a.txt",
+        "7 tests run: 7 passed, 0 skipped",
+    ] {
+        assert!(
+            stderr.contains(line),
+            "Expected to find substring\n  {line}\nin stderr\n  {stderr}",
+        );
+    }
+}
+
+#[test]
+fn run_root_env() {
+    let stderr = run_nextest(&["--test=root_env"]);
+
+    for line in [
+        "datatest-stable::root_env test_corpus::a.txt",
+        "datatest-stable::root_env test_corpus::b.txt",
+        "2 tests run: 2 passed, 0 skipped",
+    ] {
+        assert!(
+            stderr.contains(line),
+            "Expected to find substring\n  {line}\nin stderr\n  {stderr}",
+        );
+    }
+
+    // The environment variable replaces the root.
+    let stderr = run_nextest_with_env(
+        &["--test=root_env"],
+        &[("__DATATEST_CORPUS", "tests/multiple-roots/spec-tests")],
+    );
+
+    for line in [
+        "datatest-stable::root_env test_corpus::a.txt",
+        "datatest-stable::root_env test_corpus::nested/c.txt",
+        "2 tests run: 2 passed, 0 skipped",
+    ] {
+        assert!(
+            stderr.contains(line),
+            "Expected to find substring\n  {line}\nin stderr\n  {stderr}",
+        );
+    }
+}
+
+#[test]
+fn run_optional() {
+    let stderr = run_nextest(&["--test=optional"]);
+
+    for line in [
+        "datatest-stable::optional test_corpus::(missing root)",
+        "datatest-stable::optional partial::local::a.txt",
+        "datatest-stable::optional partial::local::b.txt",
+        "datatest-stable::optional partial::proprietary::(missing root)",
+        "4 tests run: 4 passed, 0 skipped",
+    ] {
+        assert!(
+            stderr.contains(line),
+            "Expected to find substring\n  {line}\nin stderr\n  {stderr}",
+        );
+    }
+
+    // Outside nextest, the reason the placeholder tests are ignored is shown.
+    let output = std::process::Command::new(cargo_bin())
+        .args(["test", "--test=optional", "--color=never"])
+        .output()
+        .expect("`cargo test` ran");
+
+    let stdout = std::str::from_utf8(&output.stdout).expect("cargo test stdout should be utf-8");
+
+    assert!(
+        output.status.success(),
+        "cargo test should succeed (exit status: {}, stdout: {stdout})",
+        output.status
+    );
+    assert!(
+        stdout.contains(
+            "ignored (directory: `tests/missing-corpus` not found, and 'test_corpus' is \
+             optional)"
+        ),
+        "Expected ignored placeholder in stdout\n  {stdout}",
+    );
+}
+
+#[test]
+fn run_sections() {
+    let stderr = run_nextest(&["--test=sections"]);
+
+    for line in [
+        "datatest-stable::sections test_arithmetic::arithmetic.txt::addition",
+        "datatest-stable::sections test_arithmetic::arithmetic.txt::multiple lines",
+        "datatest-stable::sections test_arithmetic::arithmetic.txt::subtraction",
+        "datatest-stable::sections test_snapshot::nested/greetings.txt::goodbye",
+        "datatest-stable::sections test_snapshot::nested/greetings.txt::hello",
+        "5 tests run: 5 passed, 0 skipped",
+    ] {
+        assert!(
+            stderr.contains(line),
+            "Expected to find substring\n  {line}\nin stderr\n  {stderr}",
+        );
+    }
+}
+
+#[test]
+fn run_directives() {
+    let stderr = run_nextest(&["--test=directives", "--features=toml"]);
+
+    for line in [
+        "datatest-stable::directives test_fixture::fails.txt",
+        "datatest-stable::directives test_fixture::pass.txt",
+        "datatest-stable::directives test_fixture::sidecar.txt",
+        "3 tests run: 3 passed, 1 skipped",
+    ] {
+        assert!(
+            stderr.contains(line),
+            "Expected to find substring\n  {line}\nin stderr\n  {stderr}",
+        );
+    }
+}
+
+#[test]
+fn run_paired() {
+    let stderr = run_nextest(&["--test=paired", "--features=include-dir"]);
+
+    for line in [
+        "datatest-stable::paired test_paired::add.in",
+        "datatest-stable::paired test_paired::nested/sub.in",
+        "datatest-stable::paired test_maybe_paired::missing.in",
+        "datatest-stable::paired test_included_paired::nested/sub.in",
+        "7 tests run: 7 passed, 0 skipped",
+    ] {
+        assert!(
+            stderr.contains(line),
+            "Expected to find substring\n  {line}\nin stderr\n  {stderr}",
+        );
+    }
+}
+
+#[test]
+fn run_ignore_files() {
+    let stderr = run_nextest(&["--test=ignore_files"]);
+
+    for line in [
+        "datatest-stable::ignore_files test_not_ignored::a.txt",
+        "datatest-stable::ignore_files test_not_ignored::nested/keep.orig",
+        "datatest-stable::ignore_files test_with_hidden::.hidden.txt",
+        "5 tests run: 5 passed, 0 skipped",
+    ] {
+        assert!(
+            stderr.contains(line),
+            "Expected to find substring\n  {line}\nin stderr\n  {stderr}",
+        );
+    }
+}
+
+#[test]
+fn run_case_names() {
+    let stderr = run_nextest(&["--test=case_names", "--features=include-dir"]);
+
+    for line in [
+        "datatest-stable::case_names test_stem::add",
+        "datatest-stable::case_names test_stem::nested/sub",
+        "datatest-stable::case_names test_stripped::a.txt",
+        "datatest-stable::case_names test_stripped::b.txt",
+        "datatest-stable::case_names test_custom::SHOUT",
+        "datatest-stable::case_names test_included_stem::case-nested/sub",
+        "datatest-stable::case_names test_captures::sub-in-nested",
+        "datatest-stable::case_names test_included_captures::dir::a",
+        "11 tests run: 11 passed, 0 skipped",
+    ] {
+        assert!(
+            stderr.contains(line),
+            "Expected to find substring\n  {line}\nin stderr\n  {stderr}",
+        );
+    }
+}
+
+#[test]
+fn run_orphans() {
+    let output = std::process::Command::new(cargo_bin())
+        .args(["test", "--test=orphans", "--color=never"])
+        .output()
+        .expect("`cargo test` ran");
+
+    let stderr = std::str::from_utf8(&output.stderr).expect("cargo test stderr should be utf-8");
+
+    assert!(
+        output.status.success(),
+        "cargo test should succeed (exit status: {}, stderr: {stderr})",
+        output.status
+    );
+    assert!(
+        stderr.contains(
+            "warning: 1 fixture file was not matched by any test group:\n  \
+             in directory: `tests/orphans`:\n    stray.json\n"
+        ),
+        "Expected orphan warning in stderr\n  {stderr}",
+    );
+}
+
+#[test]
+fn failing_test_exit_code() {
+    let output = std::process::Command::new(cargo_bin())
+        .args(["test", "--test=builder", "--color=never"])
+        .env("__DATATEST_BUILDER_FAIL", "1")
+        .output()
+        .expect("`cargo test` ran");
+
+    let stdout = std::str::from_utf8(&output.stdout).expect("cargo test stdout should be utf-8");
+
+    assert!(
+        !output.status.success(),
+        "cargo test should fail (exit status: {}, stdout: {stdout})",
+        output.status
+    );
+    assert!(
+        stdout.contains("---- always_fails::alpha/one.txt ----"),
+        "Expected failure in stdout\n  {stdout}",
+    );
+}
+
+#[test]
+fn empty_group_exit_code() {
+    let output = std::process::Command::new(cargo_bin())
+        .args(["test", "--test=builder", "--color=never"])
+        .env("__DATATEST_BUILDER_EMPTY", "1")
+        .output()
+        .expect("`cargo test` ran");
+
+    let stderr = std::str::from_utf8(&output.stderr).expect("cargo test stderr should be utf-8");
+
+    assert!(
+        !output.status.success(),
+        "cargo test should fail (exit status: {}, stderr: {stderr})",
+        output.status
+    );
+    assert!(
+        stderr.contains(
+            "error: no test cases found for test 'empty' -- scanned directory: `tests/builder` \
+             with pattern '\\.regression$' -- if the group can be empty, set `allow_empty = true`"
+        ),
+        "Expected error in stderr\n  {stderr}",
+    );
+    // The error is reported without a panic.
+    assert!(
+        !stderr.contains("panicked"),
+        "Expected no panic in stderr\n  {stderr}"
+    );
+}
+
+#[test]
+fn run_context() {
+    let stderr = run_nextest(&["--test=context"]);
+
+    for line in [
+        "datatest-stable::context bytes::other.json",
+        "datatest-stable::context test_context::b.txt",
+        "datatest-stable::context test_context::dir/a.txt",
+        "datatest-stable::context test_context_string::c.skip.txt",
+        "6 tests run: 6 passed, 0 skipped",
+    ] {
+        assert!(
+            stderr.contains(line),
+            "Expected to find substring\n  {line}\nin stderr\n  {stderr}",
+        );
+    }
+}
+
+#[test]
+fn run_return_types() {
+    let stderr = run_nextest(&["--test=return_types"]);
+
+    for line in [
+        "datatest-stable::return_types test_unit::b.txt",
+        "datatest-stable::return_types test_debug_error::b.txt",
+        "datatest-stable::return_types test_async_unit::b.txt",
+        "5 tests run: 5 passed, 0 skipped",
+    ] {
+        assert!(
+            stderr.contains(line),
+            "Expected to find substring\n  {line}\nin stderr\n  {stderr}",
+        );
+    }
+}
+
+#[test]
+fn failure_messages() {
+    let output = std::process::Command::new(cargo_bin())
+        .args(["test", "--test=return_types", "--color=never"])
+        .env("__DATATEST_RETURN_TYPES_FAIL", "1")
+        .output()
+        .expect("`cargo test` ran");
+
+    let stdout = std::str::from_utf8(&output.stdout).expect("cargo test stdout should be utf-8");
+
+    assert!(
+        !output.status.success(),
+        "cargo test should fail (exit status: {}, stdout: {stdout})",
+        output.status
+    );
+    for message in [
+        // Errors that implement `Display` are reported with it, followed by
+        // their sources.
+        "---- test_error_source::b.txt ----\n\
+         error reading fixture for 'tests/files/b.txt'\n\
+         \n\
+         Caused by:\n    \
+         disk on fire\n",
+        "---- test_string_error::b.txt ----\n\
+         tests/files/b.txt failed with a string (1 lines)\n",
+        // Other errors are reported with `Debug`.
+        "---- test_debug_error::b.txt ----\n\
+         Lines { expected: 0, actual: 1 }\n",
+        "---- test_unit::b.txt ----\n\
+         test panicked: tests/files/b.txt failed with a panic\n",
+    ] {
+        assert!(
+            stdout.contains(message),
+            "Expected to find substring\n  {message}\nin stdout\n  {stdout}",
+        );
+    }
+}
+
 #[test]
 fn ui() {
     let t = trybuild::TestCases::new();
     t.compile_fail("tests/compile-fail/*.rs");
 }
 
+#[cfg(feature = "macros")]
+#[test]
+fn ui_macros() {
+    let t = trybuild::TestCases::new();
+    t.compile_fail("tests/compile-fail-macros/*.rs");
+    // Literal regexes and globs passed to `harness!` are only checked at
+    // compile time with the `macros` feature.
+    t.compile_fail("tests/compile-fail-literals/*.rs");
+}
+
 #[cfg(unix)]
 mod unix {
     use super::*;
@@ -80,6 +561,9 @@ mod unix {
         "datatest-stable::example test_artifact::::colon::dir/a.txt",
         "datatest-stable::example test_artifact::dir/a.txt",
         "datatest-stable::example test_artifact::b.txt",
+        "datatest-stable::example test_artifact_glob::::colon::dir/a.txt",
+        "datatest-stable::example test_closure::::colon::dir/::.txt",
+        "datatest-stable::example test_closure::::colon::dir/a.txt",
         "datatest-stable::example with_contents::test_artifact_bytes::::colon::dir/::.txt",
         "datatest-stable::example with_contents::test_artifact_bytes::::colon::dir/a.txt",
         "datatest-stable::example with_contents::test_artifact_bytes::dir/a.txt",
@@ -88,6 +572,7 @@ mod unix {
         "datatest-stable::example with_contents::test_artifact_string::dir/a.txt",
         "datatest-stable::example with_contents::test_artifact_string::b.txt",
         "datatest-stable::example with_contents::test_artifact_string::c.skip.txt",
+        "datatest-stable::example artifact_string_b::b.txt",
         "datatest-stable::example with_contents::test_artifact_utf8_bytes::::colon::dir/::.txt",
         "datatest-stable::example with_contents::test_artifact_utf8_bytes::::colon::dir/a.txt",
         "datatest-stable::example with_contents::test_artifact_utf8_bytes::dir/a.txt",
@@ -107,13 +592,19 @@ mod unix {
         std::fs::create_dir_all(temp_dir.path().join("tests")).expect("created dir");
         let dest = temp_dir.path().join("tests/files");
 
-        // Make a copy of tests/files inside the temp dir.
+        // Make a copy of tests/files and tests/snapshots inside the temp dir.
         fs_extra::dir::copy(
             "tests/files",
             temp_dir.path().join("tests"),
             &fs_extra::dir::CopyOptions::new(),
         )
         .expect("copied files");
+        fs_extra::dir::copy(
+            "tests/snapshots",
+            temp_dir.path().join("tests"),
+            &fs_extra::dir::CopyOptions::new(),
+        )
+        .expect("copied snapshots");
 
         // Add some files with colons in their names. (They can't be checked into the repo because
         // it needs to be cloned on Windows.)
@@ -143,7 +634,7 @@ mod unix {
             .iter()
             .chain(EXPECTED_UNIX_LINES.iter())
             .copied()
-            .chain(std::iter::once("31 tests run: 31 passed, 0 skipped"))
+            .chain(std::iter::once("43 tests run: 43 passed, 0 skipped"))
         {
             assert!(
                 stderr.contains(line),
@@ -153,6 +644,32 @@ mod unix {
     }
 }
 
+/// Runs `cargo nextest run` with the given extra arguments, asserting that it
+/// succeeds and returning its standard error.
+fn run_nextest(args: &[&str]) -> String {
+    run_nextest_with_env(args, &[])
+}
+
+fn run_nextest_with_env(args: &[&str], env: &[(&str, &str)]) -> String {
+    let output = std::process::Command::new(cargo_bin())
+        .args(["nextest", "run", "--color=never"])
+        .args(args)
+        .env("__DATATEST_FULL_SCAN_FORBIDDEN", "1")
+        .envs(env.iter().copied())
+        .output()
+        .expect("`cargo nextest` was successful");
+
+    let stderr = String::from_utf8(output.stderr).expect("cargo nextest stderr should be utf-8");
+
+    assert!(
+        output.status.success(),
+        "nextest exited with 0 (exit status: {}, stderr: {stderr})",
+        output.status
+    );
+
+    stderr
+}
+
 fn cargo_bin() -> String {
     std::env::var("CARGO").unwrap_or_else(|_| "cargo".to_string())
 }
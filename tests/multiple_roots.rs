@@ -0,0 +1,62 @@
+// Copyright (c) The datatest-stable Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use datatest_stable::{Result, TestContext, Utf8Path};
+
+fn test_roots(context: &TestContext, contents: String) -> Result<()> {
+    // Each fixture contains the name of the root it's in.
+    let root = context.root().expect("roots are directories on disk");
+    assert_eq!(root.file_name(), Some(contents.trim()));
+    assert_eq!(context.test_path(), root.join(context.rel_path()));
+    Ok(())
+}
+
+#[cfg(feature = "include-dir")]
+fn test_labelled(path: &Utf8Path, contents: String) -> Result<()> {
+    // Paths are relative to the root, for included directories as well as
+    // directories on disk.
+    assert!(path.is_relative(), "{path} is relative");
+    assert!(
+        ["local", "spec-tests"].contains(&contents.trim()),
+        "unexpected contents of {path}: {contents}"
+    );
+    Ok(())
+}
+
+#[cfg(not(feature = "include-dir"))]
+fn test_labelled(path: &Utf8Path, contents: String) -> Result<()> {
+    assert!(!contents.is_empty(), "{path} isn't empty");
+    Ok(())
+}
+
+#[cfg(feature = "include-dir")]
+macro_rules! maybe_include_dir {
+    () => {
+        datatest_stable::include_dir!("$CARGO_MANIFEST_DIR/tests/multiple-roots/local")
+    };
+}
+
+#[cfg(not(feature = "include-dir"))]
+macro_rules! maybe_include_dir {
+    () => {
+        "tests/multiple-roots/local"
+    };
+}
+
+datatest_stable::harness! {
+    {
+        // Without labels, roots are labelled with the names of their
+        // directories.
+        test = test_roots,
+        root = ["tests/multiple-roots/local", "tests/multiple-roots/spec-tests"],
+        pattern = r"\.txt$",
+    },
+    {
+        test = test_labelled,
+        root = [
+            local = maybe_include_dir!(),
+            spec = "tests/multiple-roots/spec-tests",
+        ],
+        glob = "*.txt",
+    },
+}
@@ -0,0 +1,29 @@
+// Copyright (c) The datatest-stable Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use datatest_stable::{Result, Utf8Path};
+
+fn test_corpus(path: &Utf8Path) -> Result<()> {
+    assert!(path.exists(), "{path} exists");
+    Ok(())
+}
+
+datatest_stable::harness! {
+    // The only root is missing, so there's a single placeholder test.
+    { test = test_corpus, root = "tests/missing-corpus", optional = true },
+    {
+        test = test_corpus,
+        root = [local = "tests/multiple-roots/local", proprietary = "tests/missing-corpus"],
+        pattern = r"\.txt$",
+        optional = true,
+        name = "partial",
+    },
+    // Groups that are empty when all is well can allow that.
+    {
+        test = test_corpus,
+        root = "tests/files",
+        pattern = r"\.regression$",
+        allow_empty = true,
+        name = "regressions",
+    },
+}
@@ -0,0 +1,25 @@
+// Copyright (c) The datatest-stable Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use datatest_stable::{Result, Snapshot, Utf8Path};
+
+fn test_snapshot(_path: &Utf8Path, contents: String, snapshot: &Snapshot) -> Result<()> {
+    snapshot.check(&contents.to_uppercase())
+}
+
+fn test_paired(_path: &Utf8Path, _input: String, _expected: String) -> Result<()> {
+    Ok(())
+}
+
+fn test_dir(path: &Utf8Path) -> Result<()> {
+    assert!(path.join("input.txt").exists());
+    Ok(())
+}
+
+// `stray.json` isn't matched by any group, so the harness warns about it.
+datatest_stable::harness! {
+    orphans = { action = warn, allow = [r"(^|/)README\.md$"] },
+    { test = test_snapshot, root = "tests/orphans", glob = "snapshots/*.txt" },
+    { test = test_paired, root = "tests/orphans", glob = "paired/*.in", expected = "{stem}.out" },
+    { test = test_dir, root = "tests/orphans/dirs", unit = dir },
+}
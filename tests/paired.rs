@@ -0,0 +1,89 @@
+// Copyright (c) The datatest-stable Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use datatest_stable::{Result, Utf8Path};
+
+fn evaluate(input: &str) -> Result<i64> {
+    let mut parts = input.split_whitespace();
+    let (Some(a), Some(op), Some(b), None) =
+        (parts.next(), parts.next(), parts.next(), parts.next())
+    else {
+        return Err(format!("expected `a op b`, found '{input}'").into());
+    };
+    let (a, b): (i64, i64) = (a.parse()?, b.parse()?);
+    match op {
+        "+" => Ok(a + b),
+        "-" => Ok(a - b),
+        _ => Err(format!("unknown operator '{op}'").into()),
+    }
+}
+
+fn test_paired(path: &Utf8Path, input: String, expected: String) -> Result<()> {
+    let actual = evaluate(&input)?;
+    if actual.to_string() != expected.trim() {
+        return Err(format!("{path}: expected {}, found {actual}", expected.trim()).into());
+    }
+    Ok(())
+}
+
+fn test_maybe_paired(path: &Utf8Path, input: String, expected: Option<String>) -> Result<()> {
+    match expected {
+        Some(expected) => test_paired(path, input, expected),
+        None => {
+            assert!(path.ends_with("missing.in"), "{path} has an expected file");
+            Ok(())
+        }
+    }
+}
+
+#[cfg(feature = "include-dir")]
+fn test_included_paired(path: &Utf8Path, input: Vec<u8>, expected: Vec<u8>) -> Result<()> {
+    test_paired(
+        path,
+        String::from_utf8(input)?,
+        String::from_utf8(expected)?,
+    )
+}
+
+#[cfg(not(feature = "include-dir"))]
+datatest_stable::harness! {
+    {
+        test = test_paired,
+        root = "tests/paired",
+        glob = "**/*.in",
+        expected = "{stem}.out",
+        on_missing = skip,
+    },
+    {
+        test = test_maybe_paired,
+        root = "tests/paired",
+        glob = "**/*.in",
+        expected = "{stem}.out",
+        on_missing = none,
+    },
+}
+
+#[cfg(feature = "include-dir")]
+datatest_stable::harness! {
+    {
+        test = test_paired,
+        root = "tests/paired",
+        glob = "**/*.in",
+        expected = "{stem}.out",
+        on_missing = skip,
+    },
+    {
+        test = test_maybe_paired,
+        root = "tests/paired",
+        glob = "**/*.in",
+        expected = "{stem}.out",
+        on_missing = none,
+    },
+    {
+        test = test_included_paired,
+        root = datatest_stable::include_dir!("$CARGO_MANIFEST_DIR/tests/paired"),
+        glob = "**/*.in",
+        expected = "{stem}.out",
+        on_missing = skip,
+    },
+}
@@ -0,0 +1,83 @@
+// Copyright (c) The datatest-stable Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use datatest_stable::Utf8Path;
+use std::{fmt, io::BufRead, path::Path};
+
+/// Used by the integration tests to check how failures are reported.
+fn should_fail() -> bool {
+    std::env::var_os("__DATATEST_RETURN_TYPES_FAIL").is_some()
+}
+
+fn test_unit(path: &Path) {
+    assert!(!should_fail(), "{} failed with a panic", path.display());
+}
+
+fn test_string_error(path: &Utf8Path, contents: String) -> Result<(), String> {
+    if should_fail() {
+        return Err(format!(
+            "{path} failed with a string ({} lines)",
+            contents.lines().count()
+        ));
+    }
+    Ok(())
+}
+
+/// An error that only implements `Debug`.
+#[derive(Debug)]
+// The fields are only read by the `Debug` impl.
+#[allow(dead_code)]
+enum Mismatch {
+    Lines { expected: usize, actual: usize },
+}
+
+fn test_debug_error(_path: &Utf8Path, contents: Vec<u8>) -> Result<(), Mismatch> {
+    if should_fail() {
+        return Err(Mismatch::Lines {
+            expected: 0,
+            actual: contents.lines().count(),
+        });
+    }
+    Ok(())
+}
+
+/// An error with a source.
+#[derive(Debug)]
+struct ReadError {
+    path: String,
+    source: std::io::Error,
+}
+
+impl fmt::Display for ReadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "error reading fixture for '{}'", self.path)
+    }
+}
+
+impl std::error::Error for ReadError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.source)
+    }
+}
+
+fn test_error_source(path: &Utf8Path) -> Result<(), ReadError> {
+    if should_fail() {
+        return Err(ReadError {
+            path: path.to_string(),
+            source: std::io::Error::new(std::io::ErrorKind::Other, "disk on fire"),
+        });
+    }
+    Ok(())
+}
+
+async fn test_async_unit(path: &Utf8Path) {
+    assert!(path.exists(), "{path} exists");
+}
+
+datatest_stable::harness! {
+    { test = test_unit, root = "tests/files", pattern = r"^b\.txt$" },
+    { test = test_string_error, root = "tests/files", pattern = r"^b\.txt$" },
+    { test = test_debug_error, root = "tests/files", pattern = r"^b\.txt$" },
+    { test = test_error_source, root = "tests/files", pattern = r"^b\.txt$" },
+    { test = test_async_unit, root = "tests/files", pattern = r"^b\.txt$" },
+}
@@ -0,0 +1,25 @@
+// Copyright (c) The datatest-stable Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use datatest_stable::{Result, TestContext};
+
+fn test_corpus(context: &TestContext, contents: String) -> Result<()> {
+    // Each fixture contains the name of the root it's in, which is either the
+    // compiled-in root or the one in the environment variable.
+    let root = context.root().expect("root is a directory on disk");
+    assert_eq!(root.file_name(), Some(contents.trim()));
+    match std::env::var("__DATATEST_CORPUS") {
+        Ok(corpus) => assert_eq!(root, corpus),
+        Err(_) => assert_eq!(root, "tests/multiple-roots/local"),
+    }
+    Ok(())
+}
+
+datatest_stable::harness! {
+    {
+        test = test_corpus,
+        root = "tests/multiple-roots/local",
+        pattern = r"\.txt$",
+        root_env = "__DATATEST_CORPUS",
+    },
+}
@@ -0,0 +1,34 @@
+// Copyright (c) The datatest-stable Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use datatest_stable::{Result, Snapshot, Utf8Path};
+
+/// Checks that every line in the section is a correct equation of the form
+/// `a op b = c`.
+fn test_arithmetic(path: &Utf8Path, contents: String) -> Result<()> {
+    assert!(!contents.is_empty(), "section in {path} is not empty");
+    for line in contents.lines() {
+        let parts: Vec<_> = line.split_whitespace().collect();
+        let [a, op, b, "=", c] = parts[..] else {
+            return Err(format!("malformed line in {path}: {line}").into());
+        };
+        let (a, b, c): (i64, i64, i64) = (a.parse()?, b.parse()?, c.parse()?);
+        let actual = match op {
+            "+" => a + b,
+            "-" => a - b,
+            "*" => a * b,
+            _ => return Err(format!("unknown operator in {path}: {op}").into()),
+        };
+        assert_eq!(actual, c, "{line}");
+    }
+    Ok(())
+}
+
+fn test_snapshot(_path: &Utf8Path, contents: String, snapshot: &Snapshot) -> Result<()> {
+    snapshot.check(&contents.to_uppercase())
+}
+
+datatest_stable::harness! {
+    { test = test_arithmetic, root = "tests/sections", pattern = r"^arithmetic\.txt$", sections = true },
+    { test = test_snapshot, root = "tests/sections", pattern = r"^nested/.*\.txt$", sections = true },
+}